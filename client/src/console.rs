@@ -0,0 +1,86 @@
+use std::{ sync::mpsc::{ channel, Receiver }, thread::spawn, io::BufRead };
+
+use log::warn;
+use shared::{
+    block::property::EnumProperty,
+    item::inventory::ClickKind,
+    util::{ block_pos::BlockPos, direction::Direction },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Inspect(BlockPos),
+    Entities,
+    SelectHotbarSlot(u8),
+    Click(usize, ClickKind),
+    Dig(BlockPos),
+    AbortDigging,
+    Place(BlockPos, Direction),
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut args = line.split_whitespace();
+
+        let command = match args.next()? {
+            "inspect" => Self::Inspect(parse_block_pos(&mut args)?),
+            "entities" => Self::Entities,
+            "select" => Self::SelectHotbarSlot(args.next()?.parse().ok()?),
+            "click" => {
+                let slot = args.next()?.parse().ok()?;
+                let kind = match args.next()? {
+                    "left" => ClickKind::Left,
+                    "right" => ClickKind::Right,
+                    "quick" => ClickKind::QuickMove,
+                    _ => return None,
+                };
+
+                Self::Click(slot, kind)
+            }
+            "dig" => Self::Dig(parse_block_pos(&mut args)?),
+            "abort" => Self::AbortDigging,
+            "place" => {
+                let pos = parse_block_pos(&mut args)?;
+                let face = args.next()?;
+
+                Self::Place(pos, Direction::VALUES.iter().find(|direction| direction.name() == face).copied()?)
+            }
+            _ => return None,
+        };
+
+        //Trailing arguments most likely mean a typo, better not to guess what was meant
+        args.next().is_none().then_some(command)
+    }
+}
+
+fn parse_block_pos<'a>(args: &mut impl Iterator<Item = &'a str>) -> Option<BlockPos> {
+    let x = args.next()?.parse().ok()?;
+    let y = args.next()?.parse().ok()?;
+    let z = args.next()?.parse().ok()?;
+
+    Some(BlockPos::new(x, y, z))
+}
+
+/// Forwards every command typed into the client's terminal.
+pub fn read_stdin() -> Receiver<ConsoleCommand> {
+    let (send, receive) = channel();
+
+    spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            match ConsoleCommand::parse(&line) {
+                Some(command) => {
+                    if send.send(command).is_err() {
+                        break;
+                    }
+                }
+                None => warn!("Unknown command: {}", line.trim()),
+            }
+        }
+    });
+
+    receive
+}
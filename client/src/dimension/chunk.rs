@@ -1,17 +1,30 @@
 use anyhow::anyhow;
+use glam::{ Vec2, Vec3 };
+use log::error;
 use metrohash::MetroHashMap;
-use once_cell::sync::Lazy;
 pub use shared::dimension::chunk::*;
-use shared::{util::chunk_pos::ChunkPos, dimension::storage::ChunkStorage};
+use shared::{
+    block::{ registry::BlockRegistry, palette::{ BlockPalette, BlockRemapper }, BlockId },
+    tag::Compound,
+    util::{ chunk_pos::ChunkPos, block_pos::BlockPos },
+    dimension::{ storage::ChunkStorage, id::DimensionId, settings::DimensionSettings },
+    entity::{ id::EntityId, movement::EntityMovement, spawn::EntitySpawn },
+};
 use tokio::sync::mpsc::{ UnboundedSender };
 
+use crate::entity::ClientEntities;
+
 pub struct ClientWorldStorage {
+    dimension: DimensionId,
+    settings: DimensionSettings,
     chunk_map: MetroHashMap<u64, Chunk>,
+    block_entities: MetroHashMap<BlockPos, Compound>, //What the server sent about each block entity
+    entities: ClientEntities,
+    empty_chunk: Chunk,
     request_chunks: UnboundedSender<ChunkPos>,
+    remapper: Option<BlockRemapper>, //Only set if the server numbers its blocks differently
 }
 
-static EMPTY_CHUNK: Lazy<Chunk> = Lazy::new(Chunk::empty);
-
 pub enum ClientChunkStorageError {
     RequestChannelClosed,
 }
@@ -41,20 +54,141 @@ impl ChunkStorage for ClientWorldStorage {
             self.request_chunks
                 .send(pos.clone())
                 .or(Err(anyhow!(ClientChunkStorageError::RequestChannelClosed)))?;
-            Ok(&EMPTY_CHUNK)
+            Ok(&self.empty_chunk)
         }
     }
 }
 
 impl ClientWorldStorage {
-    fn receive_chunk(&mut self, pos: &ChunkPos, chunk: Chunk) {
-        self.chunk_map.insert(pos.as_long(), chunk);
+    pub fn receive_chunk(&mut self, dimension: DimensionId, pos: &ChunkPos, mut chunk: Chunk) {
+        //Chunks that were already on their way when the dimension changed are stale
+        if dimension == self.dimension {
+            if let Some(remapper) = &self.remapper {
+                chunk.remap(remapper);
+            }
+
+            self.chunk_map.insert(pos.as_long(), chunk);
+        }
+    }
+
+    /// Called with the palette the server sends at login; everything received afterwards is translated to local ids.
+    pub fn receive_palette(&mut self, palette: &BlockPalette) -> anyhow::Result<()> {
+        let registry = BlockRegistry::global()?;
+
+        self.remapper = if palette == registry.palette() {
+            None
+        } else {
+            let remapper = BlockRemapper::new(palette, registry);
+
+            for name in remapper.missing() {
+                error!("The server uses block {} which doesn't exist on this client", name);
+            }
+
+            Some(remapper)
+        };
+
+        Ok(())
+    }
+
+    /// Applies a single block the server changed. Updates for chunks that aren't loaded are dropped,
+    /// the chunk will come with the change once it's requested.
+    pub fn receive_block_update(
+        &mut self,
+        dimension: DimensionId,
+        pos: BlockPos,
+        block: BlockId
+    ) -> anyhow::Result<()> {
+        if dimension != self.dimension {
+            return Ok(());
+        }
+
+        let block = self.remap_block(block);
+
+        match self.chunk_map.get_mut(&pos.get_chunk().as_long()) {
+            Some(chunk) => chunk.set_block(pos, block),
+            None => Ok(()),
+        }
+    }
+
+    /// Stores what the server lets clients see of a block entity. Empty data means it was removed.
+    pub fn receive_block_entity_data(&mut self, dimension: DimensionId, pos: BlockPos, data: Compound) {
+        if dimension != self.dimension {
+            return;
+        }
+
+        if data.is_empty() {
+            self.block_entities.remove(&pos);
+        } else {
+            self.block_entities.insert(pos, data);
+        }
     }
 
-    fn new(request_chunks: UnboundedSender<ChunkPos>) -> Self {
+    pub fn block_entity_data(&self, pos: &BlockPos) -> Option<&Compound> {
+        self.block_entities.get(pos)
+    }
+
+    pub fn receive_entity_spawn(&mut self, dimension: DimensionId, spawn: EntitySpawn) {
+        if dimension == self.dimension {
+            self.entities.spawn(spawn);
+        }
+    }
+
+    pub fn receive_entity_move(&mut self, dimension: DimensionId, id: EntityId, movement: EntityMovement) {
+        if dimension == self.dimension {
+            self.entities.apply_movement(id, movement);
+        }
+    }
+
+    pub fn receive_entity_look(&mut self, dimension: DimensionId, id: EntityId, rotation: Vec2) {
+        if dimension == self.dimension {
+            self.entities.set_rotation(id, rotation);
+        }
+    }
+
+    pub fn receive_entity_velocity(&mut self, dimension: DimensionId, id: EntityId, velocity: Vec3) {
+        if dimension == self.dimension {
+            self.entities.set_velocity(id, velocity);
+        }
+    }
+
+    pub fn receive_entity_despawn(&mut self, dimension: DimensionId, ids: &[EntityId]) {
+        if dimension == self.dimension {
+            self.entities.remove(ids);
+        }
+    }
+
+    pub fn entities(&self) -> &ClientEntities {
+        &self.entities
+    }
+
+    /// Translates a block id sent by the server, e.g. in a block update.
+    fn remap_block(&self, id: BlockId) -> BlockId {
+        self.remapper.as_ref().map_or(id, |remapper| remapper.remap(id))
+    }
+
+    pub fn change_dimension(&mut self, dimension: DimensionId, settings: DimensionSettings) {
+        self.dimension = dimension;
+        self.settings = settings;
+        self.chunk_map.clear();
+        self.block_entities.clear();
+        self.entities.clear();
+        self.empty_chunk = Chunk::empty(settings.height);
+    }
+
+    pub fn new(
+        dimension: DimensionId,
+        settings: DimensionSettings,
+        request_chunks: UnboundedSender<ChunkPos>
+    ) -> Self {
         Self {
+            dimension,
+            settings,
             chunk_map: MetroHashMap::default(),
+            block_entities: MetroHashMap::default(),
+            entities: ClientEntities::default(),
+            empty_chunk: Chunk::empty(settings.height),
             request_chunks,
+            remapper: None,
         }
     }
-}
\ No newline at end of file
+}
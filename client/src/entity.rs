@@ -0,0 +1,64 @@
+use glam::{ Vec2, Vec3 };
+use metrohash::MetroHashMap;
+use shared::entity::{ id::EntityId, movement::EntityMovement, spawn::EntitySpawn };
+
+/// An entity as far as the server told us about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientEntity {
+    pub kind: String,
+    pub pos: Vec3,
+    pub velocity: Vec3,
+    pub rotation: Vec2,
+}
+
+/// The entities the server currently sends us. Packets about entities we don't know are dropped, they might
+/// belong to a dimension we just left.
+#[derive(Debug, Default)]
+pub struct ClientEntities {
+    entities: MetroHashMap<EntityId, ClientEntity>,
+}
+
+impl ClientEntities {
+    pub fn spawn(&mut self, spawn: EntitySpawn) {
+        let entity = ClientEntity {
+            kind: spawn.kind,
+            pos: spawn.pos,
+            velocity: spawn.velocity,
+            rotation: spawn.rotation,
+        };
+
+        self.entities.insert(spawn.id, entity);
+    }
+
+    pub fn apply_movement(&mut self, id: EntityId, movement: EntityMovement) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.pos = movement.apply(entity.pos);
+        }
+    }
+
+    pub fn set_rotation(&mut self, id: EntityId, rotation: Vec2) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.rotation = rotation;
+        }
+    }
+
+    pub fn set_velocity(&mut self, id: EntityId, velocity: Vec3) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.velocity = velocity;
+        }
+    }
+
+    pub fn remove(&mut self, ids: &[EntityId]) {
+        for id in ids {
+            self.entities.remove(id);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&EntityId, &ClientEntity)> {
+        self.entities.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+}
@@ -0,0 +1,224 @@
+use std::{ time::{ Duration, Instant }, thread::sleep, sync::mpsc::Receiver };
+
+use glam::Vec3;
+use log::{ info, warn, error };
+use metrohash::MetroHashSet;
+use shared::{
+    dimension::{ id::DimensionId, settings::DimensionSettings, storage::ChunkStorage },
+    entity::physics,
+    net::{ packet::{ Packet, PacketDirection }, packet_data::PacketData, NetworkHandler },
+    util::chunk_pos::ChunkPos,
+};
+use tokio::sync::mpsc::{ unbounded_channel, UnboundedReceiver };
+
+use crate::{
+    console::ConsoleCommand,
+    dimension::chunk::ClientWorldStorage,
+    player::LocalPlayer,
+    net::ClientNetworkHandler,
+};
+
+/// Everything the client knows, kept up to date with the server and ticked at the same rate it is.
+pub struct Game {
+    net_handler: ClientNetworkHandler,
+    world: ClientWorldStorage,
+    player: LocalPlayer,
+    spawned: bool, //Set once the server put the player somewhere, it doesn't move before that
+    chunk_requests: UnboundedReceiver<ChunkPos>,
+    requested: MetroHashSet<u64>, //Chunks asked for that haven't arrived yet, so they're only asked for once
+    console: Receiver<ConsoleCommand>,
+}
+
+impl Game {
+    pub const TICK_DURATION: Duration = Duration::from_millis(50);
+
+    /// Connects to the server at `address` and logs in.
+    pub fn connect(address: &str, console: Receiver<ConsoleCommand>) -> anyhow::Result<Self> {
+        let net_handler = ClientNetworkHandler::for_server(address)?;
+        let (request_chunks, chunk_requests) = unbounded_channel();
+
+        //Replaced by the dimension the server puts the player into right after the login
+        let world = ClientWorldStorage::new(DimensionId::OVERWORLD, DimensionSettings::overworld(), request_chunks);
+
+        let game = Self {
+            net_handler,
+            world,
+            player: LocalPlayer::default(),
+            spawned: false,
+            chunk_requests,
+            requested: MetroHashSet::default(),
+            console,
+        };
+        game.send(PacketData::Login)?;
+
+        Ok(game)
+    }
+
+    /// Ticks until the connection is closed.
+    pub fn run(mut self) -> anyhow::Result<()> {
+        info!("Connected to the server");
+
+        loop {
+            let tick_start = Instant::now();
+
+            if !self.tick()? {
+                break;
+            }
+
+            if let Some(remaining) = Self::TICK_DURATION.checked_sub(tick_start.elapsed()) {
+                sleep(remaining);
+            }
+        }
+
+        self.net_handler.close_all();
+
+        Ok(())
+    }
+
+    /// Handles what the server sent, then moves the player. Returns false once the server closed the connection.
+    pub fn tick(&mut self) -> anyhow::Result<bool> {
+        for packet in self.net_handler.retrieve_incoming() {
+            if let PacketData::Disconnect = packet.data {
+                return Ok(false);
+            }
+
+            let packet_type = packet.packet_type;
+
+            if let Err(e) = self.handle_packet(packet.data) {
+                error!("Failed to handle a {:?} packet: {}", packet_type, e);
+            }
+        }
+
+        self.handle_console();
+
+        if self.spawned {
+            if let Err(e) = self.tick_player() {
+                error!("Failed to tick the player: {}", e);
+            }
+        }
+
+        self.request_chunks()?;
+
+        Ok(true)
+    }
+
+    fn handle_console(&mut self) {
+        //If the console is gone there just won't be any more commands, so keep running
+        while let Ok(command) = self.console.try_recv() {
+            if let Err(e) = self.run_command(command.clone()) {
+                error!("Failed to run {:?}: {}", command, e);
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: ConsoleCommand) -> anyhow::Result<()> {
+        match command {
+            ConsoleCommand::Inspect(pos) => match self.world.block_entity_data(&pos) {
+                Some(data) => info!("Block entity at {:?}: {:?}", pos, data),
+                None => info!("No block entity at {:?}", pos),
+            },
+            ConsoleCommand::Entities => {
+                for (id, entity) in self.world.entities().iter() {
+                    info!("{:?}: {} at {}", id, entity.kind, entity.pos);
+                }
+            }
+            ConsoleCommand::SelectHotbarSlot(slot) => {
+                let packet = self.player.select_hotbar_slot(slot)?;
+                self.send(packet)?;
+            }
+            ConsoleCommand::Click(slot, kind) => {
+                let packet = self.player.click(slot, kind)?;
+                self.send(packet)?;
+            }
+            ConsoleCommand::Dig(pos) => {
+                //Only one block is dug at a time, switching to another one gives up on the first
+                if let Some(abort) = self.player.abort_digging() {
+                    self.send(abort)?;
+                }
+
+                let start = self.player.start_digging(pos);
+                self.send(start)?;
+            }
+            ConsoleCommand::AbortDigging => {
+                if let Some(abort) = self.player.abort_digging() {
+                    self.send(abort)?;
+                }
+            }
+            ConsoleCommand::Place(pos, face) => {
+                //The middle of the clicked face
+                let hit = Vec3::splat(0.5) + face.offset().as_vec3() * 0.5;
+                self.send(self.player.click_block(pos, face, hit))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_packet(&mut self, data: PacketData) -> anyhow::Result<()> {
+        match data {
+            PacketData::BlockPalette(palette) => self.world.receive_palette(&palette)?,
+            PacketData::ChangeDimension(dimension, settings) => {
+                self.world.change_dimension(dimension, settings);
+                self.requested.clear();
+            }
+            PacketData::ChunkData(dimension, pos, chunk) => {
+                self.requested.remove(&pos.as_long());
+                self.world.receive_chunk(dimension, &pos, chunk);
+            }
+            PacketData::BlockUpdate(dimension, pos, block) => self.world.receive_block_update(dimension, pos, block)?,
+            PacketData::BlockEntityData(dimension, pos, data) =>
+                self.world.receive_block_entity_data(dimension, pos, data),
+            PacketData::SpawnEntity(dimension, spawn) => self.world.receive_entity_spawn(dimension, spawn),
+            PacketData::EntityMove(dimension, id, movement) => self.world.receive_entity_move(dimension, id, movement),
+            PacketData::EntityLook(dimension, id, rotation) => self.world.receive_entity_look(dimension, id, rotation),
+            PacketData::EntityVelocity(dimension, id, velocity) =>
+                self.world.receive_entity_velocity(dimension, id, velocity),
+            PacketData::DespawnEntities(dimension, ids) => self.world.receive_entity_despawn(dimension, &ids),
+            PacketData::PlayerTeleport(teleport) => {
+                let confirm = self.player.receive_teleport(teleport);
+                self.spawned = true;
+                self.send(confirm)?;
+            }
+            PacketData::WindowItems(items) => self.player.receive_window_items(items)?,
+            PacketData::SetSlot(update) => self.player.receive_slot(update)?,
+            data => warn!("Ignoring unexpected {:?} packet from the server", data.packet_type()),
+        }
+
+        Ok(())
+    }
+
+    /// Moves the player and tells the server where it went, then digs on. Waits for the chunk below the player first,
+    /// or it would fall through the world until the chunk arrives.
+    fn tick_player(&mut self) -> anyhow::Result<()> {
+        let ground = physics::ground_below(self.player.pos).get_chunk();
+
+        if !self.world.is_chunk_cached(&ground) {
+            self.world.get_chunk(&ground)?;
+            return Ok(());
+        }
+
+        self.player.tick(&mut self.world)?;
+        self.send(self.player.movement_packet())?;
+
+        if let Some(dig) = self.player.dig_tick(&mut self.world)? {
+            self.send(dig)?;
+        }
+
+        Ok(())
+    }
+
+    /// Asks the server for the chunks the world storage was missing since the last tick.
+    fn request_chunks(&mut self) -> anyhow::Result<()> {
+        while let Ok(pos) = self.chunk_requests.try_recv() {
+            if self.requested.insert(pos.as_long()) {
+                self.send(PacketData::RequestChunk(pos))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send(&self, data: PacketData) -> anyhow::Result<()> {
+        self.net_handler.enqueue_packet(Packet::new(PacketDirection::ToServer, data))
+    }
+}
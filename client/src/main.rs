@@ -1,6 +1,32 @@
+use log::error;
+use shared::{ block::registry::BlockRegistry, net::DEFAULT_ADDRESS, util::logger };
+
+use crate::game::Game;
+
+mod console;
 mod dimension;
+mod entity;
+mod game;
+mod player;
 mod net;
 
 fn main() {
-    println!("Hello, world!");
-}
\ No newline at end of file
+    let _logger = logger::default_config();
+
+    //Before anything looks up a block; ids the server uses differently are remapped once its palette arrives
+    BlockRegistry::builtin().install().expect("Nothing else installs a block registry.");
+
+    let address = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+
+    let game = match Game::connect(&address, console::read_stdin()) {
+        Ok(game) => game,
+        Err(e) => {
+            error!("Failed to connect to {}: {}", address, e);
+            return;
+        }
+    };
+
+    if let Err(e) = game.run() {
+        error!("Client stopped unexpectedly: {}", e);
+    }
+}
@@ -1,12 +1,16 @@
 use std::{
-    sync::mpsc::{ channel, Sender, Receiver },
+    sync::mpsc::{ channel, Sender, Receiver, TryRecvError },
     net::{ TcpStream },
     io::{ BufWriter, Write },
     thread::{ JoinHandle, spawn },
 };
 
-use log::error;
-use shared::net::{ packet::{ Packet, PacketSource }, NetworkHandler };
+use log::{ error, info };
+use shared::net::{
+    packet::{ Packet, PacketDirection, PacketSource, PacketReader },
+    packet_data::PacketData,
+    NetworkHandler,
+};
 
 pub struct ClientNetworkHandler {
     outgoing_sender: Sender<Packet>,
@@ -15,81 +19,94 @@ pub struct ClientNetworkHandler {
 }
 
 impl ClientNetworkHandler {
-    fn handle_incoming(stream: &mut TcpStream, send: &Sender<Packet>) -> anyhow::Result<()> {
-        while let Some(packet) = Packet::try_from_stream(stream, PacketSource::Server)? {
+    /// Passes on every packet that has arrived completely. Returns false once the server closed the connection.
+    fn handle_incoming(
+        stream: &mut TcpStream,
+        reader: &mut PacketReader,
+        send: &Sender<Packet>
+    ) -> anyhow::Result<bool> {
+        let open = reader.fill(stream)?;
+
+        while let Some(packet) = reader.next_packet(&PacketSource::Server)? {
             send.send(packet)?;
         }
 
-        Ok(())
+        Ok(open)
     }
 
-    fn handle_outgoing(stream: &mut TcpStream, receive: &Receiver<Packet>) -> anyhow::Result<()> {
+    /// Writes every queued packet. Returns false once the handler was closed and nothing more will be queued.
+    fn handle_outgoing(stream: &mut TcpStream, receive: &Receiver<Packet>) -> anyhow::Result<bool> {
         let mut writer = BufWriter::new(stream);
-        while let Ok(packet) = receive.try_recv() {
-            packet.write_to_buffer(&mut writer)?;
-        }
+        let open = loop {
+            match receive.try_recv() {
+                Ok(packet) => packet.write_to_buffer(&mut writer)?,
+                Err(TryRecvError::Empty) => break true,
+                Err(TryRecvError::Disconnected) => break false,
+            }
+        };
         writer.flush()?;
 
-        Ok(())
+        Ok(open)
     }
 
-    pub fn for_server(address: &str) -> Self {
+    pub fn for_server(address: &str) -> anyhow::Result<Self> {
         let (send_out, receive_out) = channel::<Packet>();
         let (send_in, receive_in) = channel();
 
-        let mut stream = TcpStream::connect(address).unwrap();
-        stream.set_nonblocking(true).unwrap();
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_nonblocking(true)?;
 
         let handle_thread = spawn(move || {
+            let mut reader = PacketReader::default();
+
             loop {
-                if let Err(error) = Self::handle_incoming(&mut stream, &send_in) {
-                    error!("Failed to receive incoming Packet(s): {}", error);
-                } else if let Err(error) = Self::handle_outgoing(&mut stream, &receive_out) {
-                    error!("Failed to dispatch outgoing Packet(s): {}", error);
+                match Self::handle_incoming(&mut stream, &mut reader, &send_in) {
+                    Ok(true) => (),
+                    Ok(false) => {
+                        info!("The server closed the connection");
+                        break;
+                    }
+                    Err(error) => {
+                        //The connection broke, or it's no longer clear where the next packet starts
+                        error!("Failed to receive incoming Packet(s): {}", error);
+                        break;
+                    }
+                }
+
+                match Self::handle_outgoing(&mut stream, &receive_out) {
+                    Ok(true) => (),
+                    Ok(false) => return,
+                    Err(error) => error!("Failed to dispatch outgoing Packet(s): {}", error),
                 }
             }
+
+            //Like the server does for its clients, so the game notices the connection is gone
+            let _ = send_in.send(Packet::new(PacketDirection::FromServer, PacketData::Disconnect));
         });
 
-        Self {
+        Ok(Self {
             server_thread: handle_thread,
             outgoing_sender: send_out,
             incoming_receiver: receive_in,
-        }
+        })
     }
 }
 
-pub struct FakeNetworkHandler {
-    outgoing: Sender<Packet>,
-    incoming: Receiver<Packet>,
-}
-
-impl NetworkHandler for FakeNetworkHandler {
+impl NetworkHandler for ClientNetworkHandler {
     fn enqueue_packet(&self, packet: Packet) -> anyhow::Result<()> {
-        match self.outgoing.send(packet) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.into()),
-        }
+        Ok(self.outgoing_sender.send(packet)?)
     }
 
     fn retrieve_incoming(&mut self) -> Vec<Packet> {
-        let mut result = Vec::new();
-        while let Ok(packet) = self.incoming.try_recv() {
-            result.push(packet);
-        }
-        result
+        self.incoming_receiver.try_iter().collect()
     }
 
-    fn close_all(self) {}
-}
-
-impl FakeNetworkHandler {
-    pub fn new_pair() -> (Self, Self) {
-        let (to_client, from_server) = channel();
-        let (to_server, from_client) = channel();
+    /// Sends what is still queued, then closes the connection.
+    fn close_all(self) {
+        drop(self.outgoing_sender);
 
-        (
-            Self { incoming: from_server, outgoing: to_server },
-            Self { incoming: from_client, outgoing: to_client },
-        )
+        if self.server_thread.join().is_err() {
+            error!("The network thread panicked");
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,129 @@
+use glam::{ Vec2, Vec3 };
+use shared::{
+    dimension::storage::ChunkStorage,
+    entity::{
+        physics::{ self, Body, Collisions, PhysicsSettings },
+        player::{ break_ticks, PlayerMove, PlayerTeleport, PlaceBlock, PLAYER_SIZE },
+    },
+    item::{
+        inventory::{ ClickKind, InventoryClick, PlayerInventory, SlotUpdate, WindowItems },
+        stack::ItemStack,
+    },
+    net::packet_data::PacketData,
+    util::{ block_pos::BlockPos, direction::Direction },
+};
+
+/// The player this client controls. It moves on its own, the server only steps in through teleports.
+#[derive(Debug, Default)]
+pub struct LocalPlayer {
+    pub pos: Vec3,
+    pub velocity: Vec3,
+    pub rotation: Vec2,
+    pub on_ground: bool,
+    pub inventory: PlayerInventory,
+    pub cursor: Option<ItemStack>,
+    pub digging: Option<(BlockPos, u32)>, //The block being broken, and for how many ticks so far
+}
+
+impl LocalPlayer {
+    /// Takes over where the server put the player. The returned confirmation has to be sent right away, the server
+    /// ignores the player's moves until it arrives.
+    pub fn receive_teleport(&mut self, teleport: PlayerTeleport) -> PacketData {
+        self.pos = teleport.pos;
+        self.velocity = Vec3::ZERO;
+        self.rotation = teleport.rotation;
+
+        PacketData::TeleportConfirm(teleport.id)
+    }
+
+    /// Moves the player by its velocity through the world it knows of, the same way the server moves entities.
+    pub fn tick<S: ChunkStorage + ?Sized>(&mut self, storage: &mut S) -> anyhow::Result<Collisions> {
+        let mut body = Body { pos: self.pos, velocity: self.velocity, size: PLAYER_SIZE, on_ground: self.on_ground };
+        let collisions = physics::step(&mut body, &PhysicsSettings::default(), storage)?;
+
+        self.pos = body.pos;
+        self.velocity = body.velocity;
+        self.on_ground = body.on_ground;
+
+        Ok(collisions)
+    }
+
+    /// Replaces the whole inventory with what the server has.
+    pub fn receive_window_items(&mut self, items: WindowItems) -> anyhow::Result<()> {
+        if items.window != PlayerInventory::WINDOW {
+            return Ok(());
+        }
+
+        for (slot, stack) in items.slots.into_iter().enumerate() {
+            self.inventory.inventory_mut().set(slot, stack)?;
+        }
+
+        self.cursor = items.cursor;
+
+        Ok(())
+    }
+
+    pub fn receive_slot(&mut self, update: SlotUpdate) -> anyhow::Result<()> {
+        match (update.window, update.slot) {
+            (PlayerInventory::WINDOW, SlotUpdate::CURSOR) => self.cursor = update.stack,
+            (PlayerInventory::WINDOW, slot) => {
+                self.inventory.inventory_mut().set(slot as usize, update.stack)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Applies a click right away, so the inventory doesn't lag behind. The server answers the returned packet with
+    /// the slots as they really are.
+    pub fn click(&mut self, slot: usize, kind: ClickKind) -> anyhow::Result<PacketData> {
+        self.inventory.click(&mut self.cursor, slot, kind)?;
+
+        Ok(PacketData::ClickSlot(InventoryClick { window: PlayerInventory::WINDOW, slot: slot as u16, kind }))
+    }
+
+    pub fn select_hotbar_slot(&mut self, slot: u8) -> anyhow::Result<PacketData> {
+        self.inventory.select(slot)?;
+
+        Ok(PacketData::SelectHotbarSlot(slot))
+    }
+
+    pub fn start_digging(&mut self, pos: BlockPos) -> PacketData {
+        self.digging = Some((pos.clone(), 0));
+
+        PacketData::DigStart(pos)
+    }
+
+    pub fn abort_digging(&mut self) -> Option<PacketData> {
+        self.digging.take().map(|(pos, _)| PacketData::DigAbort(pos))
+    }
+
+    /// Digs on at the block for another tick. Once it's been long enough to break it, the server is told, which
+    /// sends the block update if it agrees.
+    pub fn dig_tick<S: ChunkStorage + ?Sized>(&mut self, storage: &mut S) -> anyhow::Result<Option<PacketData>> {
+        let Some((pos, ticks)) = self.digging.as_mut() else {
+            return Ok(None);
+        };
+
+        *ticks += 1;
+
+        let block = storage.get_chunk(&pos.get_chunk())?.get_block(pos.clone())?.resolve()?;
+
+        match break_ticks(block) {
+            Some(needed) if *ticks >= needed => Ok(self.digging.take().map(|(pos, _)| PacketData::DigFinish(pos))),
+            Some(_) => Ok(None),
+            None => Ok(self.abort_digging()),
+        }
+    }
+
+    /// Clicks a block with whatever the player holds. The server decides whether that uses or places anything.
+    pub fn click_block(&self, pos: BlockPos, face: Direction, hit: Vec3) -> PacketData {
+        PacketData::PlaceBlock(PlaceBlock { pos, face, hit })
+    }
+
+    /// Tells the server where the player is now, sent once every tick.
+    pub fn movement_packet(&self) -> PacketData {
+        PacketData::PlayerMove(PlayerMove { pos: self.pos, rotation: self.rotation, on_ground: self.on_ground })
+    }
+}
@@ -1,64 +1,178 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::ItemEnum;
-
-#[proc_macro_attribute]
-pub fn count_ids(_attr: TokenStream, target: TokenStream) -> TokenStream {
-    let target_enum = syn::parse::<ItemEnum>(target).unwrap();
-    let name = target_enum.clone().ident;
-    let fields = target_enum
-        .variants
+use syn::{ DeriveInput, Data, Field, Fields, Lit, Meta, NestedMeta, Attribute, Type };
+
+fn attribute_values(attrs: &[Attribute], attribute: &str) -> Vec<(String, Lit)> {
+    attrs
         .iter()
-        .map(|f| f.ident.clone())
-        .collect::<Vec<_>>();
+        .filter(|attr| attr.path.is_ident(attribute))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .filter_map(|meta| {
+            match meta {
+                Meta::List(list) => Some(list.nested),
+                _ => None,
+            }
+        })
+        .flatten()
+        .filter_map(|nested| {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(pair)) =>
+                    pair.path.get_ident().map(|ident| (ident.to_string(), pair.lit)),
+                _ => None,
+            }
+        })
+        .collect()
+}
 
-    let inners = target_enum
-        .variants
+fn int_value(values: &[(String, Lit)], key: &str) -> Option<i32> {
+    values
         .iter()
-        .map(|v| {
-            v.fields
-                .iter()
-                .collect::<Vec<_>>()
-                .first()
-                .unwrap()
-                .ty
-                .clone()
+        .find(|(name, _)| name == key)
+        .and_then(|(_, lit)| {
+            match lit {
+                Lit::Int(int) => int.base10_parse().ok(),
+                _ => None,
+            }
         })
-        .collect::<Vec<_>>();
+}
 
-    let reps = (0..(fields.len() as u8)).collect::<Vec<_>>();
+fn is_integer(ty: &Type) -> bool {
+    const INTEGERS: [&str; 12] = [
+        "u8", "u16", "u32", "u64", "u128", "usize",
+        "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
 
-    let implem = quote! {
-        #target_enum
+    match ty {
+        Type::Path(path) => INTEGERS.iter().any(|int| path.path.is_ident(int)),
+        _ => false,
+    }
+}
 
-        impl #name {
-            fn repr(&self) -> u8 {
-                match self {
-                    #(Self::#fields(_) => #reps,)*
-                }
+/// The range of an integer property. Other properties don't have one, they get 0 to 0.
+fn property_range(field: &Field, values: &[(String, Lit)]) -> Result<(i32, i32), TokenStream> {
+    if !is_integer(&field.ty) {
+        return Ok((0, 0));
+    }
+
+    match (int_value(values, "min"), int_value(values, "max")) {
+        (Some(min), Some(max)) => Ok((min, max)),
+        _ => {
+            let message = "Integer properties need #[property(min = .., max = ..)]";
+
+            //Point at the attribute that lacks the range, or at the field if it has none at all
+            let error = match field.attrs.iter().find(|attr| attr.path.is_ident("property")) {
+                Some(attr) => syn::Error::new_spanned(attr, message),
+                None => syn::Error::new_spanned(field, message),
+            };
+
+            Err(error.to_compile_error().into())
+        }
+    }
+}
+
+fn str_value(values: &[(String, Lit)], key: &str) -> Option<String> {
+    values
+        .iter()
+        .find(|(name, _)| name == key)
+        .and_then(|(_, lit)| {
+            match lit {
+                Lit::Str(string) => Some(string.value()),
+                _ => None,
             }
+        })
+}
+
+/// Implements `State` for a struct whose fields are its block properties.
+/// The block name is given with `#[block(name = "...")]`; integer fields need `#[property(min = .., max = ..)]`,
+/// and any field can be renamed with `#[property(name = "...")]`.
+#[proc_macro_derive(State, attributes(block, property))]
+pub fn derive_state(target: TokenStream) -> TokenStream {
+    let target = syn::parse::<DeriveInput>(target).unwrap();
+    let name = target.ident;
+
+    let Some(block_name) = str_value(&attribute_values(&target.attrs, "block"), "name") else {
+        return quote! { compile_error!("Missing #[block(name = \"...\")] attribute"); }.into();
+    };
 
-            fn from_ints(repr: u8, variant: u8) -> anyhow::Result<Self> {
-                match repr {
-                    #(#reps => Ok(Self::#fields(#inners::from_id(variant)?)),)*
-                    _ => Err(anyhow::anyhow!(InvalidBlockIdError(repr))),
+    let fields = match target.data {
+        Data::Struct(data) =>
+            match data.fields {
+                Fields::Named(named) => named.named.into_iter().collect::<Vec<_>>(),
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(_) => {
+                    return quote! { compile_error!("States need named fields"); }.into();
                 }
             }
+        _ => {
+            return quote! { compile_error!("Only structs can be states"); }.into();
+        }
+    };
 
-            fn variant_id(&self) -> u8 {
-                match self {
-                    #(Self::#fields(inner) => inner.id(),)*
-                }
+    let idents = fields
+        .iter()
+        .map(|field| field.ident.clone().unwrap())
+        .collect::<Vec<_>>();
+
+    let properties = fields
+        .iter()
+        .map(|field| {
+            let values = attribute_values(&field.attrs, "property");
+            let property_name = str_value(&values, "name").unwrap_or_else(||
+                field.ident.as_ref().unwrap().to_string()
+            );
+            let (min, max) = property_range(field, &values)?;
+            let ty = &field.ty;
+
+            Ok(quote! {
+                ::shared::block::property::Property::<#ty>::ranged(#property_name, #min, #max)
+            })
+        })
+        .collect::<Result<Vec<_>, TokenStream>>();
+
+    let properties = match properties {
+        Ok(properties) => properties,
+        Err(error) => {
+            return error;
+        }
+    };
+
+    let count = fields.len();
+
+    let construct = if fields.is_empty() {
+        quote! { Self }
+    } else {
+        quote! { Self { #(#idents,)* } }
+    };
+
+    let implem =
+        quote! {
+        impl ::shared::block::state::State for #name {
+            const NAME: &'static str = #block_name;
+
+            fn properties() -> Vec<::shared::block::registry::PropertyDefinition> {
+                vec![#(#properties.definition(),)*]
             }
 
-            fn inner_handler(&self) -> &dyn BlockHandler {
-                match self {
-                    #(Self::#fields(inner) => inner,)*
-                }
+            fn id(&self) -> u8 {
+                let digits: [(usize, usize); #count] = [
+                    #((
+                        ::shared::block::property::PropertyValue::to_index(self.#idents, &#properties),
+                        #properties.count()
+                    ),)*
+                ];
+
+                ::shared::block::property::encode_state(&digits)
             }
-        }
 
+            fn from_id(id: u8) -> anyhow::Result<Self> {
+                #[allow(unused_mut)]
+                let mut decoder = ::shared::block::property::StateDecoder::new(#block_name, id);
+                #(let #idents = decoder.next(&#properties)?;)*
+                decoder.finish()?;
 
+                Ok(#construct)
+            }
+        }
     };
 
     implem.into()
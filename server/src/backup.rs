@@ -0,0 +1,277 @@
+use std::{ path::{ Path, PathBuf }, hash::Hasher, fmt::Write as _ };
+
+use anyhow::{ anyhow, bail };
+use chrono::Local;
+use log::info;
+use metrohash::{ MetroHash64, MetroHashMap };
+
+use crate::util::{ write_atomically, is_temp_file };
+
+pub enum SnapshotError {
+    UnknownSnapshot(String),
+    InvalidManifest(String, usize),
+}
+
+impl From<SnapshotError> for anyhow::Error {
+    fn from(value: SnapshotError) -> Self {
+        match value {
+            SnapshotError::UnknownSnapshot(id) => anyhow!("There is no snapshot named {}", id),
+            SnapshotError::InvalidManifest(id, line) =>
+                anyhow!("The manifest of snapshot {} is broken in line {}", id, line),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ManifestEntry {
+    hash: u64,
+    source: String, //The snapshot that actually holds a copy of this file
+}
+
+/// Lists every file of the world at the time the snapshot was taken. Files that didn't change since the previous snapshot
+/// aren't copied again, their entry points to the older snapshot instead.
+#[derive(Debug, Clone, Default)]
+struct Manifest {
+    files: MetroHashMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    const FILE_NAME: &'static str = "manifest";
+
+    fn load(snapshot_folder: &Path, id: &str) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(snapshot_folder.join(Self::FILE_NAME))?;
+        let mut files = MetroHashMap::default();
+
+        for (number, line) in text.lines().enumerate() {
+            let mut parts = line.split('\t');
+
+            let (Some(path), Some(hash), Some(source)) = (parts.next(), parts.next(), parts.next()) else {
+                bail!(SnapshotError::InvalidManifest(id.to_string(), number + 1));
+            };
+
+            let hash = u64::from_str_radix(hash, 16)
+                .map_err(|_| SnapshotError::InvalidManifest(id.to_string(), number + 1))?;
+
+            files.insert(path.to_string(), ManifestEntry { hash, source: source.to_string() });
+        }
+
+        Ok(Self { files })
+    }
+
+    fn save(&self, snapshot_folder: &Path) -> anyhow::Result<()> {
+        let mut paths: Vec<&String> = self.files.keys().collect();
+        paths.sort();
+
+        let mut text = String::new();
+
+        for path in paths {
+            let entry = &self.files[path];
+            writeln!(text, "{}\t{:016x}\t{}", path, entry.hash, entry.source)?;
+        }
+
+        write_atomically(&snapshot_folder.join(Self::FILE_NAME), text.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub files: usize,
+    pub copied: usize,
+}
+
+pub struct SnapshotManager {
+    world_folder: PathBuf,
+    backup_folder: PathBuf,
+}
+
+impl SnapshotManager {
+    pub fn new(world_folder: &Path, backup_folder: &Path) -> Self {
+        Self {
+            world_folder: world_folder.to_path_buf(),
+            backup_folder: backup_folder.to_path_buf(),
+        }
+    }
+
+    /// All snapshots, oldest first.
+    pub fn list(&self) -> anyhow::Result<Vec<SnapshotInfo>> {
+        if !self.backup_folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut ids = Vec::new();
+
+        for entry in std::fs::read_dir(&self.backup_folder)? {
+            let entry = entry?;
+
+            if entry.path().join(Manifest::FILE_NAME).exists() {
+                ids.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+
+        //Ids are timestamps, so sorting them by name sorts them by age
+        ids.sort();
+
+        ids.into_iter()
+            .map(|id| {
+                let manifest = Manifest::load(&self.backup_folder.join(&id), &id)?;
+                let copied = manifest.files
+                    .values()
+                    .filter(|entry| entry.source == id)
+                    .count();
+
+                Ok(SnapshotInfo { files: manifest.files.len(), copied, id })
+            })
+            .collect()
+    }
+
+    /// Copies every file of the world that changed since the last snapshot. The world must not be saved to while this runs.
+    pub fn create(&self) -> anyhow::Result<SnapshotInfo> {
+        let previous = match self.list()?.last() {
+            Some(snapshot) =>
+                Manifest::load(&self.backup_folder.join(&snapshot.id), &snapshot.id)?,
+            None => Manifest::default(),
+        };
+
+        let id = self.next_id();
+        let snapshot_folder = self.backup_folder.join(&id);
+        std::fs::create_dir_all(&snapshot_folder)?;
+
+        let mut manifest = Manifest::default();
+        let mut copied = 0;
+
+        for path in Self::world_files(&self.world_folder)? {
+            let data = std::fs::read(self.world_folder.join(&path))?;
+            let hash = Self::hash(&data);
+
+            let source = match previous.files.get(&path) {
+                Some(entry) if entry.hash == hash => entry.source.clone(),
+                _ => {
+                    let target = snapshot_folder.join(&path);
+
+                    if let Some(parent) = target.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+
+                    std::fs::write(target, data)?;
+                    copied += 1;
+                    id.clone()
+                }
+            };
+
+            manifest.files.insert(path, ManifestEntry { hash, source });
+        }
+
+        //The manifest is written last, so a snapshot that was interrupted never shows up in the list
+        manifest.save(&snapshot_folder)?;
+
+        info!("Created snapshot {}, copied {} of {} files", id, copied, manifest.files.len());
+
+        Ok(SnapshotInfo { id, files: manifest.files.len(), copied })
+    }
+
+    /// Puts the world back into the state of the given snapshot. The server must not be running on this world.
+    pub fn restore(&self, id: &str) -> anyhow::Result<()> {
+        //Only ids of actual snapshots, anything else could point outside of the backup folder
+        if !self.list()?.iter().any(|snapshot| snapshot.id == id) {
+            bail!(SnapshotError::UnknownSnapshot(id.to_string()));
+        }
+
+        let snapshot_folder = self.backup_folder.join(id);
+
+        let manifest = Manifest::load(&snapshot_folder, id)?;
+
+        for (path, entry) in &manifest.files {
+            let data = std::fs::read(self.backup_folder.join(&entry.source).join(path))?;
+            let target = self.world_folder.join(path);
+
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            write_atomically(&target, &data)?;
+        }
+
+        //Anything that didn't exist yet when the snapshot was taken has to go
+        for path in Self::world_files(&self.world_folder)? {
+            if !manifest.files.contains_key(&path) {
+                std::fs::remove_file(self.world_folder.join(path))?;
+            }
+        }
+
+        info!("Restored snapshot {}", id);
+
+        Ok(())
+    }
+
+    fn next_id(&self) -> String {
+        let base = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut id = base.clone();
+        let mut suffix = 1;
+
+        while self.backup_folder.join(&id).exists() {
+            //Padded, so the ids of snapshots taken within the same second still sort by age
+            id = format!("{}-{:03}", base, suffix);
+            suffix += 1;
+        }
+
+        id
+    }
+
+    fn hash(data: &[u8]) -> u64 {
+        let mut hasher = MetroHash64::new();
+        hasher.write(data);
+        hasher.finish()
+    }
+
+    /// Paths of all files in the world folder, relative to it and separated by '/'. Temporary files are left out,
+    /// they may belong to a write the server is in the middle of.
+    fn world_files(world_folder: &Path) -> anyhow::Result<Vec<String>> {
+        let mut result = Vec::new();
+        let mut folders = vec![world_folder.to_path_buf()];
+
+        while let Some(folder) = folders.pop() {
+            for entry in std::fs::read_dir(&folder)? {
+                let path = entry?.path();
+
+                if path.is_dir() {
+                    folders.push(path);
+                } else if is_temp_file(&path) {
+                    continue;
+                } else if let Ok(relative) = path.strip_prefix(world_folder) {
+                    let parts: Vec<_> = relative
+                        .components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect();
+                    result.push(parts.join("/"));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Handles `snapshot create`, `snapshot list` and `snapshot restore <id>` on the command line.
+pub fn run_cli(args: &[String], world_folder: &Path, backup_folder: &Path) -> anyhow::Result<()> {
+    let manager = SnapshotManager::new(world_folder, backup_folder);
+
+    match args {
+        [command] if command == "create" => {
+            let snapshot = manager.create()?;
+            println!("Created snapshot {} ({} files, {} copied)", snapshot.id, snapshot.files, snapshot.copied);
+        }
+        [command] if command == "list" => {
+            for snapshot in manager.list()? {
+                println!("{}\t{} files, {} copied", snapshot.id, snapshot.files, snapshot.copied);
+            }
+        }
+        [command, id] if command == "restore" => {
+            manager.restore(id)?;
+            println!("Restored snapshot {}", id);
+        }
+        _ => bail!("Usage: server snapshot <create | list | restore <id>>"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::{ sync::mpsc::{ channel, Receiver }, thread::spawn, io::BufRead };
+
+use log::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Stop,
+    SaveAll,
+    Snapshot,
+    Status,
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "stop" => Some(Self::Stop),
+            "save-all" => Some(Self::SaveAll),
+            "snapshot" => Some(Self::Snapshot),
+            "status" => Some(Self::Status),
+            _ => None,
+        }
+    }
+}
+
+/// Forwards every command typed into the server's terminal.
+pub fn read_stdin() -> Receiver<ConsoleCommand> {
+    let (send, receive) = channel();
+
+    spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            match ConsoleCommand::parse(&line) {
+                Some(command) => {
+                    if send.send(command).is_err() {
+                        break;
+                    }
+                }
+                None => warn!("Unknown command: {}", line.trim()),
+            }
+        }
+    });
+
+    receive
+}
@@ -0,0 +1,460 @@
+use std::{ sync::mpsc::Receiver, time::{ Duration, Instant }, thread::sleep };
+
+use anyhow::anyhow;
+use glam::Vec3;
+use log::{ info, warn, error };
+use metrohash::MetroHashMap;
+use shared::{
+    block::registry::BlockRegistry,
+    dimension::{ id::DimensionId, access::WorldAccess },
+    entity::{ id::EntityId, player::{ PlayerMove, PlaceBlock } },
+    item::inventory::{ InventoryClick, PlayerInventory, SlotUpdate },
+    util::{ block_pos::BlockPos, chunk_pos::ChunkPos },
+    net::{
+        NetworkHandler,
+        packet::{ Packet, PacketDirection, ClientId },
+        packet_data::PacketData,
+    },
+};
+
+use crate::{
+    world::ServerWorld,
+    console::ConsoleCommand,
+    backup::SnapshotManager,
+    dimension::registry::{ ServerDimension, DimensionRegistry, DimensionRegistryError },
+    entity::{ EntityError, player::{ MoveOutcome, PlaceOutcome } },
+};
+
+pub enum ControllerError {
+    NotLoggedIn(ClientId),
+    AlreadyLoggedIn(ClientId),
+}
+
+impl From<ControllerError> for anyhow::Error {
+    fn from(value: ControllerError) -> Self {
+        match value {
+            ControllerError::NotLoggedIn(client) => anyhow!("Client {:?} isn't logged in", client),
+            ControllerError::AlreadyLoggedIn(client) => anyhow!("Client {:?} is already logged in", client),
+        }
+    }
+}
+
+pub struct ServerController<N: NetworkHandler> {
+    net_handler: N,
+    world: ServerWorld,
+    console: Receiver<ConsoleCommand>,
+    snapshots: SnapshotManager,
+    autosave_interval: Duration,
+    last_save: Instant,
+    clients: MetroHashMap<ClientId, DimensionId>, //The dimension each logged in client is in
+    players: MetroHashMap<ClientId, EntityId>, //The player entity of each client, in the dimension it's in
+}
+
+impl<N: NetworkHandler> ServerController<N> {
+    pub const TICK_DURATION: Duration = Duration::from_millis(50);
+    pub const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+    pub fn new(
+        net_handler: N,
+        world: ServerWorld,
+        console: Receiver<ConsoleCommand>,
+        snapshots: SnapshotManager
+    ) -> Self {
+        Self {
+            net_handler,
+            world,
+            console,
+            snapshots,
+            autosave_interval: Self::DEFAULT_AUTOSAVE_INTERVAL,
+            last_save: Instant::now(),
+            clients: MetroHashMap::default(),
+            players: MetroHashMap::default(),
+        }
+    }
+
+    pub fn with_autosave_interval(self, autosave_interval: Duration) -> Self {
+        Self { autosave_interval, ..self }
+    }
+
+    /// Ticks the server until it is told to stop, then saves the world.
+    pub fn run(mut self) -> anyhow::Result<()> {
+        info!("Server started");
+
+        while self.handle_console() {
+            let tick_start = Instant::now();
+
+            self.tick();
+
+            if let Some(remaining) = Self::TICK_DURATION.checked_sub(tick_start.elapsed()) {
+                sleep(remaining);
+            }
+        }
+
+        self.shutdown()
+    }
+
+    pub fn tick(&mut self) {
+        for packet in self.net_handler.retrieve_incoming() {
+            self.handle_packet(packet);
+        }
+
+        self.world.tick();
+
+        let dimensions = self.world.dimensions_mut();
+
+        if let Err(e) = dimensions.broadcast_changes(&self.net_handler, &self.clients, &self.players) {
+            error!("Failed to send block updates: {}", e);
+        }
+
+        if let Err(e) = dimensions.broadcast_entities(&self.net_handler, &self.clients, &self.players) {
+            error!("Failed to send entity updates: {}", e);
+        }
+
+        if self.last_save.elapsed() >= self.autosave_interval {
+            //A failed autosave shouldn't take the server down, the next one might work
+            if let Err(e) = self.save_all() {
+                error!("Autosave failed: {}", e);
+            }
+        }
+    }
+
+    /// Flushes everything to disk, blocking until it is done.
+    pub fn save_all(&mut self) -> anyhow::Result<()> {
+        self.last_save = Instant::now();
+        self.world.save()
+    }
+
+    /// Saves everything and then snapshots the world. Both happen within the same tick, so no save can sneak in between.
+    pub fn snapshot(&mut self) -> anyhow::Result<()> {
+        self.save_all()?;
+        self.snapshots.create()?;
+
+        Ok(())
+    }
+
+    fn handle_packet(&mut self, packet: Packet) {
+        match (packet.data, packet.direction) {
+            (PacketData::Login, PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.login(client.clone()) {
+                    error!("Failed to log in client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::Disconnect, PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.disconnect(&client) {
+                    error!("Failed to remove the player of client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::PlayerMove(movement), PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.move_player(client.clone(), movement) {
+                    error!("Failed to move the player of client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::TeleportConfirm(teleport), PacketDirection::FromClient(client)) => {
+                let confirmed = self
+                    .player_dimension(&client)
+                    .and_then(|(dimension, id)| dimension.confirm_teleport(id, teleport));
+
+                match confirmed {
+                    Ok(true) => {}
+                    Ok(false) => warn!("Client {:?} confirmed teleport {} it wasn't sent", client, teleport),
+                    Err(e) => error!("Failed to confirm a teleport of client {:?}: {}", client, e),
+                }
+            }
+            (PacketData::ClickSlot(click), PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.click_slot(client.clone(), click) {
+                    error!("Failed to handle a click of client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::SelectHotbarSlot(slot), PacketDirection::FromClient(client)) => {
+                let selected = self
+                    .player_dimension(&client)
+                    .and_then(|(dimension, id)| {
+                        dimension.with_player(id, |player, _, _| player.inventory_mut().select(slot))
+                    });
+
+                if let Err(e) = selected {
+                    warn!("Client {:?} couldn't select hotbar slot {}: {}", client, slot, e);
+                }
+            }
+            (PacketData::DigStart(pos), PacketDirection::FromClient(client)) => {
+                let started = self
+                    .player_dimension(&client)
+                    .and_then(|(dimension, id)| dimension.start_digging(id, pos.clone()));
+
+                match started {
+                    Ok(true) => {}
+                    Ok(false) => warn!("Client {:?} can't dig at {:?}", client, pos),
+                    Err(e) => error!("Failed to start digging for client {:?}: {}", client, e),
+                }
+            }
+            (PacketData::DigAbort(pos), PacketDirection::FromClient(client)) => {
+                let aborted = self
+                    .player_dimension(&client)
+                    .and_then(|(dimension, id)| dimension.abort_digging(id, &pos));
+
+                if let Err(e) = aborted {
+                    error!("Failed to stop digging for client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::DigFinish(pos), PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.finish_digging(client.clone(), pos) {
+                    error!("Failed to finish digging for client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::PlaceBlock(place), PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.use_held_item(client.clone(), place) {
+                    error!("Failed to handle a block click of client {:?}: {}", client, e);
+                }
+            }
+            (PacketData::RequestChunk(pos), PacketDirection::FromClient(client)) => {
+                if let Err(e) = self.send_chunk(client.clone(), pos) {
+                    error!("Failed to send a chunk to client {:?}: {}", client, e);
+                }
+            }
+            (data, direction) => {
+                warn!("Ignoring unexpected {:?} packet from {:?}", data.packet_type(), direction);
+            }
+        }
+    }
+
+    /// Tells a new client how blocks are numbered, then puts it into the spawn dimension.
+    fn login(&mut self, client: ClientId) -> anyhow::Result<()> {
+        if self.clients.contains_key(&client) {
+            return Err(ControllerError::AlreadyLoggedIn(client).into());
+        }
+
+        self.net_handler.enqueue_packet(
+            Packet::new(
+                PacketDirection::ToClient(client.clone()),
+                PacketData::BlockPalette(BlockRegistry::global()?.palette().clone())
+            )
+        )?;
+
+        let spawn = self.world.info().spawn_dimension;
+        let pos = Vec3::from(self.world.info().spawn.clone()) + Vec3::new(0.5, 0.0, 0.5);
+        self.world.dimensions().move_client(&self.net_handler, client.clone(), spawn)?;
+
+        let dimension = self.world
+            .dimensions_mut()
+            .get_mut(spawn)
+            .ok_or(DimensionRegistryError::UnknownDimension(spawn))?;
+        let (player, teleport) = dimension.spawn_player(pos)?;
+        let items = dimension.with_player(player, |player, _, _| Ok(player.window_items()))?;
+
+        self.net_handler.enqueue_packet(
+            Packet::new(PacketDirection::ToClient(client.clone()), PacketData::PlayerTeleport(teleport))
+        )?;
+        self.net_handler.enqueue_packet(
+            Packet::new(PacketDirection::ToClient(client.clone()), PacketData::WindowItems(items))
+        )?;
+        self.clients.insert(client.clone(), spawn);
+        self.players.insert(client, player);
+
+        Ok(())
+    }
+
+    /// Forgets a client that left, together with its player. Clients that never logged in are ignored.
+    fn disconnect(&mut self, client: &ClientId) -> anyhow::Result<()> {
+        let (Some(dimension), Some(player)) = (self.clients.remove(client), self.players.remove(client)) else {
+            return Ok(());
+        };
+
+        info!("Client {:?} left", client);
+
+        self.world
+            .dimensions_mut()
+            .get_mut(dimension)
+            .ok_or(DimensionRegistryError::UnknownDimension(dimension))?
+            .despawn_player(player)
+    }
+
+    /// The dimension a client's player is in, and its id there.
+    fn player_dimension(&mut self, client: &ClientId) -> anyhow::Result<(&mut ServerDimension, EntityId)> {
+        let (Some(dimension), Some(player)) = (self.clients.get(client), self.players.get(client)) else {
+            return Err(ControllerError::NotLoggedIn(client.clone()).into());
+        };
+
+        let dimension = self.world
+            .dimensions_mut()
+            .get_mut(*dimension)
+            .ok_or(DimensionRegistryError::UnknownDimension(*dimension))?;
+
+        Ok((dimension, *player))
+    }
+
+    /// Sends a client a chunk of the dimension its player is in. Chunks too far away for it to get their block updates
+    /// are refused, they'd only go stale.
+    fn send_chunk(&mut self, client: ClientId, pos: ChunkPos) -> anyhow::Result<()> {
+        let (dimension, player) = self.player_dimension(&client)?;
+        let center = dimension.chunks().entities().get(player).ok_or(EntityError::Missing(player))?.pos();
+
+        if !DimensionRegistry::is_nearby(&BlockPos::new(pos.x() * 16 + 8, 0, pos.z() * 16 + 8), center) {
+            warn!("Client {:?} requested chunk {:?}, which is out of its range", client, pos);
+            return Ok(());
+        }
+
+        for data in dimension.chunk_packets(&pos)? {
+            self.net_handler.enqueue_packet(Packet::new(PacketDirection::ToClient(client.clone()), data))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks a move sent by a client, and puts it back where it was if the move isn't possible.
+    fn move_player(&mut self, client: ClientId, movement: PlayerMove) -> anyhow::Result<()> {
+        let (dimension, player) = self.player_dimension(&client)?;
+
+        if let MoveOutcome::Rejected(teleport) = dimension.move_player(player, movement)? {
+            warn!("Client {:?} moved wrongly, putting it back", client);
+
+            self.net_handler.enqueue_packet(
+                Packet::new(PacketDirection::ToClient(client), PacketData::PlayerTeleport(teleport))
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a click to the inventory of a client's player, and tells it what changed. Clicks that make no sense,
+    /// e.g. because the client is out of sync, get it the whole inventory again instead.
+    fn click_slot(&mut self, client: ClientId, click: InventoryClick) -> anyhow::Result<()> {
+        let (dimension, player) = self.player_dimension(&client)?;
+
+        let packets = match dimension.with_player(player, |player, _, _| player.handle_click(click)) {
+            Ok(updates) => updates.into_iter().map(PacketData::SetSlot).collect(),
+            Err(e) => {
+                warn!("Client {:?} clicked wrongly, sending its inventory again: {}", client, e);
+
+                let items = dimension.with_player(player, |player, _, _| Ok(player.window_items()))?;
+                vec![PacketData::WindowItems(items)]
+            }
+        };
+
+        for data in packets {
+            self.net_handler.enqueue_packet(Packet::new(PacketDirection::ToClient(client.clone()), data))?;
+        }
+
+        Ok(())
+    }
+
+    /// Breaks the block a client finished digging at. The resulting block update reaches every nearby client; if
+    /// the block doesn't break, the client that thought it would is told it's still there.
+    fn finish_digging(&mut self, client: ClientId, pos: BlockPos) -> anyhow::Result<()> {
+        let (dimension, player) = self.player_dimension(&client)?;
+
+        if !dimension.finish_digging(player, pos.clone())? {
+            warn!("Client {:?} broke the block at {:?} when it couldn't", client, pos);
+            self.resend_blocks(client, &[pos])?;
+        }
+
+        Ok(())
+    }
+
+    /// Uses or places against the block a client clicked. If nothing comes of it, the client is told how the blocks
+    /// around the click and its held stack really are, in case it guessed wrong.
+    fn use_held_item(&mut self, client: ClientId, place: PlaceBlock) -> anyhow::Result<()> {
+        let (dimension, player) = self.player_dimension(&client)?;
+
+        match dimension.use_held_item(player, place.clone())? {
+            PlaceOutcome::Used => {}
+            PlaceOutcome::Placed(_, update) => {
+                self.net_handler.enqueue_packet(
+                    Packet::new(PacketDirection::ToClient(client), PacketData::SetSlot(update))
+                )?;
+            }
+            PlaceOutcome::Rejected => {
+                let held = dimension.with_player(player, |player, _, _| {
+                    let inventory = player.inventory();
+
+                    Ok(SlotUpdate {
+                        window: PlayerInventory::WINDOW,
+                        slot: inventory.selected() as u16,
+                        stack: inventory.selected_stack().cloned(),
+                    })
+                })?;
+
+                self.net_handler.enqueue_packet(
+                    Packet::new(PacketDirection::ToClient(client.clone()), PacketData::SetSlot(held))
+                )?;
+                self.resend_blocks(client, &[place.pos.clone(), place.pos.offset(place.face)])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends a client the blocks at `positions` as they are. Blocks that aren't loaded are left out, rather than
+    /// loading them for a client that has no business with them.
+    fn resend_blocks(&mut self, client: ClientId, positions: &[BlockPos]) -> anyhow::Result<()> {
+        let (dimension, _) = self.player_dimension(&client)?;
+        let mut packets = Vec::new();
+
+        for pos in positions {
+            if dimension.height().contains_y(pos.y()) && dimension.is_loaded(pos) {
+                packets.push(PacketData::BlockUpdate(dimension.id(), pos.clone(), dimension.get_block(pos.clone())?));
+            }
+        }
+
+        for data in packets {
+            self.net_handler.enqueue_packet(Packet::new(PacketDirection::ToClient(client.clone()), data))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns false once the server should shut down.
+    fn handle_console(&mut self) -> bool {
+        //If the console is gone there just won't be any more commands, so keep running
+        while let Ok(command) = self.console.try_recv() {
+            match command {
+                ConsoleCommand::Stop => {
+                    return false;
+                }
+                ConsoleCommand::SaveAll => {
+                    match self.save_all() {
+                        Ok(()) => info!("Saved the world"),
+                        Err(e) => error!("Failed to save the world: {}", e),
+                    }
+                }
+                ConsoleCommand::Snapshot => {
+                    if let Err(e) = self.snapshot() {
+                        error!("Failed to create a snapshot: {}", e);
+                    }
+                }
+                ConsoleCommand::Status => self.log_status(),
+            }
+        }
+
+        true
+    }
+
+    /// Logs how many players are online, and how much of each dimension is loaded or waiting to be processed.
+    fn log_status(&self) {
+        info!("{} player(s) online", self.players.len());
+
+        for dimension in self.world.dimensions().iter() {
+            let chunks = dimension.chunks();
+
+            info!(
+                "{}: {} chunk(s) loaded, {} of them unsaved, {} neighbour update(s) queued, \
+                {} block tick(s) scheduled, {} block entities, {} entities",
+                dimension.name(),
+                chunks.loaded_chunks().len(),
+                chunks.dirty_count(),
+                dimension.pending_updates(),
+                chunks.scheduled_tick_count(),
+                chunks.block_entity_count(),
+                chunks.entities().len()
+            );
+        }
+    }
+
+    pub fn shutdown(mut self) -> anyhow::Result<()> {
+        info!("Stopping server, saving world...");
+
+        self.save_all()?;
+        self.net_handler.close_all();
+
+        Ok(())
+    }
+}
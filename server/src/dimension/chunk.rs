@@ -1,40 +1,433 @@
-use std::{ path::Path, collections::hash_map::Entry };
+use std::{ path::Path, cell::RefCell, collections::{ VecDeque, hash_map::Entry } };
 
+use itertools::Itertools;
+use metrohash::{ MetroHashMap, MetroHashSet };
 use log::error;
-use metrohash::MetroHashMap;
-use once_cell::sync::Lazy;
 use shared::{
-    dimension::{ chunk::*, storage::{ ChunkStorage, ChunkLoader } },
-    util::{ chunk_pos::ChunkPos },
+    block::{ BlockId, entity::{ BlockEntity, BlockEntities }, state::BlockHandler },
+    tag::Compound,
+    dimension::{ chunk::*, storage::{ ChunkStorage, ChunkLoader }, height::WorldHeight },
+    util::{ chunk_pos::ChunkPos, block_pos::BlockPos },
+    entity::id::EntityId,
 };
 
+use crate::{ util::remove_interrupted_writes, entity::{ Entity, store::{ EntityStore, EntityIds } } };
+
+use super::{ generator::ChunkGenerator, region::{ RegionFile, SavedChunk }, ticks::ScheduledTick };
+
 pub struct DiskChunkLoader {
     save_folder: Box<Path>,
+    //Parsed regions, so loading the chunks of a region one by one doesn't read and remap the whole file every time
+    regions: RefCell<MetroHashMap<(i32, i32), RegionFile>>,
+    cached_order: RefCell<VecDeque<(i32, i32)>>,
 }
 
 impl DiskChunkLoader {
-    fn get_region_pos(chunk: &ChunkPos) -> (i32, i32) {
-        (chunk.x() / 32, chunk.z() / 32)
+    pub const MAX_CACHED_REGIONS: usize = 16;
+
+    pub fn new(save_folder: &Path) -> Self {
+        Self {
+            save_folder: save_folder.into(),
+            regions: RefCell::default(),
+            cached_order: RefCell::default(),
+        }
     }
-}
 
-impl ChunkLoader for DiskChunkLoader {
-    fn get_chunk(&self, _pos: &ChunkPos) -> Option<Chunk> {
-        None
+    /// Creates the save folder and cleans up after any write that was cut short by a crash.
+    pub fn prepare(&self) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.save_folder)?;
+        remove_interrupted_writes(&self.save_folder)
+    }
+
+    /// Writes the given chunks to their region files, rewriting each affected region exactly once.
+    pub fn save_chunks(&self, chunks: Vec<(ChunkPos, SavedChunk)>) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.save_folder)?;
+
+        let by_region = chunks.into_iter().into_group_map_by(|(pos, ..)| RegionFile::region_of(pos));
+
+        for (region, chunks) in by_region {
+            self.with_region(region, |file, path| {
+                for (pos, saved) in chunks {
+                    file.set_chunk(&pos, saved)?;
+                }
+
+                file.save(path)
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `get_chunk`, but also returns the ticks that were scheduled in the chunk and its block entities.
+    pub fn load_chunk(&self, pos: &ChunkPos) -> anyhow::Result<Option<SavedChunk>> {
+        self.with_region(RegionFile::region_of(pos), |file, _| file.get_chunk(pos))
+    }
+
+    /// Runs `f` on a region, reading it from disk only if it isn't cached yet. The region that was cached first makes
+    /// room when there are too many.
+    fn with_region<R>(
+        &self,
+        region: (i32, i32),
+        f: impl FnOnce(&mut RegionFile, &Path) -> anyhow::Result<R>
+    ) -> anyhow::Result<R> {
+        let path = RegionFile::path(&self.save_folder, region);
+        let mut regions = self.regions.borrow_mut();
+
+        let file = match regions.entry(region) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let file = entry.insert(RegionFile::load(&path)?);
+                self.cached_order.borrow_mut().push_back(region);
+                file
+            }
+        };
+
+        let result = f(file, &path);
+
+        let mut order = self.cached_order.borrow_mut();
+
+        while order.len() > Self::MAX_CACHED_REGIONS {
+            if let Some(oldest) = order.pop_front() {
+                regions.remove(&oldest);
+            }
+        }
+
+        result
     }
 }
 
-trait ChunkGenerator {
-    fn try_generate(&self, pos: &ChunkPos) -> anyhow::Result<Chunk>;
+impl ChunkLoader for DiskChunkLoader {
+    fn get_chunk(&self, pos: &ChunkPos) -> anyhow::Result<Option<Chunk>> {
+        Ok(self.load_chunk(pos)?.map(|saved| saved.chunk))
+    }
 }
 
 pub struct ServerChunkStorage {
+    height: WorldHeight,
     file_loader: DiskChunkLoader,
     generator: Box<dyn ChunkGenerator>,
     chunk_map: MetroHashMap<u64, Chunk>,
+    ticks: MetroHashMap<u64, Vec<ScheduledTick>>,
+    block_entities: MetroHashMap<u64, BlockEntities>,
+    entities: EntityStore,
+    //Where each entity that is taken out was, and what it saved back then if it's saved at all
+    taken_entities: MetroHashMap<EntityId, (u64, Option<Compound>)>,
+    taken_block_entities: MetroHashMap<BlockPos, Compound>, //Same for block entities
+    dirty: MetroHashSet<u64>,
 }
 
-static EMPTY_CHUNK: Lazy<Chunk> = Lazy::new(Chunk::empty);
+impl ServerChunkStorage {
+    pub fn new(
+        height: WorldHeight,
+        file_loader: DiskChunkLoader,
+        generator: Box<dyn ChunkGenerator>,
+        entity_ids: EntityIds
+    ) -> Self {
+        Self {
+            height,
+            file_loader,
+            generator,
+            chunk_map: MetroHashMap::default(),
+            ticks: MetroHashMap::default(),
+            block_entities: MetroHashMap::default(),
+            entities: EntityStore::new(entity_ids),
+            taken_entities: MetroHashMap::default(),
+            taken_block_entities: MetroHashMap::default(),
+            dirty: MetroHashSet::default(),
+        }
+    }
+
+    pub fn file_loader(&self) -> &DiskChunkLoader {
+        &self.file_loader
+    }
+
+    /// Like `get_chunk`, but marks the chunk as modified so it gets written on the next save.
+    pub fn get_chunk_mut(&mut self, pos: &ChunkPos) -> anyhow::Result<&mut Chunk> {
+        self.get_chunk(pos)?;
+
+        let id = pos.as_long();
+        self.dirty.insert(id);
+
+        Ok(self.chunk_map.get_mut(&id).expect("The chunk was just loaded."))
+    }
+
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockId) -> anyhow::Result<()> {
+        self.get_chunk_mut(&pos.get_chunk())?.set_block(pos, block)
+    }
+
+    pub fn loaded_chunks(&self) -> Vec<ChunkPos> {
+        self.chunk_map.keys().map(|id| ChunkPos::from_long(*id)).collect()
+    }
+
+    /// Adds a tick to the chunk it happens in, loading the chunk if needed.
+    /// A block that already has a tick pending doesn't get a second one.
+    pub fn schedule_tick(&mut self, tick: ScheduledTick) -> anyhow::Result<()> {
+        let chunk = tick.pos.get_chunk();
+        self.get_chunk_mut(&chunk)?;
+
+        let ticks = self.ticks.entry(chunk.as_long()).or_default();
+
+        if !ticks.iter().any(|pending| pending.pos == tick.pos && pending.block == tick.block) {
+            ticks.push(tick);
+        }
+
+        Ok(())
+    }
+
+    pub fn scheduled_tick_count(&self) -> usize {
+        self.ticks.values().map(Vec::len).sum()
+    }
+
+    /// Removes and returns every tick of a loaded chunk that is due at `time`, sorted by due time and priority.
+    pub fn take_due_ticks(&mut self, time: u64) -> Vec<ScheduledTick> {
+        let mut due = Vec::new();
+
+        for (id, ticks) in self.ticks.iter_mut() {
+            if ticks.iter().any(|tick| tick.due <= time) {
+                let (now, later) = std::mem::take(ticks).into_iter().partition(|tick| tick.due <= time);
+                due.extend::<Vec<_>>(now);
+                *ticks = later;
+                self.dirty.insert(*id);
+            }
+        }
+
+        self.ticks.retain(|_, ticks| !ticks.is_empty());
+
+        //Stable, so ticks with the same due time and priority run in the order they were scheduled in. That only holds
+        //within a chunk though, which of two chunks goes first is up to the map
+        due.sort_by_key(|tick| (tick.due, tick.priority));
+        due
+    }
+
+    pub fn block_entity(&self, pos: &BlockPos) -> Option<&dyn BlockEntity> {
+        self.block_entities.get(&pos.get_chunk().as_long())?.get(pos)
+    }
+
+    /// The block entities in the chunk at `pos`, in no particular order. Doesn't load the chunk.
+    pub fn block_entities_in<'a>(
+        &'a self,
+        pos: &'a ChunkPos
+    ) -> impl Iterator<Item = (BlockPos, &'a dyn BlockEntity)> + 'a {
+        self.block_entities.get(&pos.as_long()).into_iter().flat_map(move |entities| entities.iter(pos))
+    }
+
+    /// Like `block_entity`, but marks its chunk as modified. Doesn't load the chunk, so a block entity in a chunk that
+    /// isn't loaded is `None`.
+    pub fn block_entity_mut(&mut self, pos: &BlockPos) -> Option<&mut dyn BlockEntity> {
+        let id = pos.get_chunk().as_long();
+        let entity = self.block_entities.get_mut(&id)?.get_mut(pos)?;
+        self.dirty.insert(id);
+
+        Some(entity)
+    }
+
+    /// Puts a block entity into the chunk at `pos`, loading it if needed. Returns the entity that was there before.
+    pub fn set_block_entity(
+        &mut self,
+        pos: &BlockPos,
+        entity: Box<dyn BlockEntity>
+    ) -> anyhow::Result<Option<Box<dyn BlockEntity>>> {
+        let chunk = pos.get_chunk();
+        self.get_chunk_mut(&chunk)?;
+
+        Ok(self.block_entities.entry(chunk.as_long()).or_default().insert(pos, entity))
+    }
+
+    /// Takes a block entity out, e.g. to tick it. Like entities, it's gone for good unless it's put back through
+    /// `restore_block_entity`.
+    pub fn take_block_entity(&mut self, pos: &BlockPos) -> Option<Box<dyn BlockEntity>> {
+        let id = pos.get_chunk().as_long();
+        let entities = self.block_entities.get_mut(&id)?;
+        let entity = entities.remove(pos)?;
+
+        if entities.is_empty() {
+            self.block_entities.remove(&id);
+        }
+
+        //Taking out another one before the first came back means the first is gone
+        if self.taken_block_entities.insert(pos.clone(), entity.save()).is_some() {
+            self.dirty.insert(id);
+        }
+
+        Some(entity)
+    }
+
+    /// Puts back a block entity from `take_block_entity`. Its chunk is only marked as modified if the entity saves
+    /// differently than when it was taken out.
+    pub fn restore_block_entity(&mut self, pos: &BlockPos, entity: Box<dyn BlockEntity>) -> anyhow::Result<()> {
+        let chunk = pos.get_chunk();
+        self.get_chunk(&chunk)?;
+
+        if self.taken_block_entities.remove(pos).as_ref() != Some(&entity.save()) {
+            self.dirty.insert(chunk.as_long());
+        }
+
+        self.block_entities.entry(chunk.as_long()).or_default().insert(pos, entity);
+
+        Ok(())
+    }
+
+    pub fn remove_block_entity(&mut self, pos: &BlockPos) -> Option<Box<dyn BlockEntity>> {
+        let id = pos.get_chunk().as_long();
+        let entities = self.block_entities.get_mut(&id)?;
+        let entity = entities.remove(pos)?;
+
+        if entities.is_empty() {
+            self.block_entities.remove(&id);
+        }
+
+        self.dirty.insert(id);
+
+        Some(entity)
+    }
+
+    pub fn block_entity_count(&self) -> usize {
+        self.block_entities.values().map(BlockEntities::len).sum()
+    }
+
+    /// Where the block entities that want to be ticked are, in no particular order.
+    pub fn ticking_block_entities(&self) -> Vec<BlockPos> {
+        self.block_entities
+            .iter()
+            .flat_map(|(id, entities)| {
+                let chunk = ChunkPos::from_long(*id);
+
+                entities
+                    .iter(&chunk)
+                    .filter(|(_, entity)| entity.is_ticking())
+                    .map(|(pos, _)| pos)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn entities(&self) -> &EntityStore {
+        &self.entities
+    }
+
+    /// Adds an entity to the chunk it's in, loading the chunk if needed. Returns the id it was given.
+    pub fn spawn_entity(&mut self, entity: Entity) -> anyhow::Result<EntityId> {
+        self.get_chunk_mut(&entity.chunk())?;
+
+        Ok(self.entities.insert(entity))
+    }
+
+    /// Takes an entity out, e.g. to tick it. Unless it's put back through `restore_entity`, it's gone for good.
+    pub fn take_entity(&mut self, id: EntityId) -> Option<Entity> {
+        let entity = self.entities.take(id)?;
+        let saved = entity.behaviour().is_saved().then(|| entity.save());
+
+        self.taken_entities.insert(id, (entity.chunk().as_long(), saved));
+
+        Some(entity)
+    }
+
+    /// Puts back an entity from `take_entity`, into the chunk it's in by now. That chunk is loaded if needed.
+    /// Chunks are only marked as modified if the entity saves differently than when it was taken out.
+    pub fn restore_entity(&mut self, entity: Entity) -> anyhow::Result<()> {
+        let chunk = entity.chunk().as_long();
+        self.get_chunk(&entity.chunk())?;
+
+        let saved = entity.behaviour().is_saved().then(|| entity.save());
+
+        match self.taken_entities.remove(&entity.id()) {
+            Some((from, before)) if before == saved && (saved.is_none() || from == chunk) => (),
+            Some((from, _)) => {
+                self.dirty.insert(from);
+                self.dirty.insert(chunk);
+            }
+            None => {
+                self.dirty.insert(chunk);
+            }
+        }
+
+        self.entities.restore(entity);
+
+        Ok(())
+    }
+
+    /// Recreates the block entities a chunk was saved with through the blocks they belong to.
+    /// Entities whose block no longer has one are dropped.
+    fn restore_block_entities(chunk: &Chunk, saved: Vec<(BlockPos, Compound)>) -> BlockEntities {
+        let mut entities = BlockEntities::default();
+
+        for (pos, data) in saved {
+            let restored = chunk
+                .get_block(pos.clone())
+                .and_then(|block| block.resolve())
+                .map(|block| block.create_block_entity())
+                .and_then(|entity| {
+                    entity
+                        .map(|mut entity| {
+                            entity.load(&data)?;
+                            Ok(entity)
+                        })
+                        .transpose()
+                });
+
+            match restored {
+                Ok(Some(entity)) => {
+                    entities.insert(&pos, entity);
+                }
+                Ok(None) => error!("Dropping block entity at {:?}, its block doesn't have one", pos),
+                Err(e) => error!("Failed to load block entity at {:?}: {}", pos, e),
+            }
+        }
+
+        entities
+    }
+
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Writes every chunk that was modified since the last save to disk. Returns the number of chunks written.
+    pub fn save_dirty(&mut self) -> anyhow::Result<usize> {
+        //Whatever was taken out and never put back is gone, which changes the chunk it was saved with
+        for (_, (chunk, saved)) in self.taken_entities.drain() {
+            if saved.is_some() {
+                self.dirty.insert(chunk);
+            }
+        }
+
+        for (pos, _) in self.taken_block_entities.drain() {
+            self.dirty.insert(pos.get_chunk().as_long());
+        }
+
+        let chunks: Vec<(ChunkPos, SavedChunk)> = self.dirty
+            .iter()
+            .filter_map(|id| {
+                let pos = ChunkPos::from_long(*id);
+                let saved = SavedChunk {
+                    chunk: self.chunk_map.get(id)?.clone(),
+                    ticks: self.ticks.get(id).cloned().unwrap_or_default(),
+                    block_entities: self.block_entities
+                        .get(id)
+                        .map(|entities| {
+                            entities
+                                .iter(&pos)
+                                .map(|(pos, entity)| (pos, entity.save()))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    entities: self.entities
+                        .in_chunk(&pos)
+                        .filter(|entity| entity.behaviour().is_saved())
+                        .map(Entity::save)
+                        .collect(),
+                };
+
+                Some((pos, saved))
+            })
+            .collect();
+        let count = chunks.len();
+
+        self.file_loader.save_chunks(chunks)?;
+        self.dirty.clear();
+
+        Ok(count)
+    }
+}
 
 impl ChunkStorage for ServerChunkStorage {
     fn is_chunk_cached(&self, pos: &ChunkPos) -> bool {
@@ -47,11 +440,32 @@ impl ChunkStorage for ServerChunkStorage {
         let entry = self.chunk_map.entry(id);
 
         if let Entry::Occupied(_) = &entry {
-            Ok(entry.or_insert_with(Chunk::empty))
-        } else if let Some(chunk) = self.file_loader.get_chunk(pos) {
-            Ok(entry.or_insert(chunk))
+            Ok(entry.or_insert_with(|| Chunk::empty(self.height)))
+        } else if let Some(saved) = self.file_loader.load_chunk(pos)? {
+            if !saved.ticks.is_empty() {
+                self.ticks.insert(id, saved.ticks);
+            }
+
+            let entities = Self::restore_block_entities(&saved.chunk, saved.block_entities);
+
+            if !entities.is_empty() {
+                self.block_entities.insert(id, entities);
+            }
+
+            for data in saved.entities {
+                match Entity::load(&data) {
+                    Ok(entity) => {
+                        self.entities.insert(entity);
+                    }
+                    Err(e) => error!("Failed to load entity in chunk {:?}: {}", pos, e),
+                }
+            }
+
+            Ok(entry.or_insert(saved.chunk))
         } else {
-            Ok(entry.or_insert(self.generator.try_generate(pos)?))
+            //Freshly generated chunks haven't been saved yet
+            self.dirty.insert(id);
+            Ok(entry.or_insert(self.generator.try_generate(pos, self.height)?))
         }
     }
-}
\ No newline at end of file
+}
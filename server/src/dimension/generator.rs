@@ -0,0 +1,45 @@
+use shared::{
+    block::BlockId,
+    dimension::{ chunk::Chunk, height::WorldHeight },
+    util::{ chunk_pos::ChunkPos, block_pos::BlockPos },
+};
+
+pub trait ChunkGenerator {
+    fn try_generate(&self, pos: &ChunkPos, height: WorldHeight) -> anyhow::Result<Chunk>;
+}
+
+/// Generates nothing but air.
+pub struct VoidGenerator;
+
+impl ChunkGenerator for VoidGenerator {
+    fn try_generate(&self, _pos: &ChunkPos, height: WorldHeight) -> anyhow::Result<Chunk> {
+        Ok(Chunk::empty(height))
+    }
+}
+
+/// Fills every chunk with the same horizontal layers, starting at the bottom of the world.
+pub struct FlatGenerator {
+    layers: Vec<BlockId>,
+}
+
+impl FlatGenerator {
+    pub fn new(layers: Vec<BlockId>) -> Self {
+        Self { layers }
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn try_generate(&self, pos: &ChunkPos, height: WorldHeight) -> anyhow::Result<Chunk> {
+        let mut chunk = Chunk::empty(height);
+
+        for (y, block) in (height.min_y()..height.max_y()).zip(self.layers.iter()) {
+            for x in 0..16 {
+                for z in 0..16 {
+                    chunk.set_block(BlockPos::new(pos.x() * 16 + x, y, pos.z() * 16 + z), *block)?;
+                }
+            }
+        }
+
+        Ok(chunk)
+    }
+}
@@ -1,8 +1,6 @@
-use shared::net::NetworkHandler;
-
 pub mod chunk;
-
-pub struct ServerController {
-    net_handler: dyn NetworkHandler
-}
-
+pub mod generator;
+pub mod region;
+pub mod registry;
+pub mod updates;
+pub mod ticks;
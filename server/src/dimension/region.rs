@@ -0,0 +1,227 @@
+use std::{ path::{ Path, PathBuf }, io::{ BufWriter, Write } };
+
+use log::error;
+use metrohash::MetroHashMap;
+use shared::{
+    block::{ registry::BlockRegistry, palette::{ BlockPalette, BlockRemapper } },
+    cbs::{ Packetable, PacketBuf, WriteExt },
+    tag::Compound,
+    dimension::chunk::Chunk,
+    util::{ chunk_pos::ChunkPos, block_pos::BlockPos },
+};
+
+use crate::util::write_atomically;
+
+use super::ticks::ScheduledTick;
+
+pub enum RegionFileError {
+    UnsupportedVersion(u32),
+}
+
+impl From<RegionFileError> for anyhow::Error {
+    fn from(value: RegionFileError) -> Self {
+        match value {
+            RegionFileError::UnsupportedVersion(version) =>
+                anyhow::anyhow!(
+                    "Region file has format version {}, but only versions up to {} are supported",
+                    version,
+                    RegionFile::VERSION
+                ),
+        }
+    }
+}
+
+/// A chunk as it's stored on disk, together with what happens in it beyond its blocks.
+#[derive(Debug, Clone)]
+pub struct SavedChunk {
+    pub chunk: Chunk,
+    pub ticks: Vec<ScheduledTick>,
+    pub block_entities: Vec<(BlockPos, Compound)>, //What each block entity in the chunk saved
+    pub entities: Vec<Compound>,
+}
+
+impl SavedChunk {
+    fn write_block_entities<T: Write + Unpin + Send>(
+        block_entities: Vec<(BlockPos, Compound)>,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u32(block_entities.len() as u32)?;
+
+        for (pos, data) in block_entities {
+            pos.write_to_buffer(buffer)?;
+            data.write_to_buffer(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_entities<T: Write + Unpin + Send>(entities: Vec<Compound>, buffer: &mut BufWriter<T>) -> anyhow::Result<()> {
+        buffer.write_u32(entities.len() as u32)?;
+
+        for entity in entities {
+            entity.write_to_buffer(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_entities(reader: &mut PacketBuf) -> anyhow::Result<Vec<Compound>> {
+        (0..reader.next_u32()?).map(|_| Compound::read_from_buf(reader)).collect()
+    }
+
+    fn read_block_entities(reader: &mut PacketBuf) -> anyhow::Result<Vec<(BlockPos, Compound)>> {
+        (0..reader.next_u32()?)
+            .map(|_| Ok((BlockPos::read_from_buf(reader)?, Compound::read_from_buf(reader)?)))
+            .collect()
+    }
+}
+
+/// A square of 32x32 chunks stored together in one file. Chunks are kept encoded until they are requested.
+/// The file starts with the block palette it was written with; if the running block registry differs, every chunk is
+/// translated to the current ids while loading.
+#[derive(Default)]
+pub struct RegionFile {
+    chunks: MetroHashMap<u16, Box<[u8]>>,
+}
+
+impl RegionFile {
+    pub const VERSION: u32 = 6;
+    pub const SIZE_SHIFT: i32 = 5;
+
+    pub fn region_of(chunk: &ChunkPos) -> (i32, i32) {
+        (chunk.x() >> Self::SIZE_SHIFT, chunk.z() >> Self::SIZE_SHIFT)
+    }
+
+    pub fn path(folder: &Path, region: (i32, i32)) -> PathBuf {
+        folder.join(format!("r.{}.{}.region", region.0, region.1))
+    }
+
+    fn local_index(chunk: &ChunkPos) -> u16 {
+        let mask = (1 << Self::SIZE_SHIFT) - 1;
+        ((chunk.x() & mask) | ((chunk.z() & mask) << Self::SIZE_SHIFT)) as u16
+    }
+
+    /// Reads a region file, or returns an empty region if it doesn't exist yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut reader = PacketBuf::new(std::fs::read(path)?.into_boxed_slice());
+
+        let version = reader.next_u32()?;
+
+        //Version 1 didn't store a palette, its ids are the ones of the registry at the time.
+        //Version 4 was never released, so there are no files with it
+        let palette = match version {
+            1 => None,
+            2 | 3 | 5 | 6 => Some(BlockPalette::read_from_buf(&mut reader)?),
+            _ => {
+                return Err(RegionFileError::UnsupportedVersion(version).into());
+            }
+        };
+
+        let mut chunks = MetroHashMap::default();
+
+        for _ in 0..reader.next_u16()? {
+            let index = reader.next_u16()?;
+            let len = reader.next_u32()? as usize;
+            let mut data = reader.next_n_bytes(len)?.to_vec();
+
+            //Before version 3 chunks had no scheduled ticks, which is the same as an empty list
+            if version < 3 {
+                data.extend_from_slice(&0u32.to_le_bytes());
+            }
+
+            //Same for block entities before version 5
+            if version < 5 {
+                data.extend_from_slice(&0u32.to_le_bytes());
+            }
+
+            //And for entities before version 6
+            if version < 6 {
+                data.extend_from_slice(&0u32.to_le_bytes());
+            }
+
+            chunks.insert(index, data.into_boxed_slice());
+        }
+
+        let mut file = Self { chunks };
+        let registry = BlockRegistry::global()?;
+
+        if let Some(palette) = palette.filter(|palette| palette != registry.palette()) {
+            file.remap(path, &BlockRemapper::new(&palette, registry), registry)?;
+        }
+
+        Ok(file)
+    }
+
+    fn remap(&mut self, path: &Path, remapper: &BlockRemapper, registry: &BlockRegistry) -> anyhow::Result<()> {
+        let fallback = registry.state_name(remapper.fallback()).unwrap_or_default();
+
+        for name in remapper.missing() {
+            error!("Block {} in {:?} no longer exists, replacing it with {}", name, path, fallback);
+        }
+
+        for data in self.chunks.values_mut() {
+            let mut saved = Self::decode(data)?;
+            saved.chunk.remap(remapper);
+            saved.ticks.iter_mut().for_each(|tick| tick.remap(remapper));
+
+            *data = Self::encode(saved)?;
+        }
+
+        Ok(())
+    }
+
+    fn decode(data: &[u8]) -> anyhow::Result<SavedChunk> {
+        let mut reader = PacketBuf::new(data.into());
+
+        Ok(SavedChunk {
+            chunk: Chunk::read_from_buf(&mut reader)?,
+            ticks: ScheduledTick::read_list(&mut reader)?,
+            block_entities: SavedChunk::read_block_entities(&mut reader)?,
+            entities: SavedChunk::read_entities(&mut reader)?,
+        })
+    }
+
+    fn encode(saved: SavedChunk) -> anyhow::Result<Box<[u8]>> {
+        let mut writer = BufWriter::new(Vec::new());
+        saved.chunk.write_to_buffer(&mut writer)?;
+        ScheduledTick::write_list(&saved.ticks, &mut writer)?;
+        SavedChunk::write_block_entities(saved.block_entities, &mut writer)?;
+        SavedChunk::write_entities(saved.entities, &mut writer)?;
+
+        Ok(writer.into_inner()?.into_boxed_slice())
+    }
+
+    /// The chunk at `pos` together with the ticks that were scheduled in it, its block entities and entities.
+    pub fn get_chunk(&self, pos: &ChunkPos) -> anyhow::Result<Option<SavedChunk>> {
+        self.chunks
+            .get(&Self::local_index(pos))
+            .map(|data| Self::decode(data))
+            .transpose()
+    }
+
+    pub fn set_chunk(&mut self, pos: &ChunkPos, saved: SavedChunk) -> anyhow::Result<()> {
+        self.chunks.insert(Self::local_index(pos), Self::encode(saved)?);
+
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(Vec::new());
+
+        writer.write_u32(Self::VERSION)?;
+        BlockRegistry::global()?.palette().clone().write_to_buffer(&mut writer)?;
+        writer.write_u16(self.chunks.len() as u16)?;
+
+        for (index, data) in &self.chunks {
+            writer.write_u16(*index)?;
+            writer.write_u32(data.len() as u32)?;
+            writer.write_all(data)?;
+        }
+
+        write_atomically(path, &writer.into_inner()?)
+    }
+}
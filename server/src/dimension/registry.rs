@@ -0,0 +1,777 @@
+use std::{ path::Path, collections::btree_map::{ BTreeMap, Entry } };
+
+use anyhow::anyhow;
+use log::error;
+use glam::{ Vec2, Vec3 };
+use metrohash::{ MetroHashSet, MetroHashMap };
+use shared::{
+    block::{
+        BlockId,
+        simple::{ GrassState, DirtState },
+        state::BlockHandler,
+        context::{ PlacementContext, UseContext },
+        entity::BlockEntity,
+    },
+    dimension::{
+        id::DimensionId,
+        settings::DimensionSettings,
+        access::WorldAccess,
+        height::WorldHeight,
+        storage::ChunkStorage,
+    },
+    util::{ block_pos::BlockPos, chunk_pos::ChunkPos, aabb::Aabb },
+    tag::Compound,
+    entity::{ id::EntityId, player::{ self, PlayerMove, PlayerTeleport, PlaceBlock } },
+    item::{ inventory::{ PlayerInventory, SlotUpdate }, stack::ItemStack },
+    net::{
+        packet::{ Packet, ClientId, PacketDirection },
+        packet_data::PacketData,
+        NetworkHandler,
+    },
+};
+
+use crate::{
+    util::Random,
+    entity::{
+        Entity,
+        EntityError,
+        EntityState,
+        tracker::{ EntityTracker, Viewer },
+        store::EntityIds,
+        player::{ PlayerEntity, MoveOutcome, PlaceOutcome },
+    },
+};
+
+use super::{
+    chunk::{ ServerChunkStorage, DiskChunkLoader },
+    updates::NeighborUpdateQueue,
+    ticks::ScheduledTick,
+    generator::{ ChunkGenerator, FlatGenerator, VoidGenerator },
+};
+
+pub struct ServerDimension {
+    id: DimensionId,
+    name: String,
+    settings: DimensionSettings,
+    chunks: ServerChunkStorage,
+    updates: NeighborUpdateQueue,
+    time: u64,
+    random: Random,
+    changes: MetroHashSet<BlockPos>, //Blocks that changed since clients were last told
+    block_entity_changes: MetroHashSet<BlockPos>, //Same for block entities, including ones that were removed
+    tracker: EntityTracker,
+}
+
+impl ServerDimension {
+    /// How many neighbour updates are delivered per tick at most.
+    pub const UPDATE_BUDGET: usize = 4096;
+
+    /// Creates a dimension whose chunks are saved in a subfolder of the world named after it.
+    pub fn new(
+        id: DimensionId,
+        name: &str,
+        settings: DimensionSettings,
+        world_folder: &Path,
+        generator: Box<dyn ChunkGenerator>,
+        seed: u64,
+        entity_ids: EntityIds
+    ) -> Self {
+        let loader = DiskChunkLoader::new(&world_folder.join(name));
+
+        Self {
+            id,
+            name: name.to_string(),
+            settings,
+            chunks: ServerChunkStorage::new(settings.height, loader, generator, entity_ids),
+            updates: NeighborUpdateQueue::default(),
+            time: 0,
+            random: Random::new(seed ^ (id.0 as u64)),
+            changes: MetroHashSet::default(),
+            block_entity_changes: MetroHashSet::default(),
+            tracker: EntityTracker::default(),
+        }
+    }
+
+    pub fn id(&self) -> DimensionId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn settings(&self) -> DimensionSettings {
+        self.settings
+    }
+
+    pub fn chunks(&self) -> &ServerChunkStorage {
+        &self.chunks
+    }
+
+    pub fn chunks_mut(&mut self) -> &mut ServerChunkStorage {
+        &mut self.chunks
+    }
+
+    pub fn pending_updates(&self) -> usize {
+        self.updates.len()
+    }
+
+    /// Every block that changed since the last call, with its current state. Blocks that changed back
+    /// and forth in the meantime are still included, since clients may have seen the state in between.
+    pub fn take_changes(&mut self) -> anyhow::Result<Vec<(BlockPos, BlockId)>> {
+        let changes = std::mem::take(&mut self.changes);
+
+        changes
+            .into_iter()
+            .map(|pos| Ok((pos.clone(), self.chunks.get_chunk(&pos.get_chunk())?.get_block(pos)?)))
+            .collect()
+    }
+
+    /// What clients get to see of every block entity that changed since the last call. Removed block entities come with
+    /// empty data, so clients know to forget about them.
+    pub fn take_block_entity_changes(&mut self) -> Vec<(BlockPos, Compound)> {
+        std::mem::take(&mut self.block_entity_changes)
+            .into_iter()
+            .filter_map(|pos| {
+                let data = match self.chunks.block_entity(&pos) {
+                    Some(entity) => entity.client_data()?,
+                    None => Compound::new(),
+                };
+
+                Some((pos, data))
+            })
+            .collect()
+    }
+
+    /// The entity packets each of `viewers` needs to stay up to date, see `EntityTracker::update`.
+    pub fn track_entities(&mut self, viewers: &[Viewer]) -> Vec<(ClientId, PacketData)> {
+        self.tracker.update(self.id, self.chunks.entities(), viewers)
+    }
+
+    /// Runs everything that happens in the dimension on its own at world time `time`: scheduled ticks, block entities,
+    /// entities, random ticks and the neighbour updates they cause. `random_tick_speed` is the number of blocks picked
+    /// per section. Whatever fails is logged and skipped, one misbehaving block shouldn't stop the rest of the
+    /// dimension.
+    pub fn tick(&mut self, time: u64, random_tick_speed: u32) {
+        self.time = time;
+        self.updates.start_tick();
+
+        for tick in self.chunks.take_due_ticks(time) {
+            let pos = tick.pos.clone();
+
+            let result = self.get_block(tick.pos.clone()).and_then(|block| {
+                if block == tick.block { self.scheduled_tick(tick.pos) } else { Ok(()) }
+            });
+
+            if let Err(e) = result {
+                error!("Failed to run the scheduled tick at {:?} in {}: {}", pos, self.name, e);
+            }
+        }
+
+        self.tick_block_entities();
+        self.tick_entities();
+        self.random_ticks(random_tick_speed);
+
+        if let Err(e) = self.process_updates(Self::UPDATE_BUDGET) {
+            error!("Failed to process block updates in {}: {}", self.name, e);
+        }
+    }
+
+    /// Everything a client needs to show the chunk at `pos`: the chunk itself, then what clients get to see of its
+    /// block entities. Loads the chunk if necessary.
+    pub fn chunk_packets(&mut self, pos: &ChunkPos) -> anyhow::Result<Vec<PacketData>> {
+        let chunk = self.chunks.get_chunk(pos)?.clone();
+        let mut packets = vec![PacketData::ChunkData(self.id, pos.clone(), chunk)];
+
+        packets.extend(
+            self.chunks
+                .block_entities_in(pos)
+                .filter_map(|(block, entity)| Some(PacketData::BlockEntityData(self.id, block, entity.client_data()?)))
+        );
+
+        Ok(packets)
+    }
+
+    pub fn spawn_entity(&mut self, entity: Entity) -> anyhow::Result<EntityId> {
+        self.chunks.spawn_entity(entity)
+    }
+
+    /// Adds a player at `pos`. The client has to be sent the returned teleport; its moves are ignored until it
+    /// confirms it.
+    pub fn spawn_player(&mut self, pos: Vec3) -> anyhow::Result<(EntityId, PlayerTeleport)> {
+        let mut entity = Entity::new(Box::<PlayerEntity>::default(), pos);
+        let (state, behaviour) = entity.split_mut();
+        let player = behaviour.downcast_mut::<PlayerEntity>().expect("The entity was just created as a player.");
+        let teleport = player.teleport(state, pos, Vec2::ZERO);
+
+        Ok((self.spawn_entity(entity)?, teleport))
+    }
+
+    /// Removes a player whose client left. Clients that see it are told it's gone with the next entity updates.
+    pub fn despawn_player(&mut self, id: EntityId) -> anyhow::Result<()> {
+        let entity = self.chunks.take_entity(id).ok_or(EntityError::Missing(id))?;
+
+        if entity.behaviour().downcast_ref::<PlayerEntity>().is_none() {
+            self.chunks.restore_entity(entity)?;
+            return Err(EntityError::NotAPlayer(id).into());
+        }
+
+        Ok(())
+    }
+
+    /// Runs `f` on a player and the chunks around it. The player is taken out of its chunk meanwhile, so it may
+    /// end up in another one.
+    pub fn with_player<R>(
+        &mut self,
+        id: EntityId,
+        f: impl FnOnce(&mut PlayerEntity, &mut EntityState, &mut ServerChunkStorage) -> anyhow::Result<R>
+    ) -> anyhow::Result<R> {
+        let mut entity = self.chunks.take_entity(id).ok_or(EntityError::Missing(id))?;
+        let (state, behaviour) = entity.split_mut();
+
+        let result = match behaviour.downcast_mut::<PlayerEntity>() {
+            Some(player) => f(player, state, &mut self.chunks),
+            None => Err(EntityError::NotAPlayer(id).into()),
+        };
+
+        //Put back even if `f` failed, the player shouldn't vanish over it
+        self.chunks.restore_entity(entity)?;
+
+        result
+    }
+
+    /// Checks a move a client sent for its player, see `PlayerEntity::handle_move`.
+    pub fn move_player(&mut self, id: EntityId, movement: PlayerMove) -> anyhow::Result<MoveOutcome> {
+        self.with_player(id, |player, state, chunks| player.handle_move(state, chunks, movement))
+    }
+
+    pub fn confirm_teleport(&mut self, id: EntityId, teleport: u32) -> anyhow::Result<bool> {
+        self.with_player(id, |player, _, _| Ok(player.confirm_teleport(teleport)))
+    }
+
+    /// Returns whether the player may start breaking the block at `pos`, see `PlayerEntity::start_digging`.
+    pub fn start_digging(&mut self, id: EntityId, pos: BlockPos) -> anyhow::Result<bool> {
+        let time = self.time;
+
+        self.with_player(id, |player, state, chunks| player.start_digging(state, chunks, pos, time))
+    }
+
+    pub fn abort_digging(&mut self, id: EntityId, pos: &BlockPos) -> anyhow::Result<()> {
+        self.with_player(id, |player, _, _| {
+            player.abort_digging(pos);
+            Ok(())
+        })
+    }
+
+    /// Breaks the block at `pos` if the player has been digging at it for long enough. Returns whether it did.
+    pub fn finish_digging(&mut self, id: EntityId, pos: BlockPos) -> anyhow::Result<bool> {
+        let time = self.time;
+        let broken = self.with_player(id, |player, state, chunks| player.finish_digging(state, chunks, &pos, time))?;
+
+        if broken {
+            self.set_block(pos, BlockId::default())?;
+        }
+
+        Ok(broken)
+    }
+
+    /// A player clicking a block: the block is used if it reacts to that, otherwise the block item the player holds
+    /// is placed, if there's room for it. The block has to be in reach, and the one it replaces replaceable.
+    /// Placing takes one item off the held stack.
+    pub fn use_held_item(&mut self, id: EntityId, place: PlaceBlock) -> anyhow::Result<PlaceOutcome> {
+        let (reachable, look, held) = self.with_player(id, |player, state, _| {
+            let held = player.inventory().selected_stack().cloned();
+
+            Ok((PlayerEntity::can_reach(state, &place.pos), player::look_direction(state.rotation), held))
+        })?;
+
+        let valid_hit = place.hit.cmpge(Vec3::ZERO).all() && place.hit.cmple(Vec3::ONE).all();
+
+        if !reachable || !valid_hit || !self.settings.height.contains_y(place.pos.y()) {
+            return Ok(PlaceOutcome::Rejected);
+        }
+
+        if self.use_block(place.pos.clone(), &UseContext { face: place.face, hit: place.hit })? {
+            return Ok(PlaceOutcome::Used);
+        }
+
+        let Some(block) = held.and_then(|stack| stack.item_type().ok()?.block()) else {
+            return Ok(PlaceOutcome::Rejected);
+        };
+
+        //Clicking something replaceable, like snow, or something the block merges with, like a slab, places the
+        //block right there instead of next to it
+        let mut context = PlacementContext { pos: place.pos.clone(), block, face: place.face, hit: place.hit, look };
+        let clicked = self.get_block(place.pos.clone())?.resolve()?;
+        let merges = clicked.can_merge(&context);
+
+        let target = if merges || clicked.is_replaceable(place.pos.clone()) {
+            place.pos.clone()
+        } else {
+            place.pos.offset(place.face)
+        };
+
+        if
+            !self.settings.height.contains_y(target.y()) ||
+            (!merges && !self.get_block(target.clone())?.resolve()?.is_replaceable(target.clone()))
+        {
+            return Ok(PlaceOutcome::Rejected);
+        }
+
+        context.pos = target.clone();
+
+        if self.place_block(&context)?.is_none() {
+            return Ok(PlaceOutcome::Rejected);
+        }
+
+        let update = self.with_player(id, |player, _, _| {
+            let inventory = player.inventory_mut();
+            ItemStack::take(inventory.selected_slot_mut(), 1);
+
+            Ok(SlotUpdate {
+                window: PlayerInventory::WINDOW,
+                slot: inventory.selected() as u16,
+                stack: inventory.selected_stack().cloned(),
+            })
+        })?;
+
+        Ok(PlaceOutcome::Placed(target, update))
+    }
+
+    fn tick_entities(&mut self) {
+        for id in self.chunks.entities().ids() {
+            if let Err(e) = self.tick_entity(id) {
+                error!("Failed to tick entity {:?} in {}: {}", id, self.name, e);
+            }
+        }
+    }
+
+    fn tick_entity(&mut self, id: EntityId) -> anyhow::Result<()> {
+        //Taken out while it ticks like block entities, which also lets it move to another chunk
+        let Some(mut entity) = self.chunks.take_entity(id) else {
+            return Ok(());
+        };
+
+        let result = entity.tick(self);
+
+        if !entity.is_removed() {
+            self.chunks.restore_entity(entity)?;
+        }
+
+        result
+    }
+
+    fn tick_block_entities(&mut self) {
+        for pos in self.chunks.ticking_block_entities() {
+            if let Err(e) = self.tick_block_entity(&pos) {
+                error!("Failed to tick the block entity at {:?} in {}: {}", pos, self.name, e);
+            }
+        }
+    }
+
+    fn tick_block_entity(&mut self, pos: &BlockPos) -> anyhow::Result<()> {
+        //Looked up before the entity is taken out, so failing here leaves it where it is
+        let block_type = self.get_block(pos.clone())?.resolve()?.block_type().id();
+
+        //Taken out while it ticks, so it can get at the world it's in
+        let Some(mut entity) = self.chunks.take_block_entity(pos) else {
+            return Ok(());
+        };
+
+        let client_data = entity.client_data();
+        let result = entity.tick(self, pos);
+
+        //If the entity replaced its own block, it went away together with it. If that can't be told, it's kept.
+        let replaced = match self.get_block(pos.clone()).and_then(|block| Ok(block.resolve()?.block_type().id())) {
+            Ok(current) => current != block_type,
+            Err(e) => {
+                error!("Failed to look up the block of the block entity at {:?} in {}: {}", pos, self.name, e);
+                false
+            }
+        };
+
+        if !replaced && self.chunks.block_entity(pos).is_none() {
+            if entity.client_data() != client_data {
+                self.block_entity_changes.insert(pos.clone());
+            }
+
+            self.chunks.restore_block_entity(pos, entity)?;
+        }
+
+        result
+    }
+
+    /// Swaps out the block entity at `pos` when a block of another type is set there.
+    fn replace_block_entity(&mut self, pos: &BlockPos, previous: BlockId, block: BlockId) -> anyhow::Result<()> {
+        let block = block.resolve()?;
+
+        if previous.resolve()?.block_type().id() == block.block_type().id() {
+            return Ok(());
+        }
+
+        if let Some(entity) = self.chunks.remove_block_entity(pos) {
+            if entity.client_data().is_some() {
+                self.block_entity_changes.insert(pos.clone());
+            }
+        }
+
+        if let Some(entity) = block.create_block_entity() {
+            if entity.client_data().is_some() {
+                self.block_entity_changes.insert(pos.clone());
+            }
+
+            self.chunks.set_block_entity(pos, entity)?;
+        }
+
+        Ok(())
+    }
+
+    fn random_ticks(&mut self, random_tick_speed: u32) {
+        if random_tick_speed == 0 {
+            return;
+        }
+
+        for chunk_pos in self.chunks.loaded_chunks() {
+            let sections: Vec<i32> = match self.chunks.get_chunk(&chunk_pos) {
+                Ok(chunk) => chunk.sections().collect(),
+                Err(e) => {
+                    error!("Failed to random tick chunk {:?} in {}: {}", chunk_pos, self.name, e);
+                    continue;
+                }
+            };
+
+            for section in sections {
+                for _ in 0..random_tick_speed {
+                    let (x, y, z) = (self.random(16) as i32, self.random(16) as i32, self.random(16) as i32);
+                    let pos = BlockPos::new(chunk_pos.x() * 16 + x, section * 16 + y, chunk_pos.z() * 16 + z);
+
+                    if let Err(e) = self.random_tick(pos.clone()) {
+                        error!("Failed to run the random tick at {:?} in {}: {}", pos, self.name, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Delivers up to `budget` queued neighbour updates, including the ones they cause in turn.
+    /// Whatever is left over stays queued for the next tick. Returns the number of updates delivered.
+    pub fn process_updates(&mut self, budget: usize) -> anyhow::Result<usize> {
+        let mut delivered = 0;
+
+        while delivered < budget {
+            let Some((pos, direction)) = self.updates.next() else {
+                break;
+            };
+
+            //Updates don't load chunks, otherwise a change at the border of the loaded area would load its neighbours
+            if !self.settings.height.contains_y(pos.y()) || !self.is_loaded(&pos) {
+                continue;
+            }
+
+            let block = self.get_block(pos.clone())?;
+            block.resolve()?.on_neighbor_changed(self, pos, direction)?;
+            delivered += 1;
+        }
+
+        Ok(delivered)
+    }
+
+    /// Places a block the way a player would, letting it pick its state first. Returns the state that was placed, or
+    /// `None` if nothing was placed because that would change nothing or an entity is in the way.
+    pub fn place_block(&mut self, context: &PlacementContext) -> anyhow::Result<Option<BlockId>> {
+        let block = context.block.resolve()?;
+        let state = block.get_placement_state(self, context)?;
+        let placed = state.resolve()?;
+
+        //Blocks that can't be placed, like a door without room for its top half, leave what's there
+        if placed.block_type().id() != block.block_type().id() || state == self.get_block(context.pos.clone())? {
+            return Ok(None);
+        }
+
+        let blocked = placed
+            .collision_shape()
+            .at(&context.pos)
+            .any(|aabb| self.chunks.entities().in_box(&aabb).next().is_some());
+
+        if blocked {
+            return Ok(None);
+        }
+
+        self.set_block(context.pos.clone(), state)?;
+
+        Ok(Some(state))
+    }
+
+    /// Returns whether the block reacted to being used.
+    pub fn use_block(&mut self, pos: BlockPos, context: &UseContext) -> anyhow::Result<bool> {
+        self.get_block(pos.clone())?.resolve()?.on_use(self, pos, context)
+    }
+
+    pub fn random_tick(&mut self, pos: BlockPos) -> anyhow::Result<()> {
+        self.get_block(pos.clone())?.resolve()?.on_random_tick(self, pos)
+    }
+
+    pub fn scheduled_tick(&mut self, pos: BlockPos) -> anyhow::Result<()> {
+        self.get_block(pos.clone())?.resolve()?.on_scheduled_tick(self, pos)
+    }
+}
+
+impl WorldAccess for ServerDimension {
+    fn height(&self) -> WorldHeight {
+        self.settings.height
+    }
+
+    fn is_loaded(&self, pos: &BlockPos) -> bool {
+        self.chunks.is_chunk_cached(&pos.get_chunk())
+    }
+
+    fn get_block(&mut self, pos: BlockPos) -> anyhow::Result<BlockId> {
+        self.chunks.get_chunk(&pos.get_chunk())?.get_block(pos)
+    }
+
+    fn set_block(&mut self, pos: BlockPos, block: BlockId) -> anyhow::Result<()> {
+        let previous = self.get_block(pos.clone())?;
+
+        if previous == block {
+            return Ok(());
+        }
+
+        self.chunks.set_block(pos.clone(), block)?;
+        self.changes.insert(pos.clone());
+
+        previous.resolve()?.on_broken(self, pos.clone())?;
+        self.replace_block_entity(&pos, previous, block)?;
+        block.resolve()?.on_placed(self, pos.clone(), previous)?;
+
+        self.updates.notify_neighbors(&pos);
+
+        Ok(())
+    }
+
+    fn block_entity_mut(&mut self, pos: &BlockPos) -> anyhow::Result<Option<&mut dyn BlockEntity>> {
+        self.chunks.get_chunk(&pos.get_chunk())?;
+
+        let entity = self.chunks.block_entity_mut(pos);
+
+        if entity.is_some() {
+            self.block_entity_changes.insert(pos.clone());
+        }
+
+        Ok(entity)
+    }
+
+    fn schedule_tick(&mut self, pos: BlockPos, delay: u64, priority: i8) -> anyhow::Result<()> {
+        let block = self.get_block(pos.clone())?;
+
+        self.chunks.schedule_tick(ScheduledTick { pos, block, due: self.time + delay, priority })
+    }
+
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>> {
+        self.chunks.collision_boxes(area)
+    }
+
+    fn random(&mut self, bound: u32) -> u32 {
+        self.random.next_bounded(bound)
+    }
+}
+
+pub enum DimensionRegistryError {
+    DuplicateId(DimensionId, String),
+    UnknownDimension(DimensionId),
+}
+
+impl From<DimensionRegistryError> for anyhow::Error {
+    fn from(value: DimensionRegistryError) -> Self {
+        match value {
+            DimensionRegistryError::DuplicateId(id, name) =>
+                anyhow!("Dimension id {} is already taken by dimension {}", id.0, name),
+            DimensionRegistryError::UnknownDimension(id) =>
+                anyhow!("There is no dimension with id {}", id.0),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DimensionRegistry {
+    dimensions: BTreeMap<DimensionId, ServerDimension>,
+    entity_ids: EntityIds,
+}
+
+impl DimensionRegistry {
+    /// How far away from their player clients are sent block changes, horizontally.
+    pub const BLOCK_UPDATE_RANGE: f32 = 128.0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An overworld, nether and end, each with their own storage in `world_folder`.
+    pub fn with_defaults(world_folder: &Path, seed: u64) -> anyhow::Result<Self> {
+        let grass = BlockId::of(&GrassState::NORMAL)?;
+        let dirt = BlockId::of(&DirtState)?;
+        let mut registry = Self::new();
+
+        let defaults: [(DimensionId, &str, DimensionSettings, Box<dyn ChunkGenerator>); 3] = [
+            (
+                DimensionId::OVERWORLD,
+                "overworld",
+                DimensionSettings::overworld(),
+                Box::new(FlatGenerator::new(vec![dirt, dirt, dirt, grass])),
+            ),
+            (
+                DimensionId::NETHER,
+                "nether",
+                DimensionSettings::nether(),
+                Box::new(FlatGenerator::new(vec![grass])),
+            ),
+            (DimensionId::END, "end", DimensionSettings::end(), Box::new(VoidGenerator)),
+        ];
+
+        for (id, name, settings, generator) in defaults {
+            let entity_ids = registry.entity_ids().clone();
+            registry.register(ServerDimension::new(id, name, settings, world_folder, generator, seed, entity_ids))?;
+        }
+
+        Ok(registry)
+    }
+
+    /// The ids every dimension in the registry should give its entities, so they're unique across all of them.
+    pub fn entity_ids(&self) -> &EntityIds {
+        &self.entity_ids
+    }
+
+    pub fn register(&mut self, dimension: ServerDimension) -> anyhow::Result<()> {
+        match self.dimensions.entry(dimension.id()) {
+            Entry::Occupied(existing) =>
+                Err(
+                    DimensionRegistryError::DuplicateId(
+                        dimension.id(),
+                        existing.get().name().to_string()
+                    ).into()
+                ),
+            Entry::Vacant(entry) => {
+                entry.insert(dimension);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get(&self, id: DimensionId) -> Option<&ServerDimension> {
+        self.dimensions.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: DimensionId) -> Option<&mut ServerDimension> {
+        self.dimensions.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ServerDimension> {
+        self.dimensions.values()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ServerDimension> {
+        self.dimensions.values_mut()
+    }
+
+    /// Sends every block and block entity change since the last call to the clients whose player is in the same
+    /// dimension and within `BLOCK_UPDATE_RANGE` of it.
+    pub fn broadcast_changes<N: NetworkHandler>(
+        &mut self,
+        net_handler: &N,
+        clients: &MetroHashMap<ClientId, DimensionId>,
+        players: &MetroHashMap<ClientId, EntityId>
+    ) -> anyhow::Result<()> {
+        for dimension in self.dimensions.values_mut() {
+            let changes = dimension.take_changes()?;
+            let block_entity_changes = dimension.take_block_entity_changes();
+
+            if changes.is_empty() && block_entity_changes.is_empty() {
+                continue;
+            }
+
+            let entities = dimension.chunks().entities();
+
+            let receivers = clients
+                .iter()
+                .filter(|(_, client_dimension)| **client_dimension == dimension.id())
+                .filter_map(|(client, _)| Some((client, players.get(client).and_then(|id| entities.get(*id))?.pos())));
+
+            for (client, center) in receivers {
+                let changes = changes.iter().filter(|(pos, _)| Self::is_nearby(pos, center));
+                let block_entity_changes = block_entity_changes.iter().filter(|(pos, _)| Self::is_nearby(pos, center));
+
+                for (pos, block) in changes {
+                    net_handler.enqueue_packet(
+                        Packet::new(
+                            PacketDirection::ToClient(client.clone()),
+                            PacketData::BlockUpdate(dimension.id(), pos.clone(), *block)
+                        )
+                    )?;
+                }
+
+                //After the blocks, so the blocks the data belongs to are already there
+                for (pos, data) in block_entity_changes {
+                    net_handler.enqueue_packet(
+                        Packet::new(
+                            PacketDirection::ToClient(client.clone()),
+                            PacketData::BlockEntityData(dimension.id(), pos.clone(), data.clone())
+                        )
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `pos` is within `BLOCK_UPDATE_RANGE` of `center`, horizontally.
+    pub fn is_nearby(pos: &BlockPos, center: Vec3) -> bool {
+        let offset = Vec3::from(pos.clone()) + Vec3::splat(0.5) - center;
+
+        Vec2::new(offset.x, offset.z).length_squared() <= Self::BLOCK_UPDATE_RANGE.powi(2)
+    }
+
+    /// Sends each client the entities around its player, and what they did since the last call.
+    pub fn broadcast_entities<N: NetworkHandler>(
+        &mut self,
+        net_handler: &N,
+        clients: &MetroHashMap<ClientId, DimensionId>,
+        players: &MetroHashMap<ClientId, EntityId>
+    ) -> anyhow::Result<()> {
+        for dimension in self.dimensions.values_mut() {
+            let entities = dimension.chunks().entities();
+
+            let viewers: Vec<Viewer> = clients
+                .iter()
+                .filter(|(_, client_dimension)| **client_dimension == dimension.id())
+                .filter_map(|(client, _)| {
+                    let player = players.get(client).and_then(|id| entities.get(*id))?;
+
+                    Some(Viewer { client: client.clone(), center: player.pos(), player: Some(player.id()) })
+                })
+                .collect();
+
+            for (client, data) in dimension.track_entities(&viewers) {
+                net_handler.enqueue_packet(Packet::new(PacketDirection::ToClient(client), data))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tells a client to drop all of its chunks and switch over to another dimension.
+    pub fn move_client<N: NetworkHandler>(
+        &self,
+        net_handler: &N,
+        client: ClientId,
+        to: DimensionId
+    ) -> anyhow::Result<()> {
+        let dimension = self.get(to).ok_or(DimensionRegistryError::UnknownDimension(to))?;
+
+        net_handler.enqueue_packet(
+            Packet::new(
+                PacketDirection::ToClient(client),
+                PacketData::ChangeDimension(dimension.id(), dimension.settings())
+            )
+        )
+    }
+}
@@ -0,0 +1,62 @@
+use std::io::{ BufWriter, Write };
+
+use shared::{
+    block::{ BlockId, palette::BlockRemapper },
+    cbs::{ Packetable, PacketBuf, WriteExt },
+    util::block_pos::BlockPos,
+};
+
+/// A block asking to have its `on_scheduled_tick` hook run at a certain world time. Saved together with the chunk it's in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledTick {
+    pub pos: BlockPos,
+    pub block: BlockId, //If the block was replaced in the meantime, the tick is dropped
+    pub due: u64,
+    pub priority: i8, //Of the ticks due at the same time, lower priorities run first
+}
+
+impl ScheduledTick {
+    pub fn remap(&mut self, remapper: &BlockRemapper) {
+        self.block = remapper.remap(self.block);
+    }
+
+    pub fn write_list<T: Write + Unpin + Send>(
+        ticks: &[ScheduledTick],
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u32(ticks.len() as u32)?;
+
+        for tick in ticks {
+            tick.clone().write_to_buffer(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_list(reader: &mut PacketBuf) -> anyhow::Result<Vec<ScheduledTick>> {
+        (0..reader.next_u32()?).map(|_| Self::read_from_buf(reader)).collect()
+    }
+}
+
+impl Packetable for ScheduledTick {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        self.pos.write_to_buffer(buffer)?;
+        self.block.write_to_buffer(buffer)?;
+        buffer.write_u64(self.due)?;
+        buffer.write_u8(self.priority as u8)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        Ok(Self {
+            pos: BlockPos::read_from_buf(reader)?,
+            block: BlockId::read_from_buf(reader)?,
+            due: reader.next_u64()?,
+            priority: reader.next_byte()? as i8,
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+use log::warn;
+use metrohash::{ MetroHashMap, MetroHashSet };
+use shared::util::{ block_pos::BlockPos, direction::Direction };
+
+/// Neighbour notifications that still have to be delivered. Changing a block queues one update for each of its six
+/// neighbours instead of running their hooks right away, so chain reactions are spread out over as many ticks as needed.
+#[derive(Default)]
+pub struct NeighborUpdateQueue {
+    queue: VecDeque<(BlockPos, Direction)>,
+    pending: MetroHashSet<(BlockPos, Direction)>,
+    delivered: MetroHashMap<BlockPos, u32>, //How often each block was updated this tick
+}
+
+impl NeighborUpdateQueue {
+    /// How many updates a single block may receive per tick. Anything past that is most likely two blocks
+    /// toggling each other forever, so further updates are dropped.
+    pub const MAX_UPDATES_PER_BLOCK: u32 = 16;
+
+    /// Queues an update for every neighbour of `pos`, in `Direction::LOOKUP` order.
+    pub fn notify_neighbors(&mut self, pos: &BlockPos) {
+        for direction in Direction::LOOKUP {
+            //The neighbour is told which side the change came from
+            let update = (pos.offset(direction), direction.opposite());
+
+            //The same update being queued twice carries no new information
+            if self.pending.insert(update.clone()) {
+                self.queue.push_back(update);
+            }
+        }
+    }
+
+    /// The next update that should be delivered, skipping any for blocks that have used up their updates this tick.
+    pub fn next(&mut self) -> Option<(BlockPos, Direction)> {
+        while let Some(update) = self.queue.pop_front() {
+            self.pending.remove(&update);
+
+            let count = self.delivered.entry(update.0.clone()).or_default();
+            *count += 1;
+
+            if *count <= Self::MAX_UPDATES_PER_BLOCK {
+                return Some(update);
+            } else if *count == Self::MAX_UPDATES_PER_BLOCK + 1 {
+                warn!(
+                    "Block at {:?} was updated more than {} times in one tick, dropping further updates",
+                    update.0,
+                    Self::MAX_UPDATES_PER_BLOCK
+                );
+            }
+        }
+
+        None
+    }
+
+    /// Resets the per-block limits. Called at the start of every tick.
+    pub fn start_tick(&mut self) {
+        self.delivered.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
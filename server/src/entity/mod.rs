@@ -0,0 +1,286 @@
+pub mod store;
+pub mod tracker;
+pub mod player;
+
+use std::{ any::Any, fmt::Debug };
+
+use anyhow::anyhow;
+use glam::{ Vec2, Vec3 };
+use metrohash::MetroHashMap;
+use once_cell::sync::Lazy;
+use shared::{
+    dimension::access::WorldAccess,
+    entity::{ id::EntityId, bounding_box, physics::{ self, Body, PhysicsSettings } },
+    tag::{ Compound, Tag },
+    util::{ aabb::Aabb, chunk_pos::ChunkPos },
+};
+use uuid::Uuid;
+
+pub enum EntityError {
+    UnknownKind(String),
+    InvalidVector(String),
+    Missing(EntityId),
+    NotAPlayer(EntityId),
+}
+
+impl From<EntityError> for anyhow::Error {
+    fn from(value: EntityError) -> Self {
+        match value {
+            EntityError::UnknownKind(kind) => anyhow!("There is no entity kind named {}", kind),
+            EntityError::InvalidVector(key) => anyhow!("Entity data has no valid vector named {}", key),
+            EntityError::Missing(id) => anyhow!("There is no entity with id {}", id.0),
+            EntityError::NotAPlayer(id) => anyhow!("Entity {} is not a player", id.0),
+        }
+    }
+}
+
+/// What all entities have in common, kept apart from their behaviour so the behaviour can change it while ticking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityState {
+    pub pos: Vec3, //At the bottom center of the bounding box
+    pub velocity: Vec3, //In blocks per tick
+    pub rotation: Vec2, //Yaw and pitch, in degrees
+    pub on_ground: bool,
+    pub removed: bool, //Set to have the entity go away at the end of its tick
+}
+
+/// What makes one kind of entity different from the others.
+pub trait EntityBehaviour: Debug + Send + Sync + Any {
+    /// The name the entity is saved with; it's created again through the `EntityTypes` with this name.
+    fn kind(&self) -> &'static str;
+
+    /// The width and height of the bounding box.
+    fn size(&self) -> Vec2;
+
+    /// How the entity moves through the world. Without physics it just moves by its velocity, through everything.
+    fn physics(&self) -> Option<PhysicsSettings> {
+        None
+    }
+
+    /// Runs once every world tick, before the entity moves by its velocity.
+    fn tick(&mut self, _state: &mut EntityState, _world: &mut dyn WorldAccess) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn save(&self) -> Compound {
+        Compound::new()
+    }
+
+    /// Whether the entity is saved with the chunk it's in. Entities that aren't just go away on unload.
+    fn is_saved(&self) -> bool {
+        true
+    }
+
+    /// Restores what `save` wrote, on a behaviour fresh from `EntityTypes::create`.
+    fn load(&mut self, _data: &Compound) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl dyn EntityBehaviour {
+    pub fn downcast_ref<T: EntityBehaviour>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: EntityBehaviour>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
+}
+
+#[derive(Debug)]
+pub struct Entity {
+    id: EntityId,
+    uuid: Uuid, //Unlike the id, this stays the same when the entity is saved and loaded again
+    state: EntityState,
+    behaviour: Box<dyn EntityBehaviour>,
+}
+
+impl Entity {
+    /// A new entity with a fresh uuid. Its id is handed out by the `EntityStore` it's put into.
+    pub fn new(behaviour: Box<dyn EntityBehaviour>, pos: Vec3) -> Self {
+        Self {
+            id: EntityId(0),
+            uuid: Uuid::new_v4(),
+            state: EntityState { pos, velocity: Vec3::ZERO, rotation: Vec2::ZERO, on_ground: false, removed: false },
+            behaviour,
+        }
+    }
+
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.behaviour.kind()
+    }
+
+    pub fn state(&self) -> &EntityState {
+        &self.state
+    }
+
+    pub fn pos(&self) -> Vec3 {
+        self.state.pos
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.state.removed
+    }
+
+    pub fn behaviour(&self) -> &dyn EntityBehaviour {
+        self.behaviour.as_ref()
+    }
+
+    /// The chunk the entity is in, and saved with.
+    pub fn chunk(&self) -> ChunkPos {
+        ChunkPos::new((self.state.pos.x.floor() as i32) >> 4, (self.state.pos.z.floor() as i32) >> 4)
+    }
+
+    /// The state and the behaviour at once, so the behaviour can change the state outside of ticks too.
+    pub fn split_mut(&mut self) -> (&mut EntityState, &mut dyn EntityBehaviour) {
+        (&mut self.state, self.behaviour.as_mut())
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        bounding_box(self.state.pos, self.behaviour.size())
+    }
+
+    pub fn tick(&mut self, world: &mut dyn WorldAccess) -> anyhow::Result<()> {
+        self.behaviour.tick(&mut self.state, world)?;
+
+        let Some(settings) = self.behaviour.physics() else {
+            self.state.pos += self.state.velocity;
+            return Ok(());
+        };
+
+        let mut body = Body {
+            pos: self.state.pos,
+            velocity: self.state.velocity,
+            size: self.behaviour.size(),
+            on_ground: self.state.on_ground,
+        };
+
+        physics::step(&mut body, &settings, world)?;
+        self.state.pos = body.pos;
+        self.state.velocity = body.velocity;
+        self.state.on_ground = body.on_ground;
+
+        Ok(())
+    }
+
+    pub fn save(&self) -> Compound {
+        let (most, least) = self.uuid.as_u64_pair();
+
+        Compound::new()
+            .with("kind", self.kind())
+            .with("uuid", vec![most as i64, least as i64])
+            .with("pos", vector_tag(&self.state.pos.to_array()))
+            .with("velocity", vector_tag(&self.state.velocity.to_array()))
+            .with("rotation", vector_tag(&self.state.rotation.to_array()))
+            .with("data", self.behaviour.save())
+    }
+
+    pub fn load(data: &Compound) -> anyhow::Result<Self> {
+        let mut behaviour = EntityTypes::global().create(data.get_string("kind")?)?;
+        behaviour.load(data.get_compound("data")?)?;
+
+        let uuid = match data.get_long_array("uuid")? {
+            [most, least] => Uuid::from_u64_pair(*most as u64, *least as u64),
+            _ => {
+                return Err(EntityError::InvalidVector("uuid".to_string()).into());
+            }
+        };
+
+        let [x, y, z] = read_vector(data, "pos")?;
+        let [vx, vy, vz] = read_vector(data, "velocity")?;
+        let [yaw, pitch] = read_vector(data, "rotation")?;
+
+        Ok(Self {
+            id: EntityId(0),
+            uuid,
+            state: EntityState {
+                pos: Vec3::new(x, y, z),
+                velocity: Vec3::new(vx, vy, vz),
+                rotation: Vec2::new(yaw, pitch),
+                on_ground: false,
+                removed: false,
+            },
+            behaviour,
+        })
+    }
+}
+
+fn vector_tag(components: &[f32]) -> Tag {
+    Tag::List(components.iter().map(|component| Tag::Float(*component)).collect())
+}
+
+fn read_vector<const N: usize>(data: &Compound, key: &str) -> anyhow::Result<[f32; N]> {
+    let components: Vec<f32> = data
+        .get_list(key)?
+        .iter()
+        .filter_map(|tag| if let Tag::Float(component) = tag { Some(*component) } else { None })
+        .collect();
+
+    components.try_into().map_err(|_| EntityError::InvalidVector(key.to_string()).into())
+}
+
+/// Every kind of entity, by name, so saved entities can be created again.
+#[derive(Default)]
+pub struct EntityTypes {
+    constructors: MetroHashMap<&'static str, fn() -> Box<dyn EntityBehaviour>>,
+}
+
+impl EntityTypes {
+    pub fn register<T: EntityBehaviour + Default>(&mut self) {
+        self.constructors.insert(T::default().kind(), Self::construct::<T>);
+    }
+
+    fn construct<T: EntityBehaviour + Default>() -> Box<dyn EntityBehaviour> {
+        Box::<T>::default()
+    }
+
+    pub fn create(&self, kind: &str) -> anyhow::Result<Box<dyn EntityBehaviour>> {
+        let constructor = self.constructors.get(kind).ok_or_else(|| EntityError::UnknownKind(kind.to_string()))?;
+
+        Ok(constructor())
+    }
+
+    pub fn global() -> &'static Self {
+        static TYPES: Lazy<EntityTypes> = Lazy::new(default_entity_types);
+
+        &TYPES
+    }
+}
+
+pub fn default_entity_types() -> EntityTypes {
+    let mut types = EntityTypes::default();
+
+    types.register::<MarkerEntity>();
+
+    types
+}
+
+/// An invisible point in the world that carries some data and does nothing else, e.g. to remember a location.
+#[derive(Debug, Clone, Default)]
+pub struct MarkerEntity {
+    pub data: Compound,
+}
+
+impl EntityBehaviour for MarkerEntity {
+    fn kind(&self) -> &'static str {
+        "marker"
+    }
+
+    fn size(&self) -> Vec2 {
+        Vec2::ZERO
+    }
+
+    fn save(&self) -> Compound {
+        self.data.clone()
+    }
+
+    fn load(&mut self, data: &Compound) -> anyhow::Result<()> {
+        self.data = data.clone();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+use glam::{ Vec2, Vec3 };
+use shared::{
+    dimension::{ access::WorldAccess, storage::ChunkStorage },
+    entity::{
+        bounding_box,
+        player::{ self, PlayerMove, PlayerTeleport, PLAYER_SIZE, PLAYER_EYE_HEIGHT, PLAYER_REACH },
+    },
+    item::{
+        inventory::{ InventoryClick, PlayerInventory, SlotUpdate, WindowItems },
+        stack::ItemStack,
+    },
+    error::item::ItemError,
+    util::{ aabb::Aabb, block_pos::BlockPos },
+};
+
+use super::{ EntityBehaviour, EntityState };
+
+/// What the server made of a `PlayerMove`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveOutcome {
+    Accepted,
+    Ignored, //Sent before the client knew about a teleport
+    Rejected(PlayerTeleport), //The client has to be put back to where the server thinks it is
+}
+
+/// What came of a player clicking a block with what it holds, see `ServerDimension::use_held_item`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceOutcome {
+    Used, //The clicked block reacted to it
+    Placed(BlockPos, SlotUpdate), //Where the block went, and what's left of the stack it came from
+    Rejected, //Nothing happened; the client has to be told how things really are if it guessed otherwise
+}
+
+/// A connected client in the world. Players belong to their client rather than the chunk they're in, so they
+/// aren't saved with it.
+#[derive(Debug, Default)]
+pub struct PlayerEntity {
+    movement_budget: f32, //How far the player may still move; a bit more is earned every tick
+    awaiting_teleport: Option<u32>,
+    next_teleport: u32,
+    inventory: PlayerInventory,
+    cursor: Option<ItemStack>, //What the player picked up in its inventory window
+    digging: Option<(BlockPos, u64)>, //The block the player is breaking, and the tick it started at
+}
+
+impl PlayerEntity {
+    /// How far a player may move per tick, horizontally and upwards. Falling is limited by `MAX_FALL_SPEED` instead.
+    pub const MAX_SPEED: f32 = 1.0;
+    /// How far a player may fall per tick, a little more than gravity and drag let anything reach.
+    pub const MAX_FALL_SPEED: f32 = 4.0;
+    /// Moves that arrive in a burst, e.g. after lag, may use up the speed of this many ticks at once.
+    pub const MAX_BUDGET_TICKS: f32 = 5.0;
+    /// How far apart the positions along a move are that are checked for collisions.
+    const COLLISION_STEP: f32 = 0.5;
+    /// The most positions checked along a single move. Moves that are allowed at all never need more.
+    const MAX_COLLISION_STEPS: f32 = 64.0;
+    /// Clients may end up slightly inside of blocks through rounding, which is let through.
+    const COLLISION_TOLERANCE: f32 = 1.0e-3;
+    /// How much further than `PLAYER_REACH` a block may be, since the server sees the player a little late.
+    pub const REACH_TOLERANCE: f32 = 1.0;
+    /// The share of a block's break time that has to have passed on the server before it breaks. The client starts
+    /// digging before the server hears of it, and may see fewer ticks pass in between.
+    pub const BREAK_TIME_TOLERANCE: f32 = 0.8;
+
+    /// Puts the player at `pos`. Moves are ignored until the client confirms the returned teleport.
+    pub fn teleport(&mut self, state: &mut EntityState, pos: Vec3, rotation: Vec2) -> PlayerTeleport {
+        self.next_teleport = self.next_teleport.wrapping_add(1);
+        self.awaiting_teleport = Some(self.next_teleport);
+        state.pos = pos;
+        state.rotation = rotation;
+
+        PlayerTeleport { id: self.next_teleport, pos, rotation }
+    }
+
+    /// Returns whether `id` was the teleport the player was waiting for, which lets its moves through again.
+    pub fn confirm_teleport(&mut self, id: u32) -> bool {
+        let confirmed = self.awaiting_teleport == Some(id);
+
+        if confirmed {
+            self.awaiting_teleport = None;
+        }
+
+        confirmed
+    }
+
+    /// Takes over a move if the player could have made it: not faster than it's allowed to and not through blocks.
+    /// Otherwise it's teleported back to where it was.
+    pub fn handle_move<S: ChunkStorage + ?Sized>(
+        &mut self,
+        state: &mut EntityState,
+        storage: &mut S,
+        movement: PlayerMove
+    ) -> anyhow::Result<MoveOutcome> {
+        if self.awaiting_teleport.is_some() {
+            return Ok(MoveOutcome::Ignored);
+        }
+
+        if !self.is_move_valid(state, storage, &movement)? {
+            return Ok(MoveOutcome::Rejected(self.teleport(state, state.pos, state.rotation)));
+        }
+
+        self.movement_budget -= Self::cost(movement.pos - state.pos);
+        state.on_ground = movement.on_ground;
+        state.pos = movement.pos;
+        state.rotation = movement.rotation;
+
+        Ok(MoveOutcome::Accepted)
+    }
+
+    fn cost(delta: Vec3) -> f32 {
+        Vec2::new(delta.x, delta.z).length() + delta.y.max(0.0)
+    }
+
+    fn is_move_valid<S: ChunkStorage + ?Sized>(
+        &self,
+        state: &EntityState,
+        storage: &mut S,
+        movement: &PlayerMove
+    ) -> anyhow::Result<bool> {
+        if !movement.pos.is_finite() || !movement.rotation.is_finite() {
+            return Ok(false);
+        }
+
+        let delta = movement.pos - state.pos;
+
+        //Falling is free, but can't happen faster than a burst of ticks at full speed
+        if Self::cost(delta) > self.movement_budget || -delta.y > Self::MAX_FALL_SPEED * Self::MAX_BUDGET_TICKS {
+            return Ok(false);
+        }
+
+        //Blocks the player is already stuck in don't count, or it could never get out of them
+        let stuck_in = storage.collision_boxes(&Self::collision_box(state.pos))?;
+        let steps = (delta.length() / Self::COLLISION_STEP).ceil().clamp(1.0, Self::MAX_COLLISION_STEPS) as u32;
+
+        for step in 1..=steps {
+            let pos = state.pos + delta * (step as f32 / steps as f32);
+
+            if storage.collision_boxes(&Self::collision_box(pos))?.iter().any(|aabb| !stuck_in.contains(aabb)) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn inventory(&self) -> &PlayerInventory {
+        &self.inventory
+    }
+
+    /// Changes made through this aren't sent to the client, it has to be sent `window_items` or a `SlotUpdate`.
+    pub fn inventory_mut(&mut self) -> &mut PlayerInventory {
+        &mut self.inventory
+    }
+
+    /// Everything in the player's inventory, to bring its client up to date.
+    pub fn window_items(&self) -> WindowItems {
+        WindowItems {
+            window: PlayerInventory::WINDOW,
+            slots: self.inventory.inventory().slots().to_vec(),
+            cursor: self.cursor.clone(),
+        }
+    }
+
+    /// Applies a click the client made in its inventory. The clicked slot and the cursor are always sent back, along
+    /// with any other slot that changed, so whatever the client predicted is replaced with what really happened.
+    pub fn handle_click(&mut self, click: InventoryClick) -> anyhow::Result<Vec<SlotUpdate>> {
+        if click.window != PlayerInventory::WINDOW {
+            return Err(ItemError::InvalidWindow(click.window).into());
+        }
+
+        let before = self.inventory.inventory().clone();
+        self.inventory.click(&mut self.cursor, click.slot as usize, click.kind)?;
+
+        let slots = self.inventory.inventory().slots();
+
+        let mut updates: Vec<SlotUpdate> = (0..slots.len())
+            .filter(|slot| *slot == click.slot as usize || slots[*slot] != before.slots()[*slot])
+            .map(|slot| SlotUpdate { window: click.window, slot: slot as u16, stack: slots[slot].clone() })
+            .collect();
+
+        updates.push(SlotUpdate { window: click.window, slot: SlotUpdate::CURSOR, stack: self.cursor.clone() });
+
+        Ok(updates)
+    }
+
+    /// Whether the player is close enough to `pos` to interact with the block there, measured from its eyes to the
+    /// nearest point of the block.
+    pub fn can_reach(state: &EntityState, pos: &BlockPos) -> bool {
+        let eye = state.pos + Vec3::Y * PLAYER_EYE_HEIGHT;
+        let min = Vec3::from(pos.clone());
+
+        eye.distance(eye.clamp(min, min + Vec3::ONE)) <= PLAYER_REACH + Self::REACH_TOLERANCE
+    }
+
+    pub fn digging(&self) -> Option<&BlockPos> {
+        self.digging.as_ref().map(|(pos, _)| pos)
+    }
+
+    /// Starts breaking the block at `pos` at tick `time`, if the player can reach it and it can be broken at all.
+    /// Returns whether it did.
+    pub fn start_digging<S: ChunkStorage + ?Sized>(
+        &mut self,
+        state: &EntityState,
+        storage: &mut S,
+        pos: BlockPos,
+        time: u64
+    ) -> anyhow::Result<bool> {
+        self.digging = None;
+
+        //Checked before looking at the block, so clients can't have far away chunks loaded
+        if !Self::can_reach(state, &pos) || !Self::is_in_world(storage, &pos)? {
+            return Ok(false);
+        }
+
+        let block = storage.get_chunk(&pos.get_chunk())?.get_block(pos.clone())?.resolve()?;
+
+        if player::break_ticks(block).is_none() {
+            return Ok(false);
+        }
+
+        self.digging = Some((pos, time));
+
+        Ok(true)
+    }
+
+    pub fn abort_digging(&mut self, pos: &BlockPos) {
+        if self.digging() == Some(pos) {
+            self.digging = None;
+        }
+    }
+
+    /// Stops breaking the block at `pos` at tick `time`, and returns whether the player was digging at it for long
+    /// enough to break it. Whatever the answer, the player isn't digging anymore afterwards.
+    pub fn finish_digging<S: ChunkStorage + ?Sized>(
+        &mut self,
+        state: &EntityState,
+        storage: &mut S,
+        pos: &BlockPos,
+        time: u64
+    ) -> anyhow::Result<bool> {
+        let Some((digging, started)) = self.digging.take() else {
+            return Ok(false);
+        };
+
+        if digging != *pos || !Self::can_reach(state, pos) {
+            return Ok(false);
+        }
+
+        //The block may have changed since the player started, it's the one there now that has to break
+        let block = storage.get_chunk(&pos.get_chunk())?.get_block(pos.clone())?.resolve()?;
+
+        let Some(ticks) = player::break_ticks(block) else {
+            return Ok(false);
+        };
+
+        Ok(time.saturating_sub(started) as f32 >= ticks as f32 * Self::BREAK_TIME_TOLERANCE)
+    }
+
+    fn is_in_world<S: ChunkStorage + ?Sized>(storage: &mut S, pos: &BlockPos) -> anyhow::Result<bool> {
+        Ok(storage.get_chunk(&pos.get_chunk())?.height().contains_y(pos.y()))
+    }
+
+    fn collision_box(pos: Vec3) -> Aabb {
+        bounding_box(pos, PLAYER_SIZE).inflate(Vec3::splat(-Self::COLLISION_TOLERANCE))
+    }
+}
+
+impl EntityBehaviour for PlayerEntity {
+    fn kind(&self) -> &'static str {
+        "player"
+    }
+
+    fn size(&self) -> Vec2 {
+        PLAYER_SIZE
+    }
+
+    fn is_saved(&self) -> bool {
+        false
+    }
+
+    fn tick(&mut self, _state: &mut EntityState, _world: &mut dyn WorldAccess) -> anyhow::Result<()> {
+        self.movement_budget = (self.movement_budget + Self::MAX_SPEED).min(Self::MAX_SPEED * Self::MAX_BUDGET_TICKS);
+
+        Ok(())
+    }
+}
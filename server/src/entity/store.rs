@@ -0,0 +1,101 @@
+use std::sync::{ Arc, atomic::{ AtomicU32, Ordering } };
+
+use glam::Vec3;
+use metrohash::{ MetroHashMap, MetroHashSet };
+use shared::{ entity::id::EntityId, util::{ aabb::Aabb, chunk_pos::ChunkPos } };
+
+use super::Entity;
+
+/// Hands out entity ids. Clones share the same counter, so every dimension gets one and ids stay unique when an
+/// entity moves from one dimension to another.
+#[derive(Debug, Clone, Default)]
+pub struct EntityIds(Arc<AtomicU32>);
+
+impl EntityIds {
+    pub fn next(&self) -> EntityId {
+        EntityId(self.0.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+/// All entities of a dimension, indexed by the chunk they're in. Entities can't be changed in place, since the index
+/// couldn't follow them around; they're taken out and put back instead.
+#[derive(Debug)]
+pub struct EntityStore {
+    entities: MetroHashMap<EntityId, Entity>,
+    by_chunk: MetroHashMap<u64, MetroHashSet<EntityId>>,
+    ids: EntityIds,
+}
+
+impl EntityStore {
+    /// How far an entity's bounding box may reach beyond its position, as far as `in_box` is concerned.
+    pub const MAX_REACH: f32 = 4.0;
+
+    pub fn new(ids: EntityIds) -> Self {
+        Self { entities: MetroHashMap::default(), by_chunk: MetroHashMap::default(), ids }
+    }
+
+    /// Adds an entity under a new id, which is returned.
+    pub fn insert(&mut self, mut entity: Entity) -> EntityId {
+        entity.id = self.ids.next();
+
+        let id = entity.id;
+        self.restore(entity);
+
+        id
+    }
+
+    /// Puts an entity that was taken out back in, under the id it had.
+    pub fn restore(&mut self, entity: Entity) {
+        self.by_chunk.entry(entity.chunk().as_long()).or_default().insert(entity.id);
+        self.entities.insert(entity.id, entity);
+    }
+
+    pub fn take(&mut self, id: EntityId) -> Option<Entity> {
+        let entity = self.entities.remove(&id)?;
+        let chunk = entity.chunk().as_long();
+
+        if let Some(ids) = self.by_chunk.get_mut(&chunk) {
+            ids.remove(&id);
+
+            if ids.is_empty() {
+                self.by_chunk.remove(&chunk);
+            }
+        }
+
+        Some(entity)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    /// Every entity id, in no particular order.
+    pub fn ids(&self) -> Vec<EntityId> {
+        self.entities.keys().copied().collect()
+    }
+
+    pub fn in_chunk<'a>(&'a self, chunk: &ChunkPos) -> impl Iterator<Item = &'a Entity> + 'a {
+        self.by_chunk
+            .get(&chunk.as_long())
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.entities.get(id))
+    }
+
+    /// The entities whose bounding box intersects `area`. Entities without a size count if their position is inside it.
+    pub fn in_box<'a>(&'a self, area: &'a Aabb) -> impl Iterator<Item = &'a Entity> + 'a {
+        //Entities are indexed by their position, so look a bit further for the ones sticking into the area
+        let search = area.inflate(Vec3::splat(Self::MAX_REACH));
+        let (min_x, max_x) = ((search.min.x.floor() as i32) >> 4, (search.max.x.floor() as i32) >> 4);
+        let (min_z, max_z) = ((search.min.z.floor() as i32) >> 4, (search.max.z.floor() as i32) >> 4);
+
+        (min_x..=max_x)
+            .flat_map(move |x| (min_z..=max_z).map(move |z| ChunkPos::new(x, z)))
+            .flat_map(move |chunk| self.in_chunk(&chunk).collect::<Vec<_>>())
+            .filter(move |entity| entity.bounding_box().intersects(area) || area.contains(entity.pos()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+}
@@ -0,0 +1,153 @@
+use glam::{ Vec2, Vec3 };
+use metrohash::{ MetroHashMap, MetroHashSet };
+use shared::{
+    dimension::id::DimensionId,
+    entity::{ id::EntityId, movement::EntityMovement, spawn::EntitySpawn },
+    net::{ packet::ClientId, packet_data::PacketData },
+    util::chunk_pos::ChunkPos,
+};
+
+use super::{ Entity, store::EntityStore };
+
+/// What clients were last told about an entity. Movement is sent relative to this, so rounding doesn't add up.
+#[derive(Debug, Clone, Copy)]
+struct SentState {
+    pos: Vec3,
+    velocity: Vec3,
+    rotation: Vec2,
+}
+
+impl SentState {
+    fn of(entity: &Entity) -> Self {
+        let state = entity.state();
+
+        Self { pos: state.pos, velocity: state.velocity, rotation: state.rotation }
+    }
+}
+
+/// A client that sees the entities around `center`. Its own player, if it has one, isn't sent to it.
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    pub client: ClientId,
+    pub center: Vec3,
+    pub player: Option<EntityId>,
+}
+
+/// Decides which entities of a dimension each client gets to see, and what it needs to be told about them.
+#[derive(Debug, Default)]
+pub struct EntityTracker {
+    sent: MetroHashMap<EntityId, SentState>, //Only for entities at least one client sees
+    viewers: MetroHashMap<ClientId, MetroHashSet<EntityId>>,
+}
+
+impl EntityTracker {
+    /// How far away from a client entities are sent to it, horizontally.
+    pub const TRACKING_RANGE: f32 = 64.0;
+
+    /// Compares the entities with what the clients know about them. `viewers` are the clients in the dimension,
+    /// with the point they see the world from; clients missing from it are forgotten.
+    pub fn update(
+        &mut self,
+        dimension: DimensionId,
+        entities: &EntityStore,
+        viewers: &[Viewer]
+    ) -> Vec<(ClientId, PacketData)> {
+        let changes = self.changes(dimension, entities);
+        let mut packets = Vec::new();
+
+        self.viewers.retain(|client, _| viewers.iter().any(|viewer| viewer.client == *client));
+
+        for Viewer { client, center, player } in viewers {
+            let known = self.viewers.entry(client.clone()).or_default();
+
+            for (id, data) in &changes {
+                if known.contains(id) {
+                    packets.push((client.clone(), data.clone()));
+                }
+            }
+
+            let visible: MetroHashSet<EntityId> = Self::chunks_in_range(*center)
+                .flat_map(|chunk| entities.in_chunk(&chunk).collect::<Vec<_>>())
+                .filter(|entity| Some(entity.id()) != *player && Self::in_range(entity.pos(), *center))
+                .map(Entity::id)
+                .collect();
+
+            let mut gone: Vec<EntityId> = known.difference(&visible).copied().collect();
+
+            if !gone.is_empty() {
+                gone.sort();
+                packets.push((client.clone(), PacketData::DespawnEntities(dimension, gone)));
+            }
+
+            let mut new: Vec<EntityId> = visible.difference(known).copied().collect();
+            new.sort();
+
+            for id in new {
+                let entity = entities.get(id).expect("Only stored entities are visible.");
+                let sent = *self.sent.entry(id).or_insert_with(|| SentState::of(entity));
+
+                let spawn = EntitySpawn {
+                    id,
+                    kind: entity.kind().to_string(),
+                    pos: sent.pos,
+                    velocity: sent.velocity,
+                    rotation: sent.rotation,
+                };
+
+                packets.push((client.clone(), PacketData::SpawnEntity(dimension, spawn)));
+            }
+
+            *known = visible;
+        }
+
+        let viewed: MetroHashSet<EntityId> = self.viewers.values().flatten().copied().collect();
+        self.sent.retain(|id, _| viewed.contains(id));
+
+        packets
+    }
+
+    /// The chunks any entity in range of `center` can be in, so not every entity has to be looked at.
+    fn chunks_in_range(center: Vec3) -> impl Iterator<Item = ChunkPos> {
+        let chunk = |coord: f32| (coord.floor() as i32) >> 4;
+        let (min_x, max_x) = (chunk(center.x - Self::TRACKING_RANGE), chunk(center.x + Self::TRACKING_RANGE));
+        let (min_z, max_z) = (chunk(center.z - Self::TRACKING_RANGE), chunk(center.z + Self::TRACKING_RANGE));
+
+        (min_x..=max_x).flat_map(move |x| (min_z..=max_z).map(move |z| ChunkPos::new(x, z)))
+    }
+
+    fn in_range(pos: Vec3, center: Vec3) -> bool {
+        Vec2::new(pos.x - center.x, pos.z - center.z).length_squared() <= Self::TRACKING_RANGE.powi(2)
+    }
+
+    /// What changed about each entity someone already sees, updating what was sent accordingly.
+    fn changes(&mut self, dimension: DimensionId, entities: &EntityStore) -> Vec<(EntityId, PacketData)> {
+        let mut changes = Vec::new();
+
+        for (id, sent) in &mut self.sent {
+            let Some(entity) = entities.get(*id) else {
+                continue;
+            };
+            let state = entity.state();
+
+            let movement = EntityMovement::between(sent.pos, state.pos);
+
+            //Moves too small for a delta wait until they add up to something
+            if movement != EntityMovement::Delta(0, 0, 0) {
+                sent.pos = movement.apply(sent.pos);
+                changes.push((*id, PacketData::EntityMove(dimension, *id, movement)));
+            }
+
+            if state.rotation != sent.rotation {
+                sent.rotation = state.rotation;
+                changes.push((*id, PacketData::EntityLook(dimension, *id, state.rotation)));
+            }
+
+            if state.velocity != sent.velocity {
+                sent.velocity = state.velocity;
+                changes.push((*id, PacketData::EntityVelocity(dimension, *id, state.velocity)));
+            }
+        }
+
+        changes
+    }
+}
@@ -1,5 +1,1053 @@
+use std::{ path::Path, time::Duration };
+
+use anyhow::bail;
+use log::error;
+use shared::util::logger;
+
+use crate::{
+    controller::ServerController,
+    net::ServerNetworkHandler,
+    world::ServerWorld,
+    backup::SnapshotManager,
+};
+
+mod backup;
+mod console;
+mod controller;
 mod dimension;
+mod entity;
 mod net;
+mod util;
+mod world;
+
+const WORLD_FOLDER: &str = "world";
+const BACKUP_FOLDER: &str = "backups";
+
 fn main() {
-    println!("Hello, world!");
+    let _logger = logger::default_config();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some((command, rest)) = args.split_first() {
+        if command == "snapshot" {
+            if let Err(e) = backup::run_cli(rest, Path::new(WORLD_FOLDER), Path::new(BACKUP_FOLDER)) {
+                error!("{}", e);
+            }
+            return;
+        }
+    }
+
+    let autosave_interval = match parse_autosave_interval(&args) {
+        Ok(interval) => interval,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    let world = match ServerWorld::open(Path::new(WORLD_FOLDER)) {
+        Ok(world) => world,
+        Err(e) => {
+            error!("Failed to open world: {}", e);
+            return;
+        }
+    };
+
+    let snapshots = SnapshotManager::new(Path::new(WORLD_FOLDER), Path::new(BACKUP_FOLDER));
+    let mut controller = ServerController::new(
+        ServerNetworkHandler::init(),
+        world,
+        console::read_stdin(),
+        snapshots
+    );
+
+    if let Some(interval) = autosave_interval {
+        controller = controller.with_autosave_interval(interval);
+    }
+
+    if let Err(e) = controller.run() {
+        error!("Server stopped unexpectedly: {}", e);
+    }
+}
+
+/// Reads `--autosave-interval <seconds>`, the only option the server takes besides its subcommands.
+fn parse_autosave_interval(args: &[String]) -> anyhow::Result<Option<Duration>> {
+    match args {
+        [] => Ok(None),
+        [option, seconds] if option == "--autosave-interval" => {
+            let seconds: u64 = seconds.parse()?;
+
+            if seconds == 0 {
+                bail!("The autosave interval has to be at least a second");
+            }
+
+            Ok(Some(Duration::from_secs(seconds)))
+        }
+        _ => bail!("Usage: server [--autosave-interval <seconds>] | server snapshot <create | list | restore <id>>"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ path::PathBuf, time::Duration };
+
+    use glam::{ Vec2, Vec3 };
+    use shared::{
+        util::{ block_pos::BlockPos, chunk_pos::ChunkPos, aabb::Aabb, direction::{ Direction, Axis } },
+        block::{
+            BlockId,
+            simple::{ GrassState, SnowState, DirtState },
+            fluid::{ WaterState, LavaState, FluidState, FluidKind },
+            orientable::{ LogState, StairState, SlabState, DoorState, Half, SlabType },
+            entity::{ SignState, SignEntity, SpawnerEntity },
+            registry::BlockRegistry,
+            context::{ PlacementContext, UseContext },
+        },
+        dimension::{
+            id::DimensionId,
+            storage::ChunkStorage,
+            access::WorldAccess,
+            settings::DimensionSettings,
+        },
+        tag::Compound,
+        entity::{
+            movement::EntityMovement,
+            player::{ self, PlayerMove, PlaceBlock },
+            physics::PhysicsSettings,
+        },
+        item::{ stack::ItemStack, inventory::{ ClickKind, InventoryClick, PlayerInventory, SlotUpdate } },
+        net::{ NetworkHandler, packet::{ ClientId, Packet, PacketDirection }, packet_data::PacketData },
+    };
+    use uuid::Uuid;
+
+    use crate::{
+        world::{ info::WorldInfo, ServerWorld },
+        backup::SnapshotManager,
+        dimension::{ updates::NeighborUpdateQueue, registry::ServerDimension },
+        entity::{
+            Entity,
+            EntityBehaviour,
+            MarkerEntity,
+            tracker::Viewer,
+            player::{ MoveOutcome, PlaceOutcome },
+        },
+        controller::ServerController,
+    };
+
+    pub fn temp_folder() -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("reengineer-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    pub fn test_world_info_round_trip() {
+        let folder = temp_folder();
+
+        let mut info = WorldInfo::new(1234);
+        info.spawn = BlockPos::new(-5, 70, 12);
+        info.time = 24000;
+        info.game_rules.random_tick_speed = 10;
+        info.save(&folder).unwrap();
+
+        let loaded = WorldInfo::load(&folder).unwrap().unwrap();
+        assert_eq!(loaded.seed, 1234);
+        assert_eq!(loaded.spawn, info.spawn);
+        assert_eq!(loaded.time, 24000);
+        assert_eq!(loaded.game_rules.random_tick_speed, 10);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_autosave_interval_option() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(super::parse_autosave_interval(&args(&[])).unwrap(), None);
+        assert_eq!(
+            super::parse_autosave_interval(&args(&["--autosave-interval", "60"])).unwrap(),
+            Some(Duration::from_secs(60))
+        );
+        assert!(super::parse_autosave_interval(&args(&["--autosave-interval", "0"])).is_err());
+        assert!(super::parse_autosave_interval(&args(&["--autosave-interval", "soon"])).is_err());
+        assert!(super::parse_autosave_interval(&args(&["--autosave"])).is_err());
+    }
+
+    #[test]
+    pub fn test_chunks_survive_reopening() {
+        let folder = temp_folder();
+        let pos = BlockPos::new(-40, 100, 7);
+
+        {
+            let mut world = ServerWorld::open(&folder).unwrap();
+            let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+            overworld.chunks_mut().set_block(pos.clone(), BlockId(0x0101)).unwrap();
+            world.save().unwrap();
+        }
+
+        //Simulate a crash in the middle of writing a region
+        let stale = folder.join("overworld").join("r.0.0.region.tmp");
+        std::fs::write(&stale, [1, 2, 3]).unwrap();
+
+        let mut world = ServerWorld::open(&folder).unwrap();
+        assert!(!stale.exists());
+
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let chunk = overworld.chunks_mut().get_chunk(&pos.get_chunk()).unwrap();
+        assert_eq!(chunk.get_block(pos).unwrap().0, 0x0101);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_incremental_snapshots() {
+        let folder = temp_folder();
+        let (world, backups) = (folder.join("world"), folder.join("backups"));
+        std::fs::create_dir_all(world.join("overworld")).unwrap();
+        std::fs::write(world.join("level.dat"), "old").unwrap();
+        std::fs::write(world.join("overworld").join("r.0.0.region"), "region").unwrap();
+
+        let manager = SnapshotManager::new(&world, &backups);
+        let first = manager.create().unwrap();
+        assert_eq!(first.copied, 2);
+
+        std::fs::write(world.join("level.dat"), "new").unwrap();
+        std::fs::write(world.join("overworld").join("r.1.0.region"), "later").unwrap();
+
+        let second = manager.create().unwrap();
+        assert_eq!((second.files, second.copied), (3, 2));
+        assert_eq!(manager.list().unwrap().len(), 2);
+
+        manager.restore(&first.id).unwrap();
+        assert_eq!(std::fs::read_to_string(world.join("level.dat")).unwrap(), "old");
+        assert!(!world.join("overworld").join("r.1.0.region").exists());
+
+        manager.restore(&second.id).unwrap();
+        assert_eq!(std::fs::read_to_string(world.join("overworld").join("r.0.0.region")).unwrap(), "region");
+        assert_eq!(std::fs::read_to_string(world.join("level.dat")).unwrap(), "new");
+
+        //Only listed snapshots can be restored, nothing else reachable from the backup folder
+        assert!(manager.restore("../world").is_err());
+        assert!(manager.restore("missing").is_err());
+
+        //A write the server is in the middle of is neither copied nor removed
+        std::fs::write(world.join("level.dat.tmp"), "unfinished").unwrap();
+        let third = manager.create().unwrap();
+        assert_eq!((third.files, third.copied), (3, 0));
+        assert!(world.join("level.dat.tmp").exists());
+
+        //Snapshots taken within the same second still list in the order they were taken
+        let created: Vec<String> = (0..10).map(|_| manager.create().unwrap().id).collect();
+        let listed: Vec<String> = manager.list().unwrap().into_iter().map(|snapshot| snapshot.id).collect();
+        assert_eq!(listed[3..], created[..]);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_grass_turns_snowy() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+        //The flat overworld is three layers of dirt with grass on top
+        let grass = BlockPos::new(3, overworld.settings().height.min_y() + 3, -2);
+        let above = grass.offset(Direction::Up);
+
+        let context = PlacementContext {
+            pos: above.clone(),
+            block: BlockId::of(&SnowState).unwrap(),
+            face: Direction::Up,
+            hit: Vec3::new(0.5, 1.0, 0.5),
+            look: Vec3::NEG_Z,
+        };
+
+        let placed = overworld.place_block(&context).unwrap().unwrap();
+        assert_eq!(placed, BlockId::of(&SnowState).unwrap());
+
+        //Neighbours only react once the queued updates are delivered
+        assert_eq!(overworld.pending_updates(), 6);
+        overworld.tick(0, 0);
+        assert_eq!(overworld.get_block(grass.clone()).unwrap(), BlockId::of(&GrassState::SNOWY).unwrap());
+
+        overworld.set_block(above, BlockId::default()).unwrap();
+        assert_eq!(overworld.process_updates(2).unwrap(), 2);
+        assert_eq!(overworld.pending_updates(), 4);
+        overworld.tick(0, 0);
+        assert_eq!(overworld.get_block(grass).unwrap(), BlockId::of(&GrassState::NORMAL).unwrap());
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_neighbor_update_limits() {
+        let mut updates = NeighborUpdateQueue::default();
+        let pos = BlockPos::new(0, 0, 0);
+
+        //Queuing the same updates again doesn't add anything
+        updates.notify_neighbors(&pos);
+        updates.notify_neighbors(&pos);
+        assert_eq!(updates.len(), 6);
+        assert_eq!(updates.next(), Some((pos.offset(Direction::North), Direction::South)));
+
+        //A block that changes again on every update it causes is cut off once each neighbour had its share
+        let mut delivered = 1;
+        while updates.next().is_some() {
+            delivered += 1;
+            updates.notify_neighbors(&pos);
+        }
+        assert_eq!(delivered, NeighborUpdateQueue::MAX_UPDATES_PER_BLOCK as usize * 6);
+
+        //Further repeats are dropped for the rest of the tick
+        updates.notify_neighbors(&pos);
+        assert_eq!(updates.next(), None);
+        assert_eq!(updates.len(), 0);
+
+        updates.start_tick();
+        updates.notify_neighbors(&pos);
+        assert!(updates.next().is_some());
+
+        //A tick delivers exactly its budget and leaves the rest queued
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let min_y = overworld.settings().height.min_y() + 10;
+
+        //Inside a single chunk, so no update is skipped for hitting one that isn't loaded
+        for y in min_y..min_y + 4 {
+            for x in 1..15 {
+                for z in 1..15 {
+                    overworld.set_block(BlockPos::new(x, y, z), stone).unwrap();
+                }
+            }
+        }
+
+        let queued = 14 * 4 * 14 * 6;
+        assert!(queued > ServerDimension::UPDATE_BUDGET);
+        assert_eq!(overworld.pending_updates(), queued);
+        overworld.tick(0, 0);
+        assert_eq!(overworld.pending_updates(), queued - ServerDimension::UPDATE_BUDGET);
+        overworld.tick(1, 0);
+        assert_eq!(overworld.pending_updates(), 0);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_scheduled_and_random_ticks() {
+        let folder = temp_folder();
+        let pos = BlockPos::new(5, DimensionSettings::overworld().height.min_y() + 3, 5);
+
+        //A fixed seed, so the random ticks are the same on every run
+        WorldInfo::new(1234).save(&folder).unwrap();
+
+        {
+            let mut world = ServerWorld::open(&folder).unwrap();
+            let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+            overworld.schedule_tick(pos.clone(), 20, 0).unwrap();
+            world.save().unwrap();
+        }
+
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+        //The tick is saved with its chunk, so it comes back once the chunk is loaded again
+        overworld.get_block(pos.clone()).unwrap();
+        assert_eq!(overworld.chunks().scheduled_tick_count(), 1);
+        overworld.tick(19, 0);
+        assert_eq!(overworld.chunks().scheduled_tick_count(), 1);
+        overworld.tick(20, 0);
+        assert_eq!(overworld.chunks().scheduled_tick_count(), 0);
+
+        //Bare dirt next to grass gets overgrown sooner or later. With a patch of it, at least some is overgrown within
+        //a thousand ticks however the random ticks fall
+        let dirt = BlockId::of(&DirtState).unwrap();
+        let y = pos.y();
+        let patch = (3..8)
+            .flat_map(|x| (3..8).map(move |z| BlockPos::new(x, y, z)))
+            .collect::<Vec<_>>();
+
+        for block in &patch {
+            overworld.set_block(block.clone(), dirt).unwrap();
+        }
+
+        for time in 21..1000 {
+            overworld.tick(time, 64);
+        }
+
+        let grass = BlockId::of(&GrassState::NORMAL).unwrap();
+        let overgrown = patch.iter().filter(|block| overworld.get_block((*block).clone()).unwrap() == grass).count();
+        assert!(overgrown > 0);
+        assert_eq!(overworld.get_block(pos.offset(Direction::Down)).unwrap(), dirt);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_fluids() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+        let y = overworld.settings().height.min_y() + 4;
+        let water = |level, falling| FluidState { kind: FluidKind::Water, level, falling }.id().unwrap();
+        let named = |name| BlockRegistry::global().unwrap().get_by_name(name).unwrap().default_state();
+
+        //With a hole two blocks away, the water only flows towards it
+        overworld.set_block(BlockPos::new(10, y - 1, 8), BlockId::default()).unwrap();
+        overworld.set_block(BlockPos::new(8, y, 8), BlockId::of(&WaterState::SOURCE).unwrap()).unwrap();
+
+        for time in 1..=5 {
+            overworld.tick(time, 0);
+        }
+
+        assert_eq!(overworld.get_block(BlockPos::new(9, y, 8)).unwrap(), water(1, false));
+        assert_eq!(overworld.get_block(BlockPos::new(7, y, 8)).unwrap(), BlockId::default());
+
+        for time in 6..200 {
+            overworld.tick(time, 0);
+        }
+
+        assert_eq!(overworld.get_block(BlockPos::new(10, y - 1, 8)).unwrap(), water(0, true));
+        assert_eq!(overworld.get_block(BlockPos::new(8, y, 1)).unwrap(), water(7, false));
+        assert_eq!(overworld.get_block(BlockPos::new(0, y, 8)).unwrap(), BlockId::default());
+
+        //Without its source the water drains away again
+        overworld.set_block(BlockPos::new(8, y, 8), BlockId::default()).unwrap();
+
+        for time in 200..400 {
+            overworld.tick(time, 0);
+        }
+
+        assert_eq!(overworld.get_block(BlockPos::new(9, y, 8)).unwrap(), BlockId::default());
+        assert_eq!(overworld.get_block(BlockPos::new(10, y - 1, 8)).unwrap(), BlockId::default());
+
+        //Lava next to water hardens, into obsidian if it's a source and into cobblestone if it's flowing
+        overworld.set_block(BlockPos::new(41, y, 40), BlockId::of(&WaterState::SOURCE).unwrap()).unwrap();
+        overworld.set_block(BlockPos::new(40, y, 40), BlockId::of(&LavaState::SOURCE).unwrap()).unwrap();
+        assert_eq!(overworld.get_block(BlockPos::new(40, y, 40)).unwrap(), named("obsidian"));
+
+        overworld.set_block(BlockPos::new(50, y, 50), BlockId::of(&LavaState::SOURCE).unwrap()).unwrap();
+
+        for time in 400..=430 {
+            overworld.tick(time, 0);
+        }
+
+        assert_eq!(
+            FluidState::of(overworld.get_block(BlockPos::new(51, y, 50)).unwrap()),
+            Some(FluidState { kind: FluidKind::Lava, level: 2, falling: false })
+        );
+
+        overworld.set_block(BlockPos::new(51, y, 51), BlockId::of(&WaterState::SOURCE).unwrap()).unwrap();
+        overworld.tick(431, 0);
+        assert_eq!(overworld.get_block(BlockPos::new(51, y, 50)).unwrap(), named("cobblestone"));
+        assert_eq!(overworld.get_block(BlockPos::new(50, y, 50)).unwrap(), BlockId::of(&LavaState::SOURCE).unwrap());
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_orientable_blocks() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+        let y = overworld.settings().height.min_y() + 4;
+        let place = |overworld: &mut ServerDimension, pos: BlockPos, block: BlockId, face, hit, look| {
+            overworld.place_block(&PlacementContext { pos, block, face, hit, look }).unwrap().unwrap()
+        };
+
+        //Logs follow the face they're placed against, stairs the way the player looks
+        let log = BlockId::of(&LogState::default()).unwrap();
+        let log = place(overworld, BlockPos::new(0, y, 0), log, Direction::East, Vec3::ONE, Vec3::X);
+        assert_eq!(log.typed::<LogState>().unwrap().axis(), Axis::X);
+
+        let stairs = place(
+            overworld,
+            BlockPos::new(1, y, 0),
+            BlockId::of(&StairState::default()).unwrap(),
+            Direction::South,
+            Vec3::new(0.5, 0.7, 1.0),
+            Vec3::new(0.3, -0.8, -0.9)
+        );
+        assert_eq!(stairs.typed::<StairState>().unwrap().facing(), Direction::North);
+        assert_eq!(stairs.typed::<StairState>().unwrap().half(), Half::Top);
+
+        //A second slab in the same space makes a double slab
+        let slab = BlockId::of(&SlabState::default()).unwrap();
+        let pos = BlockPos::new(2, y, 0);
+        place(overworld, pos.clone(), slab, Direction::Up, Vec3::new(0.5, 1.0, 0.5), Vec3::NEG_Y);
+        assert_eq!(overworld.get_block(pos.clone()).unwrap(), slab);
+        let double = place(overworld, pos, slab, Direction::Up, Vec3::new(0.5, 1.0, 0.5), Vec3::NEG_Y);
+        assert_eq!(double.typed::<SlabState>().unwrap().kind(), SlabType::Double);
+        assert!(double.resolve().unwrap().collision_shape().is_full());
+
+        //Doors are two blocks tall, open and close as a whole, and go away as a whole
+        let pos = BlockPos::new(3, y, 0);
+        let door = BlockId::of(&DoorState::default()).unwrap();
+        let lower = place(overworld, pos.clone(), door, Direction::Up, Vec3::ONE, Vec3::X);
+        let upper = overworld.get_block(pos.offset(Direction::Up)).unwrap();
+        assert_eq!(upper, BlockId::of(&DoorState::new(Direction::East, Half::Top, false)).unwrap());
+
+        let closed = lower.resolve().unwrap().collision_shape().clone();
+        let context = UseContext { face: Direction::West, hit: Vec3::ONE };
+        assert!(overworld.use_block(pos.offset(Direction::Up), &context).unwrap());
+        let opened = overworld.get_block(pos.clone()).unwrap();
+        assert!(opened.typed::<DoorState>().unwrap().is_open());
+        assert_ne!(opened.resolve().unwrap().collision_shape(), &closed);
+
+        overworld.set_block(pos.offset(Direction::Up), BlockId::default()).unwrap();
+        overworld.tick(0, 0);
+        assert_eq!(overworld.get_block(pos).unwrap(), BlockId::default());
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_block_entities() {
+        let folder = temp_folder();
+        let y = DimensionSettings::overworld().height.min_y() + 4;
+        let (sign, spawner) = (BlockPos::new(-20, y, 3), BlockPos::new(-21, y, 3));
+
+        {
+            let mut world = ServerWorld::open(&folder).unwrap();
+            let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+            let spawner_block = BlockRegistry::global().unwrap().get_by_name("spawner").unwrap().default_state();
+
+            overworld.set_block(sign.clone(), BlockId::of(&SignState).unwrap()).unwrap();
+            overworld.set_block(spawner.clone(), spawner_block).unwrap();
+            assert_eq!(overworld.chunks().block_entity_count(), 2);
+
+            let entity = overworld.block_entity_mut(&sign).unwrap().unwrap();
+            entity.downcast_mut::<SignEntity>().unwrap().set_line(1, "Hello");
+
+            //Only the sign has anything to show to clients
+            let changes = overworld.take_block_entity_changes();
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].1.get_string("line1").unwrap(), "Hello");
+
+            for time in 1..=SpawnerEntity::MIN_DELAY as u64 + 1 {
+                overworld.tick(time, 0);
+            }
+
+            world.save().unwrap();
+        }
+
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+        //Block entities are saved with their chunk
+        let entity = overworld.block_entity_mut(&spawner).unwrap().unwrap();
+        let entity = entity.downcast_ref::<SpawnerEntity>().unwrap();
+        assert_eq!(entity.spawns(), 1);
+        assert!(entity.delay() >= SpawnerEntity::MIN_DELAY);
+
+        let entity = overworld.chunks().block_entity(&sign).unwrap();
+        assert_eq!(entity.downcast_ref::<SignEntity>().unwrap().line(1), "Hello");
+
+        //Clients asking for the chunk get it first, then what they may see of its block entities
+        let packets = overworld.chunk_packets(&sign.get_chunk()).unwrap();
+        assert_eq!(packets.len(), 2);
+        let chunk = sign.get_chunk().as_long();
+        assert!(matches!(&packets[0], PacketData::ChunkData(DimensionId::OVERWORLD, pos, _) if pos.as_long() == chunk));
+        assert!(matches!(&packets[1], PacketData::BlockEntityData(_, pos, _) if *pos == sign));
+
+        //Replacing the block removes its entity, and clients are told to forget it
+        overworld.take_block_entity_changes();
+        overworld.set_block(sign.clone(), BlockId::default()).unwrap();
+        assert!(overworld.chunks().block_entity(&sign).is_none());
+        assert_eq!(overworld.take_block_entity_changes(), vec![(sign, Compound::new())]);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_entities() {
+        let folder = temp_folder();
+        let y = DimensionSettings::overworld().height.min_y() as f32 + 4.0;
+
+        let uuid = {
+            let mut world = ServerWorld::open(&folder).unwrap();
+            let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+
+            let marker = MarkerEntity { data: Compound::new().with("name", "spot") };
+            let mut entity = Entity::new(Box::new(marker), Vec3::new(14.5, y, 8.5));
+            entity.split_mut().0.velocity = Vec3::new(1.0, 0.0, 0.0);
+            let uuid = entity.save().get_long_array("uuid").unwrap().to_vec();
+            let id = overworld.spawn_entity(entity).unwrap();
+
+            let entities = overworld.chunks().entities();
+            assert_eq!(entities.in_chunk(&ChunkPos::new(0, 0)).count(), 1);
+
+            for time in 1..=3 {
+                overworld.tick(time, 0);
+            }
+
+            //The entity moved into the next chunk, and is tracked there
+            let entities = overworld.chunks().entities();
+            assert_eq!(entities.get(id).unwrap().pos(), Vec3::new(17.5, y, 8.5));
+            assert_eq!(entities.in_chunk(&ChunkPos::new(0, 0)).count(), 0);
+            assert_eq!(entities.in_chunk(&ChunkPos::new(1, 0)).count(), 1);
+
+            let area = Aabb::new(Vec3::new(17.0, y - 1.0, 8.0), Vec3::new(18.0, y + 1.0, 9.0));
+            assert_eq!(entities.in_box(&area).map(Entity::id).collect::<Vec<_>>(), vec![id]);
+            let area = Aabb::new(Vec3::new(14.0, y - 1.0, 8.0), Vec3::new(15.0, y + 1.0, 9.0));
+            assert_eq!(entities.in_box(&area).count(), 0);
+
+            let mut entity = overworld.chunks_mut().take_entity(id).unwrap();
+            entity.split_mut().0.velocity = Vec3::ZERO;
+            overworld.chunks_mut().restore_entity(entity).unwrap();
+            world.save().unwrap();
+
+            //An entity that stays the way it is doesn't get its chunk saved again every tick
+            let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+            overworld.tick(4, 0);
+            assert_eq!(overworld.chunks_mut().save_dirty().unwrap(), 0);
+
+            //Ids are unique across dimensions, so an entity keeps its id when it moves to another one
+            let nether = world.dimensions_mut().get_mut(DimensionId::NETHER).unwrap();
+            let pos = Vec3::new(0.5, DimensionSettings::nether().height.min_y() as f32 + 2.0, 0.5);
+            let other = nether.spawn_entity(Entity::new(Box::<MarkerEntity>::default(), pos)).unwrap();
+            assert_ne!(other, id);
+
+            uuid
+        };
+
+        //Entities are saved with the chunk they're in
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        assert_eq!(overworld.chunks().entities().len(), 0);
+
+        overworld.chunks_mut().get_chunk(&ChunkPos::new(1, 0)).unwrap();
+        let entity = overworld.chunks().entities().in_chunk(&ChunkPos::new(1, 0)).next().unwrap();
+        assert_eq!(entity.save().get_long_array("uuid").unwrap(), uuid);
+        assert_eq!(entity.pos(), Vec3::new(17.5, y, 8.5));
+        assert_eq!(entity.state().velocity, Vec3::ZERO);
+        assert_eq!(entity.behaviour().downcast_ref::<MarkerEntity>().unwrap().data.get_string("name").unwrap(), "spot");
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_entity_tracking() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let y = overworld.settings().height.min_y() as f32 + 4.0;
+        let (near_client, far_client) = (ClientId::new(), ClientId::new());
+        let viewer = |client: &ClientId, center| Viewer { client: client.clone(), center, player: None };
+
+        let mut near = Entity::new(Box::<MarkerEntity>::default(), Vec3::new(5.0, y, 5.0));
+        near.split_mut().0.velocity = Vec3::new(0.1, 0.0, 0.0);
+        let near = overworld.spawn_entity(near).unwrap();
+        let far = Entity::new(Box::<MarkerEntity>::default(), Vec3::new(200.0, y, 5.0));
+        let far = overworld.spawn_entity(far).unwrap();
+
+        //Only entities in range are sent
+        let packets = overworld.track_entities(&[viewer(&near_client, Vec3::ZERO)]);
+        assert_eq!(packets.len(), 1);
+        assert!(
+            matches!(&packets[0].1, PacketData::SpawnEntity(_, spawn) if spawn.id == near && spawn.kind == "marker")
+        );
+
+        //Nothing changed, nothing to send
+        assert!(overworld.track_entities(&[viewer(&near_client, Vec3::ZERO)]).is_empty());
+
+        overworld.tick(1, 0);
+        let packets = overworld.track_entities(&[viewer(&near_client, Vec3::ZERO)]);
+        assert_eq!(packets.len(), 1);
+        assert!(matches!(packets[0].1, PacketData::EntityMove(_, id, EntityMovement::Delta(410, 0, 0)) if id == near));
+
+        //Long distances are teleports, and the other client only gets the entity near it
+        let mut entity = overworld.chunks_mut().take_entity(near).unwrap();
+        entity.split_mut().0.pos = Vec3::new(40.0, y, 5.0);
+        entity.split_mut().0.velocity = Vec3::ZERO;
+        overworld.chunks_mut().restore_entity(entity).unwrap();
+
+        let viewers = [viewer(&near_client, Vec3::ZERO), viewer(&far_client, Vec3::new(200.0, y, 0.0))];
+        let packets = overworld.track_entities(&viewers);
+        let near_packets: Vec<&PacketData> = packets
+            .iter()
+            .filter(|(client, _)| *client == near_client)
+            .map(|(_, data)| data)
+            .collect();
+        assert_eq!(near_packets.len(), 2);
+        assert!(
+            matches!(near_packets[0], PacketData::EntityMove(_, _, EntityMovement::Teleport(pos)) if pos.x == 40.0)
+        );
+        assert!(matches!(near_packets[1], PacketData::EntityVelocity(_, _, velocity) if *velocity == Vec3::ZERO));
+
+        let far_packets: Vec<&PacketData> = packets
+            .iter()
+            .filter(|(client, _)| *client == far_client)
+            .map(|(_, data)| data)
+            .collect();
+        assert_eq!(far_packets.len(), 1);
+        assert!(matches!(far_packets[0], PacketData::SpawnEntity(_, spawn) if spawn.id == far));
+
+        //Removed entities are despawned for everyone who saw them
+        overworld.chunks_mut().take_entity(near).unwrap();
+        let packets = overworld.track_entities(&viewers);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].0, near_client);
+        assert!(matches!(&packets[0].1, PacketData::DespawnEntities(_, ids) if *ids == vec![near]));
+
+        //Clients that left are forgotten, and get everything again when they come back
+        overworld.track_entities(&viewers[..1]);
+        let packets = overworld.track_entities(&viewers);
+        assert_eq!(packets.len(), 1);
+        assert!(matches!(&packets[0].1, PacketData::SpawnEntity(_, spawn) if spawn.id == far));
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_player_movement() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let y = overworld.settings().height.min_y() + 4;
+        let start = Vec3::new(0.5, y as f32, 0.5);
+        let to = |pos| PlayerMove { pos, rotation: Vec2::new(90.0, 10.0), on_ground: true };
+
+        let (player, teleport) = overworld.spawn_player(start).unwrap();
+        overworld.tick(1, 0);
+
+        //Moves are ignored until the client knows where the player is
+        assert_eq!(overworld.move_player(player, to(start + Vec3::X * 0.5)).unwrap(), MoveOutcome::Ignored);
+        assert!(!overworld.confirm_teleport(player, teleport.id + 1).unwrap());
+        assert!(overworld.confirm_teleport(player, teleport.id).unwrap());
+
+        assert_eq!(overworld.move_player(player, to(start + Vec3::X * 0.5)).unwrap(), MoveOutcome::Accepted);
+        let entity = overworld.chunks().entities().get(player).unwrap();
+        assert_eq!(entity.pos(), start + Vec3::X * 0.5);
+        assert_eq!(entity.state().rotation, Vec2::new(90.0, 10.0));
+        assert!(entity.state().on_ground);
+
+        //Too far for a single tick, so the player is put back
+        let teleport = match overworld.move_player(player, to(start + Vec3::X * 3.0)).unwrap() {
+            MoveOutcome::Rejected(teleport) => teleport,
+            other => panic!("Expected the move to be rejected, got {:?}", other),
+        };
+        assert_eq!(teleport.pos, start + Vec3::X * 0.5);
+        assert_eq!(overworld.move_player(player, to(start)).unwrap(), MoveOutcome::Ignored);
+        assert!(overworld.confirm_teleport(player, teleport.id).unwrap());
+
+        //With enough ticks the same distance is fine, but not through a wall or into the ground
+        for time in 2..10 {
+            overworld.tick(time, 0);
+        }
+
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        overworld.set_block(BlockPos::new(2, y, 0), stone).unwrap();
+        overworld.set_block(BlockPos::new(2, y + 1, 0), stone).unwrap();
+
+        assert!(matches!(overworld.move_player(player, to(start + Vec3::X * 3.0)).unwrap(), MoveOutcome::Rejected(_)));
+        overworld.confirm_teleport(player, teleport.id + 1).unwrap();
+        assert!(matches!(overworld.move_player(player, to(start - Vec3::Y)).unwrap(), MoveOutcome::Rejected(_)));
+        overworld.confirm_teleport(player, teleport.id + 2).unwrap();
+        assert!(matches!(overworld.move_player(player, to(Vec3::NAN)).unwrap(), MoveOutcome::Rejected(_)));
+        overworld.confirm_teleport(player, teleport.id + 3).unwrap();
+
+        let around_wall = start + Vec3::new(0.5, 0.0, 1.5);
+        assert_eq!(overworld.move_player(player, to(around_wall)).unwrap(), MoveOutcome::Accepted);
+
+        //Falling costs nothing, but isn't unlimited either, even where there's nothing to fall onto
+        let end = world.dimensions_mut().get_mut(DimensionId::END).unwrap();
+        let high = Vec3::new(0.5, end.settings().height.min_y() as f32 + 100.0, 0.5);
+        let (faller, teleport) = end.spawn_player(high).unwrap();
+        end.confirm_teleport(faller, teleport.id).unwrap();
+
+        assert!(matches!(end.move_player(faller, to(high - Vec3::Y * 1.0e9)).unwrap(), MoveOutcome::Rejected(_)));
+        end.confirm_teleport(faller, teleport.id + 1).unwrap();
+        assert_eq!(end.move_player(faller, to(high - Vec3::Y * 10.0)).unwrap(), MoveOutcome::Accepted);
+
+        //Players belong to their client and aren't saved with the world
+        world.save().unwrap();
+        drop(world);
+
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        overworld.chunks_mut().get_chunk(&ChunkPos::new(0, 0)).unwrap();
+        assert_eq!(overworld.chunks().entities().len(), 0);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_player_inventory() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let (player, _) = overworld.spawn_player(Vec3::new(0.5, 0.0, 0.5)).unwrap();
+        let click = |slot, kind| InventoryClick { window: PlayerInventory::WINDOW, slot, kind };
+
+        let stone = ItemStack::of("stone", 10).unwrap();
+        let pickaxe = ItemStack::of("wooden_pickaxe", 1).unwrap();
+
+        overworld
+            .with_player(player, |player, _, _| {
+                player.inventory_mut().inventory_mut().insert(stone.clone());
+                player.inventory_mut().inventory_mut().set(PlayerInventory::HOTBAR_SIZE, Some(pickaxe.clone()))
+            })
+            .unwrap();
+
+        //The clicked slot and the cursor are always sent back
+        let updates = overworld.with_player(player, |player, _, _| player.handle_click(click(0, ClickKind::Right)));
+        assert_eq!(updates.unwrap(), vec![
+            SlotUpdate { window: 0, slot: 0, stack: Some(stone.clone().with_count(5)) },
+            SlotUpdate { window: 0, slot: SlotUpdate::CURSOR, stack: Some(stone.clone().with_count(5)) }
+        ]);
+
+        //Along with anything else that changed
+        let quick_move = click(PlayerInventory::HOTBAR_SIZE as u16, ClickKind::QuickMove);
+        let updates = overworld.with_player(player, |player, _, _| player.handle_click(quick_move)).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0], SlotUpdate { window: 0, slot: 1, stack: Some(pickaxe.clone()) });
+        assert_eq!(updates[1], SlotUpdate { window: 0, slot: PlayerInventory::HOTBAR_SIZE as u16, stack: None });
+
+        //Clicks outside of the inventory change nothing
+        let outside = click(PlayerInventory::SIZE as u16, ClickKind::Left);
+        assert!(overworld.with_player(player, |player, _, _| player.handle_click(outside)).is_err());
+        let other_window = InventoryClick { window: 3, ..click(0, ClickKind::Left) };
+        assert!(overworld.with_player(player, |player, _, _| player.handle_click(other_window)).is_err());
+
+        let items = overworld.with_player(player, |player, _, _| Ok(player.window_items())).unwrap();
+        assert_eq!(items.slots.len(), PlayerInventory::SIZE);
+        assert_eq!(items.slots[0], Some(stone.clone().with_count(5)));
+        assert_eq!(items.slots[1], Some(pickaxe));
+        assert_eq!(items.cursor, Some(stone.with_count(5)));
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[derive(Default)]
+    struct RecordingNetworkHandler {
+        sent: std::sync::Arc<std::sync::Mutex<Vec<Packet>>>, //Shared, so it can be looked at after handing it over
+        incoming: Option<std::sync::mpsc::Receiver<Packet>>,
+    }
+
+    impl NetworkHandler for RecordingNetworkHandler {
+        fn enqueue_packet(&self, packet: Packet) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push(packet);
+            Ok(())
+        }
+
+        fn retrieve_incoming(&mut self) -> Vec<Packet> {
+            self.incoming.as_ref().map_or_else(Vec::new, |incoming| incoming.try_iter().collect())
+        }
+
+        fn close_all(self) {}
+    }
+
+    #[test]
+    pub fn test_login_and_disconnect() {
+        let folder = temp_folder();
+        let world = ServerWorld::open(&folder).unwrap();
+        let (client, other) = (ClientId::new(), ClientId::new());
+        let (incoming, receiver) = std::sync::mpsc::channel();
+        let send = |client: &ClientId, data| {
+            incoming.send(Packet::new(PacketDirection::FromClient(client.clone()), data)).unwrap();
+        };
+
+        let net = RecordingNetworkHandler { incoming: Some(receiver), ..Default::default() };
+        let sent = net.sent.clone();
+        let snapshots = SnapshotManager::new(&folder, &folder.join("backups"));
+        let mut controller = ServerController::new(net, world, std::sync::mpsc::channel().1, snapshots);
+
+        let received = |to: &ClientId, is_kind: fn(&PacketData) -> bool| {
+            sent.lock()
+                .unwrap()
+                .iter()
+                .filter(|packet| matches!(&packet.direction, PacketDirection::ToClient(client) if client == to))
+                .filter(|packet| is_kind(&packet.data))
+                .count()
+        };
+
+        //Logging in twice doesn't get a client a second player, each client sees the other's one
+        send(&client, PacketData::Login);
+        send(&client, PacketData::Login);
+        send(&other, PacketData::Login);
+        controller.tick();
+        assert_eq!(received(&client, |data| matches!(data, PacketData::PlayerTeleport(..))), 1);
+        assert_eq!(received(&client, |data| matches!(data, PacketData::SpawnEntity(..))), 1);
+        assert_eq!(received(&other, |data| matches!(data, PacketData::SpawnEntity(..))), 1);
+
+        //Leaving takes the player with it, and leaving again or without logging in does nothing
+        send(&client, PacketData::Disconnect);
+        send(&client, PacketData::Disconnect);
+        send(&ClientId::new(), PacketData::Disconnect);
+        controller.tick();
+        assert_eq!(received(&other, |data| matches!(data, PacketData::DespawnEntities(_, ids) if ids.len() == 1)), 1);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_digging_and_placing() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let y = overworld.settings().height.min_y() + 4;
+        let (player, _) = overworld.spawn_player(Vec3::new(0.5, y as f32, 0.5)).unwrap();
+        let air = BlockId::default();
+        overworld.tick(1, 0);
+
+        //Breaking takes as long as the block's hardness says, give or take a little
+        let ground = BlockPos::new(2, y - 1, 0);
+        let ticks = player::break_ticks(overworld.get_block(ground.clone()).unwrap().resolve().unwrap()).unwrap();
+        assert!(ticks > 5);
+
+        assert!(overworld.start_digging(player, ground.clone()).unwrap());
+        assert!(!overworld.finish_digging(player, ground.clone()).unwrap());
+        assert_ne!(overworld.get_block(ground.clone()).unwrap(), air);
+
+        assert!(overworld.start_digging(player, ground.clone()).unwrap());
+        overworld.abort_digging(player, &ground).unwrap();
+        overworld.tick(1 + ticks as u64, 0);
+        assert!(!overworld.finish_digging(player, ground.clone()).unwrap());
+
+        assert!(overworld.start_digging(player, ground.clone()).unwrap());
+        overworld.tick(2 + ticks as u64, 0);
+        assert!(!overworld.finish_digging(player, BlockPos::new(3, y - 1, 0)).unwrap());
+        assert!(overworld.start_digging(player, ground.clone()).unwrap());
+        overworld.tick(2 + ticks as u64 * 2, 0);
+        assert!(overworld.finish_digging(player, ground.clone()).unwrap());
+        assert_eq!(overworld.get_block(ground.clone()).unwrap(), air);
+
+        //Nothing out of reach, and nothing without anything to aim at
+        assert!(!overworld.start_digging(player, BlockPos::new(20, y - 1, 0)).unwrap());
+        assert!(!overworld.start_digging(player, BlockPos::new(0, y + 2, 0)).unwrap());
+
+        //Placing needs a block in hand, and takes it from there
+        let click = |pos: BlockPos| PlaceBlock { pos, face: Direction::Up, hit: Vec3::new(0.5, 1.0, 0.5) };
+        let next_to = BlockPos::new(1, y - 1, 0);
+        assert_eq!(overworld.use_held_item(player, click(next_to.clone())).unwrap(), PlaceOutcome::Rejected);
+
+        let stone = ItemStack::of("stone", 2).unwrap();
+        overworld
+            .with_player(player, |player, _, _| player.inventory_mut().inventory_mut().set(0, Some(stone.clone())))
+            .unwrap();
+
+        let above = BlockPos::new(1, y, 0);
+        let update = SlotUpdate { window: 0, slot: 0, stack: Some(stone.with_count(1)) };
+        assert_eq!(
+            overworld.use_held_item(player, click(next_to)).unwrap(),
+            PlaceOutcome::Placed(above.clone(), update)
+        );
+        let stone_block = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        assert_eq!(overworld.get_block(above.clone()).unwrap(), stone_block);
+
+        //Not into anything that isn't replaceable, out of reach or where the player stands
+        for pos in [above.offset(Direction::Down), BlockPos::new(20, y - 1, 0), BlockPos::new(0, y - 1, 0)] {
+            assert_eq!(overworld.use_held_item(player, click(pos)).unwrap(), PlaceOutcome::Rejected);
+        }
+
+        let outside = PlaceBlock { hit: Vec3::new(0.5, 2.0, 0.5), ..click(BlockPos::new(0, y - 1, 1)) };
+        assert_eq!(overworld.use_held_item(player, outside).unwrap(), PlaceOutcome::Rejected);
+
+        //The last one in hand is placed and the slot is empty afterwards
+        let outcome = overworld.use_held_item(player, click(BlockPos::new(0, y - 1, 1))).unwrap();
+        assert!(matches!(outcome, PlaceOutcome::Placed(_, SlotUpdate { stack: None, .. })));
+        assert_eq!(overworld.use_held_item(player, click(BlockPos::new(0, y - 1, 2))).unwrap(), PlaceOutcome::Rejected);
+
+        //A door without room for its top half isn't placed and stays in hand
+        let door = ItemStack::of("oak_door", 1).unwrap();
+        overworld
+            .with_player(player, |player, _, _| player.inventory_mut().inventory_mut().set(0, Some(door.clone())))
+            .unwrap();
+
+        let roof = BlockPos::new(1, y + 1, 1);
+        overworld.set_block(roof.clone(), stone_block).unwrap();
+        assert_eq!(overworld.use_held_item(player, click(BlockPos::new(1, y - 1, 1))).unwrap(), PlaceOutcome::Rejected);
+        assert_eq!(overworld.get_block(roof.offset(Direction::Down)).unwrap(), air);
+        let held = overworld.with_player(player, |player, _, _| Ok(player.inventory().selected_stack().cloned()));
+        assert_eq!(held.unwrap(), Some(door));
+
+        //A slab clicked on its open side becomes a double slab instead of getting another one next to it
+        let slab = ItemStack::of("oak_slab", 2).unwrap();
+        overworld
+            .with_player(player, |player, _, _| player.inventory_mut().inventory_mut().set(0, Some(slab.clone())))
+            .unwrap();
+
+        let slab_pos = BlockPos::new(2, y, 1);
+        overworld.use_held_item(player, click(slab_pos.offset(Direction::Down))).unwrap();
+        let kind = |overworld: &mut ServerDimension| {
+            overworld.get_block(slab_pos.clone()).unwrap().typed::<SlabState>()
+        };
+        assert_eq!(kind(overworld).unwrap().kind(), SlabType::Bottom);
+
+        let onto_slab = PlaceBlock { hit: Vec3::new(0.5, 0.5, 0.5), ..click(slab_pos.clone()) };
+        let outcome = overworld.use_held_item(player, onto_slab).unwrap();
+        assert_eq!(outcome, PlaceOutcome::Placed(slab_pos.clone(), SlotUpdate { window: 0, slot: 0, stack: None }));
+        assert_eq!(kind(overworld).unwrap().kind(), SlabType::Double);
+        assert_eq!(overworld.get_block(slab_pos.offset(Direction::Up)).unwrap(), air);
+
+        //Only clients close enough hear of the changes
+        let far_player = overworld.spawn_player(Vec3::new(500.0, y as f32, 0.5)).unwrap().0;
+        let (near, far) = (ClientId::new(), ClientId::new());
+        let clients = [(near.clone(), DimensionId::OVERWORLD), (far.clone(), DimensionId::OVERWORLD)];
+        let players = [(near.clone(), player), (far.clone(), far_player)];
+        let net = RecordingNetworkHandler::default();
+
+        world.dimensions_mut().broadcast_changes(
+            &net,
+            &clients.into_iter().collect(),
+            &players.into_iter().collect()
+        ).unwrap();
+
+        let sent = net.sent.lock().unwrap();
+        let updates = sent.iter().filter(|packet| matches!(packet.data, PacketData::BlockUpdate(..))).count();
+        assert_eq!(updates, 5);
+        assert!(
+            sent.iter().all(|packet| matches!(&packet.direction, PacketDirection::ToClient(client) if *client == near))
+        );
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[derive(Debug, Default)]
+    struct FallingEntity;
+
+    impl EntityBehaviour for FallingEntity {
+        fn kind(&self) -> &'static str {
+            "falling"
+        }
+
+        fn size(&self) -> Vec2 {
+            Vec2::new(0.5, 0.5)
+        }
+
+        fn physics(&self) -> Option<PhysicsSettings> {
+            Some(PhysicsSettings::default())
+        }
+    }
+
+    #[test]
+    pub fn test_entity_physics() {
+        let folder = temp_folder();
+        let mut world = ServerWorld::open(&folder).unwrap();
+        let overworld = world.dimensions_mut().get_mut(DimensionId::OVERWORLD).unwrap();
+        let y = overworld.settings().height.min_y() as f32 + 4.0;
+
+        let mut entity = Entity::new(Box::new(FallingEntity), Vec3::new(0.5, y + 3.0, 0.5));
+        entity.split_mut().0.velocity = Vec3::new(0.5, 0.0, 0.0);
+        let falling = overworld.spawn_entity(entity).unwrap();
+        let marker = Entity::new(Box::<MarkerEntity>::default(), Vec3::new(0.5, y + 3.0, 0.5));
+        let marker = overworld.spawn_entity(marker).unwrap();
+
+        for time in 1..=40 {
+            overworld.tick(time, 0);
+        }
+
+        //Entities with physics fall onto the ground and slow down there, others float
+        let entity = overworld.chunks().entities().get(falling).unwrap();
+        assert!((entity.pos().y - y).abs() < 1.0e-4);
+        assert!(entity.state().on_ground);
+        assert!(entity.state().velocity.x.abs() < 1.0e-3);
+        assert_eq!(overworld.chunks().entities().get(marker).unwrap().pos().y, y + 3.0);
+
+        std::fs::remove_dir_all(folder).unwrap();
+    }
 }
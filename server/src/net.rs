@@ -1,16 +1,18 @@
 use std::{
-    sync::mpsc::{ Sender, Receiver, channel, TrySendError },
+    sync::mpsc::{ Sender, Receiver, channel, SendError },
     thread::{ spawn },
     net::{ TcpListener, TcpStream },
     io::BufWriter,
 };
 
 use anyhow::{ bail, anyhow };
-use log::error;
+use log::{ error, info };
 use metrohash::MetroHashMap;
 use shared::net::{
-    packet::{ Packet, ClientId, PacketSource, PacketDirection },
+    packet::{ Packet, ClientId, PacketSource, PacketDirection, PacketReader },
+    packet_data::PacketData,
     NetworkHandler,
+    DEFAULT_ADDRESS,
 };
 
 pub struct ServerNetworkHandler {
@@ -20,29 +22,32 @@ pub struct ServerNetworkHandler {
 }
 
 impl ServerNetworkHandler {
+    /// Passes on every packet the client sent that has arrived completely. Returns false once the client closed the
+    /// connection.
     fn receive_client_packets(
         client: &ClientId,
-        stream: &mut TcpStream,
+        (stream, reader): &mut (TcpStream, PacketReader),
         send: &Sender<Packet>
-    ) -> anyhow::Result<()> {
-        while
-            let Some(packet) = Packet::try_from_stream(
-                stream,
-                PacketSource::Client(client.clone())
-            )?
-        {
+    ) -> anyhow::Result<bool> {
+        let open = reader.fill(stream)?;
+        let source = PacketSource::Client(client.clone());
+
+        while let Some(packet) = reader.next_packet(&source)? {
             send.send(packet)?;
         }
 
-        Ok(())
+        Ok(open)
     }
 
     fn dispatch_packet(
         packet: Packet,
-        clients: &MetroHashMap<ClientId, TcpStream>
+        clients: &MetroHashMap<ClientId, (TcpStream, PacketReader)>
     ) -> anyhow::Result<()> {
         let mut stream = if let PacketDirection::ToClient(id) = &packet.direction {
-            clients.get(id).ok_or_else(|| anyhow!("Client with id {:?} doesn't exist!", id))?
+            clients
+                .get(id)
+                .map(|(stream, _)| stream)
+                .ok_or_else(|| anyhow!("Client with id {:?} doesn't exist!", id))?
         } else {
             bail!(
                 "Invalid packet direction: {:?}, expected PacketDirection::ToClient(ClientId)",
@@ -62,11 +67,11 @@ impl ServerNetworkHandler {
         let (send_in, receive_in) = channel();
         let (terminate_sender, terminate_receiver) = channel();
 
-        let listener = TcpListener::bind("127.0.0.1:19354").unwrap();
+        let listener = TcpListener::bind(DEFAULT_ADDRESS).unwrap();
 
         listener.set_nonblocking(true).unwrap();
 
-        let handle_thread = spawn(move || {
+        spawn(move || {
             let mut clients = MetroHashMap::default();
 
             'main_loop: loop {
@@ -76,19 +81,49 @@ impl ServerNetworkHandler {
 
                 let client = listener.accept();
                 if let Ok((stream, _)) = client {
-                    clients.insert(ClientId::new(), stream);
+                    //Accepted streams don't always inherit the listener's mode, and one client mustn't block the rest
+                    match stream.set_nonblocking(true) {
+                        Ok(()) => {
+                            clients.insert(ClientId::new(), (stream, PacketReader::default()));
+                        }
+                        Err(error) => error!("Failed to make the connection of a new client non-blocking: {}", error),
+                    }
                 }
 
-                for (id, stream) in clients.iter_mut() {
-                    if let Err(error) = Self::receive_client_packets(id, stream, &send_in) {
-                        error!("Failed to receive Packet(s) sent by client {:?}: {}", id, error);
+                let mut closed = Vec::new();
 
-                        if error.is::<TrySendError<Packet>>() {
-                            break 'main_loop;
+                for (id, client) in clients.iter_mut() {
+                    match Self::receive_client_packets(id, client, &send_in) {
+                        Ok(true) => (),
+                        Ok(false) => {
+                            info!("Client {:?} closed its connection", id);
+                            closed.push(id.clone());
+                        }
+                        Err(error) => {
+                            if error.is::<SendError<Packet>>() {
+                                break 'main_loop;
+                            }
+
+                            //Either the connection broke or it's no longer clear where the next packet starts
+                            error!("Failed to receive Packet(s) sent by client {:?}: {}", id, error);
+                            closed.push(id.clone());
                         }
                     }
                 }
 
+                for id in closed {
+                    if let Some((stream, _)) = clients.remove(&id) {
+                        let _ = stream.shutdown(std::net::Shutdown::Both);
+                    }
+
+                    //The rest of the server learns about it like about a client that left on its own
+                    let disconnect = Packet::new(PacketDirection::FromClient(id), PacketData::Disconnect);
+
+                    if send_in.send(disconnect).is_err() {
+                        break 'main_loop;
+                    }
+                }
+
                 while let Ok(packet) = receive_out.try_recv() {
                     if let Err(error) = Self::dispatch_packet(packet, &clients) {
                         error!("Failed to dispatch packet: {}", error)
@@ -96,7 +131,7 @@ impl ServerNetworkHandler {
                 }
             }
 
-            for (_, stream) in clients.into_iter() {
+            for (_, (stream, _)) in clients.into_iter() {
                 stream.shutdown(std::net::Shutdown::Both).unwrap();
             }
         });
@@ -0,0 +1,83 @@
+use std::{ path::{ Path, PathBuf }, fs::{ File, OpenOptions }, io::Write };
+
+use log::warn;
+
+const TEMP_EXTENSION: &str = "tmp";
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(TEMP_EXTENSION);
+    path.with_file_name(name)
+}
+
+/// Replaces the file at `path` in a way that a crash at any point leaves either the old or the new contents behind, never a mix.
+/// The data is written and synced to a temporary file first, which is then renamed over the original.
+pub fn write_atomically(path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    let temp = temp_path(path);
+
+    {
+        let mut file = OpenOptions::new().write(true).create(true).truncate(true).open(&temp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+
+    std::fs::rename(&temp, path)?;
+
+    //Make sure the rename itself is on disk as well
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `path` is the temporary file of a write that hasn't finished, or was interrupted.
+pub fn is_temp_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == TEMP_EXTENSION)
+}
+
+/// Removes temporary files left behind by a write that was interrupted by a crash. The original files are still intact in that case.
+pub fn remove_interrupted_writes(folder: &Path) -> anyhow::Result<()> {
+    if !folder.exists() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if is_temp_file(&path) {
+            warn!("Discarding interrupted write {:?}", path);
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A small, seedable random number generator (xorshift64*). Game logic uses it so ticking stays reproducible for a given seed.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        //A state of zero would only ever produce zeroes
+        Self { state: seed ^ 0x9e37_79b9_7f4a_7c15 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number from `0` up to, but not including, `bound`.
+    pub fn next_bounded(&mut self, bound: u32) -> u32 {
+        (((self.next_u64() >> 32) * (bound as u64)) >> 32) as u32
+    }
+}
@@ -0,0 +1,179 @@
+use std::{ path::Path, io::{ BufWriter, Write } };
+
+use anyhow::anyhow;
+use shared::{
+    cbs::{ Packetable, PacketBuf, WriteExt },
+    dimension::id::DimensionId,
+    util::block_pos::BlockPos,
+};
+use uuid::Uuid;
+
+use crate::util::write_atomically;
+
+pub enum WorldInfoError {
+    UnsupportedVersion(u32),
+}
+
+impl From<WorldInfoError> for anyhow::Error {
+    fn from(value: WorldInfoError) -> Self {
+        match value {
+            WorldInfoError::UnsupportedVersion(version) =>
+                anyhow!(
+                    "World info has format version {}, but only versions up to {} are supported",
+                    version,
+                    WorldInfo::FORMAT_VERSION
+                ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRules {
+    pub do_daylight_cycle: bool,
+    pub random_tick_speed: u32,
+    pub keep_inventory: bool,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            do_daylight_cycle: true,
+            random_tick_speed: 3,
+            keep_inventory: false,
+        }
+    }
+}
+
+impl GameRules {
+    fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("doDaylightCycle", self.do_daylight_cycle.to_string()),
+            ("randomTickSpeed", self.random_tick_speed.to_string()),
+            ("keepInventory", self.keep_inventory.to_string())
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        match name {
+            "doDaylightCycle" => {
+                self.do_daylight_cycle = value.parse()?;
+            }
+            "randomTickSpeed" => {
+                self.random_tick_speed = value.parse()?;
+            }
+            "keepInventory" => {
+                self.keep_inventory = value.parse()?;
+            }
+            _ => log::warn!("Ignoring unknown game rule {}={}", name, value),
+        }
+
+        Ok(())
+    }
+
+    fn write<T: Write + Unpin + Send>(&self, buffer: &mut BufWriter<T>) -> anyhow::Result<()> {
+        //Rules are stored by name so adding a new one doesn't require a new format version
+        let entries = self.entries();
+        buffer.write_u16(entries.len() as u16)?;
+
+        for (name, value) in entries {
+            buffer.write_string(name)?;
+            buffer.write_string(&value)?;
+        }
+
+        Ok(())
+    }
+
+    fn read(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        let mut rules = Self::default();
+
+        for _ in 0..reader.next_u16()? {
+            let name = reader.next_string()?;
+            let value = reader.next_string()?;
+            rules.set(&name, &value)?;
+        }
+
+        Ok(rules)
+    }
+}
+
+/// World-level state that doesn't belong to any chunk or dimension.
+#[derive(Debug, Clone)]
+pub struct WorldInfo {
+    pub game_version: String,
+    pub seed: u64,
+    pub spawn_dimension: DimensionId,
+    pub spawn: BlockPos,
+    pub time: u64,
+    pub game_rules: GameRules,
+}
+
+impl WorldInfo {
+    pub const FORMAT_VERSION: u32 = 1;
+    pub const FILE_NAME: &'static str = "level.dat";
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            seed,
+            spawn_dimension: DimensionId::OVERWORLD,
+            spawn: BlockPos::new(0, 0, 0),
+            time: 0,
+            game_rules: GameRules::default(),
+        }
+    }
+
+    pub fn with_random_seed() -> Self {
+        Self::new(Uuid::new_v4().as_u64_pair().0)
+    }
+
+    pub fn tick(&mut self) {
+        self.time += 1;
+    }
+
+    pub fn load(world_folder: &Path) -> anyhow::Result<Option<Self>> {
+        let path = world_folder.join(Self::FILE_NAME);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut reader = PacketBuf::new(std::fs::read(path)?.into_boxed_slice());
+        let version = reader.next_u32()?;
+
+        Self::migrate(version, &mut reader).map(Some)
+    }
+
+    /// Reads world info written in any known format version and converts it to the current layout.
+    /// When the format changes, bump `FORMAT_VERSION` and keep a reader for the old version here.
+    fn migrate(version: u32, reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        match version {
+            1 => Self::read_v1(reader),
+            _ => Err(WorldInfoError::UnsupportedVersion(version).into()),
+        }
+    }
+
+    fn read_v1(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        Ok(Self {
+            game_version: reader.next_string()?,
+            seed: reader.next_u64()?,
+            spawn_dimension: DimensionId::read_from_buf(reader)?,
+            spawn: BlockPos::read_from_buf(reader)?,
+            time: reader.next_u64()?,
+            game_rules: GameRules::read(reader)?,
+        })
+    }
+
+    pub fn save(&self, world_folder: &Path) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(Vec::new());
+
+        writer.write_u32(Self::FORMAT_VERSION)?;
+        writer.write_string(&self.game_version)?;
+        writer.write_u64(self.seed)?;
+        self.spawn_dimension.write_to_buffer(&mut writer)?;
+        self.spawn.clone().write_to_buffer(&mut writer)?;
+        writer.write_u64(self.time)?;
+        self.game_rules.write(&mut writer)?;
+
+        write_atomically(&world_folder.join(Self::FILE_NAME), &writer.into_inner()?)
+    }
+}
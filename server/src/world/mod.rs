@@ -0,0 +1,113 @@
+use std::path::{ Path, PathBuf };
+
+use log::info;
+use shared::block::{ default_handlers, registry::{ BlockRegistry, BlockIdMapping } };
+
+use crate::{
+    dimension::registry::DimensionRegistry,
+    util::{ remove_interrupted_writes, write_atomically },
+};
+
+use self::info::WorldInfo;
+
+pub mod info;
+
+pub struct ServerWorld {
+    folder: PathBuf,
+    info: WorldInfo,
+    dimensions: DimensionRegistry,
+}
+
+impl ServerWorld {
+    /// Block definitions next to the server take precedence over the ones shipped with it.
+    pub const BLOCK_DEFINITIONS: &'static str = "blocks.toml";
+
+    /// Opens the world saved in `folder`, creating a new one if there is nothing there yet.
+    pub fn open(folder: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(folder)?;
+        remove_interrupted_writes(folder)?;
+
+        let info = match WorldInfo::load(folder)? {
+            Some(info) => info,
+            None => {
+                let info = WorldInfo::with_random_seed();
+                info!("Creating new world in {:?} with seed {}", folder, info.seed);
+                info.save(folder)?;
+                info
+            }
+        };
+
+        Self::install_block_registry(folder)?;
+
+        let dimensions = DimensionRegistry::with_defaults(folder, info.seed)?;
+
+        for dimension in dimensions.iter() {
+            dimension.chunks().file_loader().prepare()?;
+        }
+
+        Ok(Self {
+            folder: folder.to_path_buf(),
+            info,
+            dimensions,
+        })
+    }
+
+    /// Loads the block definitions with the ids this world was saved with, handing out new ids to blocks that were added since.
+    fn install_block_registry(folder: &Path) -> anyhow::Result<()> {
+        let mapping_path = folder.join(BlockIdMapping::FILE_NAME);
+
+        let mut mapping = if mapping_path.exists() {
+            BlockIdMapping::parse(&std::fs::read_to_string(&mapping_path)?)?
+        } else {
+            BlockIdMapping::default()
+        };
+
+        let definitions_path = Path::new(Self::BLOCK_DEFINITIONS);
+        let definitions = if definitions_path.exists() {
+            std::fs::read_to_string(definitions_path)?
+        } else {
+            BlockRegistry::BUILTIN_DEFINITIONS.to_string()
+        };
+
+        let registry = BlockRegistry::load(&definitions, &mut mapping, &default_handlers())?;
+        write_atomically(&mapping_path, mapping.serialize()?.as_bytes())?;
+        registry.install()?;
+
+        Ok(())
+    }
+
+    pub fn info(&self) -> &WorldInfo {
+        &self.info
+    }
+
+    pub fn dimensions(&self) -> &DimensionRegistry {
+        &self.dimensions
+    }
+
+    pub fn dimensions_mut(&mut self) -> &mut DimensionRegistry {
+        &mut self.dimensions
+    }
+
+    pub fn tick(&mut self) {
+        self.info.tick();
+
+        for dimension in self.dimensions.iter_mut() {
+            dimension.tick(self.info.time, self.info.game_rules.random_tick_speed);
+        }
+    }
+
+    /// Writes all modified chunks of every dimension and the world info to disk. Only returns once everything has been synced.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let mut chunks = 0;
+
+        for dimension in self.dimensions.iter_mut() {
+            chunks += dimension.chunks_mut().save_dirty()?;
+        }
+
+        self.info.save(&self.folder)?;
+
+        info!("Saved {} chunk(s)", chunks);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+use criterion::{ black_box, criterion_group, criterion_main, Criterion };
+use shared::block::{ BlockId, simple::GrassState, registry::BlockRegistry };
+
+fn block_lookup(c: &mut Criterion) {
+    let registry = BlockRegistry::builtin().install().unwrap();
+    let grass = BlockId::of(&GrassState::SNOWY).unwrap();
+
+    //Builds the table, so the first iteration isn't an outlier
+    grass.resolve().unwrap();
+
+    c.bench_function("resolve", |b| b.iter(|| black_box(grass).resolve().unwrap().to_id()));
+
+    let ids: Vec<BlockId> = registry
+        .types()
+        .flat_map(|block_type| block_type.states())
+        .collect();
+
+    c.bench_function("resolve every state", |b| {
+        b.iter(|| {
+            for id in &ids {
+                black_box(black_box(*id).resolve().unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, block_lookup);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+use glam::Vec3;
+
+use crate::util::{ block_pos::BlockPos, direction::Direction };
+
+use super::BlockId;
+
+/// Where and how a block is about to be placed.
+#[derive(Debug, Clone)]
+pub struct PlacementContext {
+    pub pos: BlockPos,
+    pub block: BlockId, //The default state of the block being placed
+    pub face: Direction, //The face of the clicked block the new one is placed against
+    pub hit: Vec3, //Where on the clicked block it was hit, from 0 to 1 on each axis
+    pub look: Vec3, //The direction the player is looking in
+}
+
+impl PlacementContext {
+    /// The horizontal direction closest to where the player is looking.
+    pub fn horizontal_facing(&self) -> Direction {
+        Direction::from_vector(Vec3::new(self.look.x, 0.0, self.look.z))
+    }
+
+    /// The direction closest to where the player is looking, including up and down.
+    pub fn nearest_facing(&self) -> Direction {
+        Direction::from_vector(self.look)
+    }
+
+    /// Whether the block goes into the upper half of its space: when placed against the bottom of a block,
+    /// or against a side above its middle.
+    pub fn is_upper_half(&self) -> bool {
+        self.face == Direction::Down || (self.face.is_horizontal() && self.hit.y > 0.5)
+    }
+}
+
+/// A player interacting with a block that is already in the world.
+#[derive(Debug, Clone)]
+pub struct UseContext {
+    pub face: Direction,
+    pub hit: Vec3,
+}
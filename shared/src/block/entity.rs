@@ -0,0 +1,207 @@
+use std::{ any::Any, fmt::Debug };
+
+use metrohash::MetroHashMap;
+use proc_macros::State;
+
+use crate::{
+    util::{ block_pos::BlockPos, chunk_pos::ChunkPos },
+    dimension::access::WorldAccess,
+    tag::Compound,
+};
+
+use super::state::BlockHandler;
+
+/// Data of a single block that doesn't fit into its state, like the text on a sign. Created by the block's
+/// `BlockHandler::create_block_entity` when the block is set, and removed again when it's replaced by another block.
+pub trait BlockEntity: Debug + Send + Sync + Any {
+    fn save(&self) -> Compound;
+
+    /// Restores what `save` wrote, on an entity fresh from `create_block_entity`.
+    fn load(&mut self, data: &Compound) -> anyhow::Result<()>;
+
+    /// Only ticking entities get `tick` called, once every world tick.
+    fn is_ticking(&self) -> bool {
+        false
+    }
+
+    fn tick(&mut self, _world: &mut dyn WorldAccess, _pos: &BlockPos) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// What clients get to see, sent whenever the entity changes. `None` if it's nothing.
+    fn client_data(&self) -> Option<Compound> {
+        None
+    }
+}
+
+impl dyn BlockEntity {
+    pub fn downcast_ref<T: BlockEntity>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: BlockEntity>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
+}
+
+/// The block entities of a chunk, keyed by their position within it.
+#[derive(Debug, Default)]
+pub struct BlockEntities {
+    entities: MetroHashMap<u32, Box<dyn BlockEntity>>,
+}
+
+impl BlockEntities {
+    fn key(pos: &BlockPos) -> u32 {
+        ((pos.y() as i16 as u16 as u32) << 8) | (((pos.z() & 15) as u32) << 4) | ((pos.x() & 15) as u32)
+    }
+
+    fn pos(chunk: &ChunkPos, key: u32) -> BlockPos {
+        BlockPos::new(
+            chunk.x() * 16 + ((key & 15) as i32),
+            (key >> 8) as u16 as i16 as i32,
+            chunk.z() * 16 + (((key >> 4) & 15) as i32)
+        )
+    }
+
+    pub fn get(&self, pos: &BlockPos) -> Option<&dyn BlockEntity> {
+        self.entities.get(&Self::key(pos)).map(|entity| entity.as_ref())
+    }
+
+    pub fn get_mut(&mut self, pos: &BlockPos) -> Option<&mut dyn BlockEntity> {
+        self.entities.get_mut(&Self::key(pos)).map(|entity| entity.as_mut())
+    }
+
+    /// Returns the entity that was there before, if any.
+    pub fn insert(&mut self, pos: &BlockPos, entity: Box<dyn BlockEntity>) -> Option<Box<dyn BlockEntity>> {
+        self.entities.insert(Self::key(pos), entity)
+    }
+
+    pub fn remove(&mut self, pos: &BlockPos) -> Option<Box<dyn BlockEntity>> {
+        self.entities.remove(&Self::key(pos))
+    }
+
+    /// `chunk` is the chunk these entities are in, to turn their keys back into positions.
+    pub fn iter<'a>(&'a self, chunk: &'a ChunkPos) -> impl Iterator<Item = (BlockPos, &'a dyn BlockEntity)> + 'a {
+        self.entities.iter().map(|(key, entity)| (Self::pos(chunk, *key), entity.as_ref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "sign")]
+pub struct SignState;
+
+impl BlockHandler for SignState {
+    fn create_block_entity(&self) -> Option<Box<dyn BlockEntity>> {
+        Some(Box::<SignEntity>::default())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignEntity {
+    lines: [String; 4],
+}
+
+impl SignEntity {
+    pub const LINES: usize = 4;
+
+    pub fn line(&self, index: usize) -> &str {
+        &self.lines[index]
+    }
+
+    pub fn set_line(&mut self, index: usize, text: &str) {
+        self.lines[index] = text.to_string();
+    }
+}
+
+impl BlockEntity for SignEntity {
+    fn save(&self) -> Compound {
+        let mut data = Compound::new();
+
+        for (index, line) in self.lines.iter().enumerate() {
+            data.insert(&format!("line{}", index), line.as_str());
+        }
+
+        data
+    }
+
+    fn load(&mut self, data: &Compound) -> anyhow::Result<()> {
+        for (index, line) in self.lines.iter_mut().enumerate() {
+            *line = data.get_string(&format!("line{}", index))?.to_string();
+        }
+
+        Ok(())
+    }
+
+    fn client_data(&self) -> Option<Compound> {
+        Some(self.save())
+    }
+}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "spawner")]
+pub struct SpawnerState;
+
+impl BlockHandler for SpawnerState {
+    fn create_block_entity(&self) -> Option<Box<dyn BlockEntity>> {
+        Some(Box::new(SpawnerEntity { delay: SpawnerEntity::MIN_DELAY, spawns: 0 }))
+    }
+}
+
+/// Counts down to its next spawn, then starts over with a random delay.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnerEntity {
+    delay: u32,
+    spawns: u32,
+}
+
+impl SpawnerEntity {
+    pub const MIN_DELAY: u32 = 200;
+    pub const MAX_DELAY: u32 = 800;
+
+    /// Ticks until the next spawn.
+    pub fn delay(&self) -> u32 {
+        self.delay
+    }
+
+    pub fn spawns(&self) -> u32 {
+        self.spawns
+    }
+}
+
+impl BlockEntity for SpawnerEntity {
+    fn save(&self) -> Compound {
+        Compound::new()
+            .with("delay", self.delay as i32)
+            .with("spawns", self.spawns as i32)
+    }
+
+    fn load(&mut self, data: &Compound) -> anyhow::Result<()> {
+        self.delay = data.get_int("delay")? as u32;
+        self.spawns = data.get_int("spawns")? as u32;
+
+        Ok(())
+    }
+
+    fn is_ticking(&self) -> bool {
+        true
+    }
+
+    fn tick(&mut self, world: &mut dyn WorldAccess, _pos: &BlockPos) -> anyhow::Result<()> {
+        if self.delay > 0 {
+            self.delay -= 1;
+        } else {
+            self.spawns += 1;
+            self.delay = Self::MIN_DELAY + world.random(Self::MAX_DELAY - Self::MIN_DELAY);
+        }
+
+        Ok(())
+    }
+}
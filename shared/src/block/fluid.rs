@@ -0,0 +1,400 @@
+use proc_macros::State;
+
+use crate::{
+    util::{ block_pos::BlockPos, direction::Direction },
+    dimension::access::WorldAccess,
+    error::block::BlockRegistryError,
+};
+
+use super::{ state::BlockHandler, registry::BlockRegistry, simple::AirState, BlockId };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FluidKind {
+    Water,
+    Lava,
+}
+
+impl FluidKind {
+    /// Ticks between two steps of spreading.
+    pub fn tick_delay(&self) -> u64 {
+        match self {
+            Self::Water => 5,
+            Self::Lava => 30,
+        }
+    }
+
+    /// How much the level goes up with every block the fluid flows sideways.
+    pub fn level_drop(&self) -> u8 {
+        match self {
+            Self::Water => 1,
+            Self::Lava => 2,
+        }
+    }
+
+    /// How many blocks sideways the fluid looks for a place to flow down.
+    pub fn drop_off_distance(&self) -> u32 {
+        match self {
+            Self::Water => 4,
+            Self::Lava => 2,
+        }
+    }
+}
+
+/// The state of any fluid block. Level 0 is a source, higher levels are flowing fluid that many steps away from it.
+/// Falling fluid is fed from above, and spreads sideways as if it were a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FluidState {
+    pub kind: FluidKind,
+    pub level: u8,
+    pub falling: bool,
+}
+
+impl FluidState {
+    pub const MAX_LEVEL: u8 = 7;
+
+    pub fn source(kind: FluidKind) -> Self {
+        Self { kind, level: 0, falling: false }
+    }
+
+    /// The fluid state of a block, if it is a fluid.
+    pub fn of(id: BlockId) -> Option<Self> {
+        id.typed::<WaterState>()
+            .map(|state| Self { kind: FluidKind::Water, level: state.level, falling: state.falling })
+            .or_else(||
+                id
+                    .typed::<LavaState>()
+                    .map(|state| Self { kind: FluidKind::Lava, level: state.level, falling: state.falling })
+            )
+    }
+
+    pub fn id(&self) -> anyhow::Result<BlockId> {
+        let (level, falling) = (self.level, self.falling);
+
+        match self.kind {
+            FluidKind::Water => BlockId::of(&WaterState { level, falling }),
+            FluidKind::Lava => BlockId::of(&LavaState { level, falling }),
+        }
+    }
+
+    pub fn is_source(&self) -> bool {
+        self.level == 0 && !self.falling
+    }
+
+    fn spread_level(&self) -> u8 {
+        if self.falling { 0 } else { self.level }
+    }
+
+    fn falling(&self) -> Self {
+        Self { kind: self.kind, level: 0, falling: true }
+    }
+
+    fn fluid_at(world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<Option<Self>> {
+        if !world.height().contains_y(pos.y()) || !world.is_loaded(pos) {
+            return Ok(None);
+        }
+
+        Ok(Self::of(world.get_block(pos.clone())?))
+    }
+
+    /// Runs when the fluid is placed or one of its neighbours changed.
+    fn on_changed(self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        if self.kind == FluidKind::Lava && self.solidify(world, &pos)? {
+            return Ok(());
+        }
+
+        world.schedule_tick(pos, self.kind.tick_delay(), 0)
+    }
+
+    /// Lava with water next to or above it turns into obsidian if it's a source, and into cobblestone otherwise.
+    fn solidify(self, world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<bool> {
+        for direction in Direction::LOOKUP {
+            if direction == Direction::Down {
+                continue;
+            }
+
+            let touches_water = Self::fluid_at(world, &pos.offset(direction))?.is_some_and(
+                |fluid| fluid.kind == FluidKind::Water
+            );
+
+            if touches_water {
+                let block = named_block(if self.is_source() { "obsidian" } else { "cobblestone" })?;
+                world.set_block(pos.clone(), block)?;
+
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn on_scheduled_tick(self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        if !self.is_source() {
+            let next = self.next_state(world, &pos)?;
+
+            //Changing the block schedules the next tick, so the spreading happens then
+            if next != Some(self) {
+                let block = match next {
+                    Some(fluid) => fluid.id()?,
+                    None => BlockId::of(&AirState)?,
+                };
+
+                return world.set_block(pos, block);
+            }
+        }
+
+        self.spread(world, pos)
+    }
+
+    /// What flowing fluid turns into with the blocks around it as they are now, `None` if nothing feeds it anymore.
+    fn next_state(self, world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<Option<Self>> {
+        if Self::fluid_at(world, &pos.offset(Direction::Up))?.is_some_and(|fluid| fluid.kind == self.kind) {
+            return Ok(Some(self.falling()));
+        }
+
+        let mut sources = 0;
+        let mut lowest = None;
+
+        for direction in Direction::HORIZONTALS {
+            let Some(fluid) = Self::fluid_at(world, &pos.offset(direction))? else {
+                continue;
+            };
+
+            if fluid.kind != self.kind {
+                continue;
+            }
+
+            if fluid.is_source() {
+                sources += 1;
+            }
+
+            lowest = Some(lowest.map_or(fluid.spread_level(), |level: u8| level.min(fluid.spread_level())));
+        }
+
+        //Water between two sources becomes a source itself, as long as it has something to rest on
+        if self.kind == FluidKind::Water && sources >= 2 {
+            let below = pos.offset(Direction::Down);
+            let supported = match Self::fluid_at(world, &below)? {
+                Some(fluid) => fluid == Self::source(self.kind),
+                None =>
+                    world.height().contains_y(below.y()) &&
+                        world.is_loaded(&below) &&
+                        !world.get_block(below.clone())?.resolve()?.is_replaceable(below),
+            };
+
+            if supported {
+                return Ok(Some(Self::source(self.kind)));
+            }
+        }
+
+        Ok(
+            lowest
+                .map(|level| level + self.kind.level_drop())
+                .filter(|level| *level <= Self::MAX_LEVEL)
+                .map(|level| Self { kind: self.kind, level, falling: false })
+        )
+    }
+
+    /// Flows down if it can, and sideways towards the nearest drop-off if it can't or is a source.
+    fn spread(self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        let below = pos.offset(Direction::Down);
+
+        if self.can_flow_into(world, &below, self.falling())? {
+            self.flow_into(world, below, self.falling())?;
+
+            if !self.is_source() {
+                return Ok(());
+            }
+        }
+
+        let level = self.spread_level() + self.kind.level_drop();
+
+        if level > Self::MAX_LEVEL {
+            return Ok(());
+        }
+
+        let flowing = Self { kind: self.kind, level, falling: false };
+
+        for direction in self.spread_directions(world, &pos, flowing)? {
+            self.flow_into(world, pos.offset(direction), flowing)?;
+        }
+
+        Ok(())
+    }
+
+    /// The directions with the shortest way to a drop-off, or every open direction if there is no drop-off close enough.
+    fn spread_directions(
+        self,
+        world: &mut dyn WorldAccess,
+        pos: &BlockPos,
+        flowing: Self
+    ) -> anyhow::Result<Vec<Direction>> {
+        let mut best = u32::MAX;
+        let mut directions = Vec::new();
+
+        for direction in Direction::HORIZONTALS {
+            let target = pos.offset(direction);
+
+            if !self.can_flow_into(world, &target, flowing)? {
+                continue;
+            }
+
+            let distance = self.drop_off_distance(world, &target, direction.opposite(), 1)?.unwrap_or(u32::MAX);
+
+            if distance < best {
+                best = distance;
+                directions.clear();
+            }
+
+            if distance == best {
+                directions.push(direction);
+            }
+        }
+
+        Ok(directions)
+    }
+
+    fn drop_off_distance(
+        self,
+        world: &mut dyn WorldAccess,
+        pos: &BlockPos,
+        from: Direction,
+        depth: u32
+    ) -> anyhow::Result<Option<u32>> {
+        if self.can_flow_into(world, &pos.offset(Direction::Down), self.falling())? {
+            return Ok(Some(0));
+        }
+
+        if depth >= self.kind.drop_off_distance() {
+            return Ok(None);
+        }
+
+        let mut shortest = None;
+
+        for direction in Direction::HORIZONTALS {
+            let next = pos.offset(direction);
+
+            if direction == from || !self.is_open(world, &next)? {
+                continue;
+            }
+
+            if let Some(distance) = self.drop_off_distance(world, &next, direction.opposite(), depth + 1)? {
+                shortest = Some(shortest.map_or(distance + 1, |shortest: u32| shortest.min(distance + 1)));
+            }
+        }
+
+        Ok(shortest)
+    }
+
+    /// Whether the fluid could ever flow through `pos`.
+    fn is_open(self, world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<bool> {
+        if !world.height().contains_y(pos.y()) || !world.is_loaded(pos) {
+            return Ok(false);
+        }
+
+        let block = world.get_block(pos.clone())?;
+
+        match Self::of(block) {
+            Some(fluid) => Ok(fluid.kind == self.kind && !fluid.is_source()),
+            None => Ok(block.resolve()?.is_replaceable(pos.clone())),
+        }
+    }
+
+    /// Fluid only replaces fluid of its own kind that's further from a source, and lava only flows down into water.
+    fn can_flow_into(self, world: &mut dyn WorldAccess, pos: &BlockPos, state: Self) -> anyhow::Result<bool> {
+        if !world.height().contains_y(pos.y()) || !world.is_loaded(pos) {
+            return Ok(false);
+        }
+
+        let block = world.get_block(pos.clone())?;
+
+        match Self::of(block) {
+            Some(fluid) if fluid.kind == self.kind =>
+                Ok(!fluid.is_source() && !fluid.falling && (state.falling || fluid.level > state.level)),
+            Some(_) => Ok(self.kind == FluidKind::Lava && state.falling),
+            None => Ok(block.resolve()?.is_replaceable(pos.clone())),
+        }
+    }
+
+    fn flow_into(self, world: &mut dyn WorldAccess, pos: BlockPos, state: Self) -> anyhow::Result<()> {
+        let block = world.get_block(pos.clone())?;
+
+        if self.kind == FluidKind::Lava && Self::of(block).is_some_and(|fluid| fluid.kind == FluidKind::Water) {
+            return world.set_block(pos, named_block("stone")?);
+        }
+
+        world.set_block(pos, state.id()?)
+    }
+}
+
+/// The default state of a block that only exists in the definitions file.
+fn named_block(name: &str) -> anyhow::Result<BlockId> {
+    BlockRegistry::global()?
+        .get_by_name(name)
+        .map(|block_type| block_type.default_state())
+        .ok_or_else(|| BlockRegistryError::UnknownBlock(name.to_string()).into())
+}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "water")]
+pub struct WaterState {
+    #[property(min = 0, max = 7)]
+    level: u8,
+    falling: bool,
+}
+
+impl WaterState {
+    pub const SOURCE: Self = Self { level: 0, falling: false };
+}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "lava")]
+pub struct LavaState {
+    #[property(min = 0, max = 7)]
+    level: u8,
+    falling: bool,
+}
+
+impl LavaState {
+    pub const SOURCE: Self = Self { level: 0, falling: false };
+}
+
+macro_rules! fluid_handler {
+    ($state:ty, $kind:expr) => {
+        impl BlockHandler for $state {
+            fn is_replaceable(&self, _pos: BlockPos) -> bool {
+                true
+            }
+
+            fn on_placed(
+                &self,
+                world: &mut dyn WorldAccess,
+                pos: BlockPos,
+                _previous: BlockId
+            ) -> anyhow::Result<()> {
+                self.fluid().on_changed(world, pos)
+            }
+
+            fn on_neighbor_changed(
+                &self,
+                world: &mut dyn WorldAccess,
+                pos: BlockPos,
+                _direction: Direction
+            ) -> anyhow::Result<()> {
+                self.fluid().on_changed(world, pos)
+            }
+
+            fn on_scheduled_tick(&self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+                self.fluid().on_scheduled_tick(world, pos)
+            }
+        }
+
+        impl $state {
+            pub fn fluid(&self) -> FluidState {
+                FluidState { kind: $kind, level: self.level, falling: self.falling }
+            }
+        }
+    };
+}
+
+fluid_handler!(WaterState, FluidKind::Water);
+fluid_handler!(LavaState, FluidKind::Lava);
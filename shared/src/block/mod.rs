@@ -1,62 +1,208 @@
 pub mod state;
 pub mod simple;
+pub mod registry;
+pub mod property;
+pub mod palette;
+pub mod context;
+pub mod fluid;
+pub mod shape;
+pub mod orientable;
+pub mod entity;
 
-use std::io::{ Write, BufWriter };
-
-use proc_macros::count_ids;
+use std::{ io::{ Write, BufWriter }, fmt::Debug };
 
 use crate::{
-    util::block_pos::BlockPos,
-    error::{ block::* },
+    util::{ block_pos::BlockPos, direction::Direction },
     cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt },
+    dimension::access::WorldAccess,
 };
 
-use self::{simple::*, state::*, state::BlockHandler};
-
-mod cache {
-    use metrohash::MetroHashMap;
-    use once_cell::sync::Lazy;
+use self::{
+    simple::*,
+    fluid::{ WaterState, LavaState },
+    orientable::{ LogState, SlabState, StairState, DoorState },
+    entity::{ BlockEntity, SignState, SpawnerState },
+    state::*,
+    registry::{ BlockRegistry, BlockType, BlockHandlers },
+    property::{ Property, PropertyValue },
+    context::{ PlacementContext, UseContext },
+    shape::BlockShape,
+};
 
-    use super::Block;
+/// Every block that has behaviour implemented in code. Blocks from the definitions file that aren't listed here use the default `BlockHandler`.
+pub fn default_handlers() -> BlockHandlers {
+    let mut handlers = BlockHandlers::default();
 
-    static mut CACHE: Lazy<MetroHashMap<u16, Block>> = Lazy::new(MetroHashMap::default);
+    handlers.register::<AirState>();
+    handlers.register::<GrassState>();
+    handlers.register::<SnowState>();
+    handlers.register::<DirtState>();
+    handlers.register::<WaterState>();
+    handlers.register::<LavaState>();
+    handlers.register::<LogState>();
+    handlers.register::<SlabState>();
+    handlers.register::<StairState>();
+    handlers.register::<DoorState>();
+    handlers.register::<SignState>();
+    handlers.register::<SpawnerState>();
 
-    pub fn get_cache() -> &'static mut Lazy<MetroHashMap<u16, Block>> {
-        unsafe {
-            &mut CACHE
-        } //race conditions etc shouldnt be an issue since even if two block states are inserted at the same time, theyre the same anyway
-    }
+    handlers
 }
 
-#[count_ids]
-#[derive(Debug, Clone)]
-pub enum Block {
-    Air(AirState),
-    Grass(GrassState),
+/// A single block state, resolved from its `BlockId` through the `BlockRegistry`.
+pub struct Block {
+    id: BlockId,
+    block_type: &'static BlockType,
+    handler: Box<dyn BlockHandler + Send + Sync>,
+    collision: BlockShape,
+    outline: BlockShape,
 }
 
 impl Block {
+    pub const AIR: &'static str = AirState::NAME;
+
+    fn new(
+        id: BlockId,
+        block_type: &'static BlockType,
+        handler: Box<dyn BlockHandler + Send + Sync>
+    ) -> Self {
+        let definition = block_type.definition();
+        let collision = handler.get_collision_shape().unwrap_or_else(|| definition.collision.shape());
+        let outline = handler
+            .get_outline_shape()
+            .or_else(|| definition.outline.map(|outline| outline.shape()))
+            .unwrap_or_else(|| collision.clone());
+
+        Self { id, block_type, handler, collision, outline }
+    }
+
     pub fn to_id(&self) -> BlockId {
-        BlockId(((self.repr() as u16) << 8) | (self.variant_id() as u16))
+        self.id
+    }
+
+    pub fn block_type(&self) -> &'static BlockType {
+        self.block_type
+    }
+
+    pub fn name(&self) -> &str {
+        self.block_type.name()
+    }
+
+    /// How long the block takes to break.
+    pub fn hardness(&self) -> f32 {
+        self.block_type.definition().hardness
+    }
+
+    /// How well the block withstands explosions.
+    pub fn blast_resistance(&self) -> f32 {
+        let definition = self.block_type.definition();
+
+        definition.blast_resistance.unwrap_or(definition.hardness)
+    }
+
+    /// How much of their speed entities keep each tick while moving on top of the block.
+    pub fn friction(&self) -> f32 {
+        self.block_type.definition().friction
+    }
+
+    /// How much light the block absorbs, from 0 for none to 15 for all of it.
+    pub fn opacity(&self) -> u8 {
+        self.block_type.definition().opacity
+    }
+
+    /// How much light the block gives off, from 0 for none to 15 for the brightest.
+    pub fn emission(&self) -> u8 {
+        self.block_type.definition().emission
+    }
+
+    /// What entities collide with.
+    pub fn collision_shape(&self) -> &BlockShape {
+        &self.collision
+    }
+
+    /// What players aim at.
+    pub fn outline_shape(&self) -> &BlockShape {
+        &self.outline
+    }
+}
+
+impl Debug for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Block").field("name", &self.name()).field("id", &self.id).finish()
     }
 }
 
 impl Default for &Block {
     fn default() -> Self {
-        cache::get_cache().entry(0).or_insert(Block::Air(AirState::DEFAULT))
+        BlockId::default().resolve().expect("Air is always registered.")
     }
 }
 
 impl BlockHandler for Block {
     fn is_replaceable(&self, pos: BlockPos) -> bool {
-        self.inner_handler().is_replaceable(pos)
+        self.handler.is_replaceable(pos)
     }
-}
 
+    fn map_color(&self) -> i32 {
+        self.handler.map_color()
+    }
 
-unsafe impl Send for Block {}
+    fn create_block_entity(&self) -> Option<Box<dyn BlockEntity>> {
+        self.handler.create_block_entity()
+    }
+
+    fn get_collision_shape(&self) -> Option<BlockShape> {
+        Some(self.collision.clone())
+    }
 
-#[derive(Default, Debug, Clone, Copy)]
+    fn get_outline_shape(&self) -> Option<BlockShape> {
+        Some(self.outline.clone())
+    }
+
+    fn get_placement_state(
+        &self,
+        world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        self.handler.get_placement_state(world, context)
+    }
+
+    fn can_merge(&self, context: &PlacementContext) -> bool {
+        self.handler.can_merge(context)
+    }
+
+    fn on_placed(&self, world: &mut dyn WorldAccess, pos: BlockPos, previous: BlockId) -> anyhow::Result<()> {
+        self.handler.on_placed(world, pos, previous)
+    }
+
+    fn on_broken(&self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        self.handler.on_broken(world, pos)
+    }
+
+    fn on_neighbor_changed(
+        &self,
+        world: &mut dyn WorldAccess,
+        pos: BlockPos,
+        direction: Direction
+    ) -> anyhow::Result<()> {
+        self.handler.on_neighbor_changed(world, pos, direction)
+    }
+
+    fn on_use(&self, world: &mut dyn WorldAccess, pos: BlockPos, context: &UseContext) -> anyhow::Result<bool> {
+        self.handler.on_use(world, pos, context)
+    }
+
+    fn on_random_tick(&self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        self.handler.on_random_tick(world, pos)
+    }
+
+    fn on_scheduled_tick(&self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        self.handler.on_scheduled_tick(world, pos)
+    }
+}
+
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BlockId(pub u16);
 
 impl FixedSizePacketable for BlockId {
@@ -79,17 +225,47 @@ impl Packetable for BlockId {
 }
 
 impl BlockId {
-    pub fn resolve(&self) -> anyhow::Result<&'static Block> {
-        let map = cache::get_cache();
+    /// The value of a property in this state, if the block has that property.
+    pub fn get<T: PropertyValue>(&self, property: &Property<T>) -> Option<T> {
+        let block_type = BlockRegistry::global().ok()?.get(self.0 >> 8)?;
+        let (stride, count) = block_type.property_layout(property)?;
+
+        T::from_index(((self.0 & 255) as usize / stride) % count, property)
+    }
 
-        if let std::collections::hash_map::Entry::Vacant(e) = map.entry(self.0) {
-            e.insert(Block::from_ints((self.0 >> 8) as u8, (self.0 & 255) as u8)?);
-        };
+    /// This state with one property changed, if the block has that property.
+    pub fn with<T: PropertyValue>(&self, property: &Property<T>, value: T) -> Option<BlockId> {
+        let block_type = BlockRegistry::global().ok()?.get(self.0 >> 8)?;
+        let (stride, count) = block_type.property_layout(property)?;
 
-        Ok(
-            map
-                .get(&self.0)
-                .expect("This is impossible as the value either existed or was just inserted.")
-        )
+        let state = (self.0 & 255) as usize;
+        let old = (state / stride) % count;
+        let new = value.to_index(property);
+
+        if new >= count {
+            return None;
+        }
+
+        Some(BlockId((self.0 & !255) | ((state - old * stride + new * stride) as u16)))
     }
-}
\ No newline at end of file
+
+    /// The typed state, if this is a state of the block `S`.
+    pub fn typed<S: State>(&self) -> Option<S> {
+        let block_type = BlockRegistry::global().ok()?.get(self.0 >> 8)?;
+
+        if block_type.name() != S::NAME {
+            return None;
+        }
+
+        S::from_id((self.0 & 255) as u8).ok()
+    }
+
+    /// The id of a typed block state in the installed registry.
+    pub fn of<S: State>(state: &S) -> anyhow::Result<Self> {
+        BlockRegistry::global()?.id_of(state)
+    }
+
+    pub fn resolve(&self) -> anyhow::Result<&'static Block> {
+        BlockRegistry::global()?.block(*self)
+    }
+}
@@ -0,0 +1,292 @@
+use proc_macros::State;
+
+use crate::{
+    util::{ block_pos::BlockPos, direction::{ Axis, Direction } },
+    dimension::access::WorldAccess,
+};
+
+use super::{
+    state::BlockHandler,
+    property::EnumProperty,
+    context::{ PlacementContext, UseContext },
+    shape::BlockShape,
+    BlockId,
+};
+
+/// Which half of its space a block takes up, or which half of a two block tall block it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Half {
+    #[default]
+    Bottom,
+    Top,
+}
+
+impl EnumProperty for Half {
+    const VALUES: &'static [Self] = &[Self::Bottom, Self::Top];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bottom => "bottom",
+            Self::Top => "top",
+        }
+    }
+}
+
+impl Half {
+    fn of(context: &PlacementContext) -> Self {
+        if context.is_upper_half() { Self::Top } else { Self::Bottom }
+    }
+
+    pub fn other(&self) -> Self {
+        match self {
+            Self::Bottom => Self::Top,
+            Self::Top => Self::Bottom,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlabType {
+    #[default]
+    Bottom,
+    Top,
+    Double,
+}
+
+impl EnumProperty for SlabType {
+    const VALUES: &'static [Self] = &[Self::Bottom, Self::Top, Self::Double];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Bottom => "bottom",
+            Self::Top => "top",
+            Self::Double => "double",
+        }
+    }
+}
+
+/// Lies along the axis of the face it was placed against.
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "oak_log")]
+pub struct LogState {
+    axis: Axis,
+}
+
+impl LogState {
+    pub fn new(axis: Axis) -> Self {
+        Self { axis }
+    }
+
+    pub fn axis(&self) -> Axis {
+        self.axis
+    }
+}
+
+impl BlockHandler for LogState {
+    fn get_placement_state(
+        &self,
+        _world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        BlockId::of(&Self { axis: context.face.axis() })
+    }
+}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "oak_slab")]
+pub struct SlabState {
+    #[property(name = "type")]
+    kind: SlabType,
+}
+
+impl SlabState {
+    pub fn new(kind: SlabType) -> Self {
+        Self { kind }
+    }
+
+    pub fn kind(&self) -> SlabType {
+        self.kind
+    }
+}
+
+impl BlockHandler for SlabState {
+    /// Placing a slab into the other half of a slab makes it a double slab.
+    fn get_placement_state(
+        &self,
+        world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        let existing = world.get_block(context.pos.clone())?.typed::<Self>();
+
+        let kind = match existing {
+            Some(slab) if slab.kind != SlabType::Double => SlabType::Double,
+            _ if context.is_upper_half() => SlabType::Top,
+            _ => SlabType::Bottom,
+        };
+
+        BlockId::of(&Self { kind })
+    }
+
+    /// Only when clicking the face in the middle of the block, which the other half goes against.
+    fn can_merge(&self, context: &PlacementContext) -> bool {
+        let open_face = match self.kind {
+            SlabType::Bottom => Direction::Up,
+            SlabType::Top => Direction::Down,
+            SlabType::Double => return false,
+        };
+
+        context.face == open_face && context.block.typed::<Self>().is_some()
+    }
+
+    fn get_collision_shape(&self) -> Option<BlockShape> {
+        Some(match self.kind {
+            SlabType::Bottom => BlockShape::slab(false),
+            SlabType::Top => BlockShape::slab(true),
+            SlabType::Double => BlockShape::full(),
+        })
+    }
+}
+
+/// The step is on the side the player was looking towards, so walking on in that direction goes up the stairs.
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "oak_stairs")]
+pub struct StairState {
+    facing: Direction,
+    half: Half,
+}
+
+impl StairState {
+    pub fn new(facing: Direction, half: Half) -> Self {
+        Self { facing, half }
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    pub fn half(&self) -> Half {
+        self.half
+    }
+}
+
+impl BlockHandler for StairState {
+    fn get_placement_state(
+        &self,
+        _world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        BlockId::of(&Self { facing: context.horizontal_facing(), half: Half::of(context) })
+    }
+
+    fn get_collision_shape(&self) -> Option<BlockShape> {
+        Some(BlockShape::stair(self.facing, self.half == Half::Top))
+    }
+}
+
+/// A two block tall door. Both halves have the full state, so each can be handled without looking at the other,
+/// and when one of them goes away, so does the other.
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "oak_door")]
+pub struct DoorState {
+    facing: Direction,
+    half: Half,
+    open: bool,
+}
+
+impl DoorState {
+    pub const THICKNESS: f32 = 3.0 / 16.0;
+
+    pub fn new(facing: Direction, half: Half, open: bool) -> Self {
+        Self { facing, half, open }
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    pub fn half(&self) -> Half {
+        self.half
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn other_half_direction(&self) -> Direction {
+        match self.half {
+            Half::Bottom => Direction::Up,
+            Half::Top => Direction::Down,
+        }
+    }
+
+    fn is_other_half(&self, block: BlockId) -> bool {
+        block
+            .typed::<Self>()
+            .is_some_and(|other| other.half == self.half.other() && other.facing == self.facing)
+    }
+}
+
+impl BlockHandler for DoorState {
+    /// Faces the player and needs room for its top half, otherwise nothing is placed.
+    fn get_placement_state(
+        &self,
+        world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        let above = context.pos.offset(Direction::Up);
+
+        if
+            !world.height().contains_y(above.y()) ||
+            !world.get_block(above.clone())?.resolve()?.is_replaceable(above)
+        {
+            return world.get_block(context.pos.clone());
+        }
+
+        BlockId::of(&Self { facing: context.horizontal_facing(), half: Half::Bottom, open: false })
+    }
+
+    fn on_placed(&self, world: &mut dyn WorldAccess, pos: BlockPos, _previous: BlockId) -> anyhow::Result<()> {
+        if self.half == Half::Bottom {
+            world.set_block(pos.offset(Direction::Up), BlockId::of(&Self { half: Half::Top, ..self.clone() })?)?;
+        }
+
+        Ok(())
+    }
+
+    fn on_neighbor_changed(
+        &self,
+        world: &mut dyn WorldAccess,
+        pos: BlockPos,
+        direction: Direction
+    ) -> anyhow::Result<()> {
+        if direction != self.other_half_direction() {
+            return Ok(());
+        }
+
+        if !self.is_other_half(world.get_block(pos.offset(direction))?) {
+            world.set_block(pos, BlockId::default())?;
+        }
+
+        Ok(())
+    }
+
+    fn on_use(&self, world: &mut dyn WorldAccess, pos: BlockPos, _context: &UseContext) -> anyhow::Result<bool> {
+        let other = pos.offset(self.other_half_direction());
+        let other_block = world.get_block(other.clone())?;
+
+        world.set_block(pos, BlockId::of(&Self { open: !self.open, ..self.clone() })?)?;
+
+        if self.is_other_half(other_block) {
+            world.set_block(other, BlockId::of(&Self { half: self.half.other(), open: !self.open, ..self.clone() })?)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Closed, the door stands on the side the player placed it from. Opening swings it to the side on its right.
+    fn get_collision_shape(&self) -> Option<BlockShape> {
+        let side = if self.open { self.facing.clockwise() } else { self.facing.opposite() };
+
+        Some(BlockShape::panel(side, Self::THICKNESS))
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{ Write, BufWriter };
+
+use metrohash::MetroHashMap;
+
+use crate::cbs::{ Packetable, DynamicSizePacketable, PacketBuf, WriteExt };
+
+use super::{ registry::BlockRegistry, BlockId };
+
+/// Every block state by name, e.g. `grass[snowy=true]`, together with the id it had when the palette was made.
+/// Stored with saved chunks and sent to clients, so block data can be translated if the other side numbers its blocks differently.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockPalette {
+    states: Vec<(String, BlockId)>,
+}
+
+impl BlockPalette {
+    pub fn of(registry: &BlockRegistry) -> Self {
+        let states = registry
+            .types()
+            .flat_map(|block_type| block_type.states().map(|id| (block_type.state_name(id), id)))
+            .collect();
+
+        Self { states }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, BlockId)> {
+        self.states.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+impl Packetable for BlockPalette {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u16(self.states.len() as u16)?;
+
+        for (name, id) in self.states {
+            buffer.write_string(&name)?;
+            id.write_to_buffer(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        let mut states = Vec::new();
+
+        for _ in 0..reader.next_u16()? {
+            states.push((reader.next_string()?, BlockId::read_from_buf(reader)?));
+        }
+
+        Ok(Self { states })
+    }
+}
+
+impl DynamicSizePacketable for BlockPalette {
+    fn size_in_bytes(&self) -> usize {
+        2 + self.states
+            .iter()
+            .map(|(name, _)| 2 + name.len() + 2)
+            .sum::<usize>()
+    }
+}
+
+/// Translates block ids from a foreign palette to the ids of a registry.
+pub struct BlockRemapper {
+    ids: MetroHashMap<BlockId, BlockId>,
+    fallback: BlockId,
+    missing: Vec<String>,
+}
+
+impl BlockRemapper {
+    /// States whose block doesn't exist in `registry` are replaced with its fallback block, and listed in `missing`.
+    pub fn new(palette: &BlockPalette, registry: &BlockRegistry) -> Self {
+        let mut ids = MetroHashMap::default();
+        let mut missing = Vec::new();
+
+        for (name, id) in palette.iter() {
+            match registry.parse_state(name) {
+                Some(new_id) => {
+                    ids.insert(*id, new_id);
+                }
+                None => missing.push(name.clone()),
+            }
+        }
+
+        Self { ids, fallback: registry.fallback(), missing }
+    }
+
+    /// The names of all states that are replaced with the fallback block.
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+
+    pub fn fallback(&self) -> BlockId {
+        self.fallback
+    }
+
+    /// Ids that aren't in the palette at all can't be identified either, so they become the fallback block too.
+    pub fn remap(&self, id: BlockId) -> BlockId {
+        self.ids.get(&id).copied().unwrap_or(self.fallback)
+    }
+}
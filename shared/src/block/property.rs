@@ -0,0 +1,171 @@
+use std::{ marker::PhantomData, fmt::Debug };
+
+use crate::error::block::BlockRegistryError;
+
+use super::registry::PropertyDefinition;
+
+/// A typed handle to one property of a block state, e.g. `Property::<bool>::new("snowy")`.
+/// Integer properties additionally carry the range of values they can take.
+#[derive(Debug, Clone, Copy)]
+pub struct Property<T: PropertyValue> {
+    name: &'static str,
+    min: i32,
+    max: i32,
+    _value: PhantomData<T>,
+}
+
+impl<T: PropertyValue> Property<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self::ranged(name, 0, 0)
+    }
+
+    pub const fn ranged(name: &'static str, min: i32, max: i32) -> Self {
+        Self { name, min, max, _value: PhantomData }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn count(&self) -> usize {
+        T::count(self)
+    }
+
+    pub fn definition(&self) -> PropertyDefinition {
+        T::definition(self)
+    }
+}
+
+pub trait PropertyValue: Copy + PartialEq + Debug + Sized + 'static {
+    fn count(property: &Property<Self>) -> usize;
+
+    fn to_index(self, property: &Property<Self>) -> usize;
+
+    fn from_index(index: usize, property: &Property<Self>) -> Option<Self>;
+
+    fn definition(property: &Property<Self>) -> PropertyDefinition;
+}
+
+impl PropertyValue for bool {
+    fn count(_property: &Property<Self>) -> usize {
+        2
+    }
+
+    fn to_index(self, _property: &Property<Self>) -> usize {
+        self as usize
+    }
+
+    fn from_index(index: usize, _property: &Property<Self>) -> Option<Self> {
+        match index {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn definition(property: &Property<Self>) -> PropertyDefinition {
+        PropertyDefinition::Bool { name: property.name.to_string() }
+    }
+}
+
+impl PropertyValue for u8 {
+    fn count(property: &Property<Self>) -> usize {
+        (property.max - property.min + 1) as usize
+    }
+
+    fn to_index(self, property: &Property<Self>) -> usize {
+        ((self as i32) - property.min) as usize
+    }
+
+    fn from_index(index: usize, property: &Property<Self>) -> Option<Self> {
+        if index < Self::count(property) {
+            Some(((index as i32) + property.min) as u8)
+        } else {
+            None
+        }
+    }
+
+    fn definition(property: &Property<Self>) -> PropertyDefinition {
+        PropertyDefinition::Int {
+            name: property.name.to_string(),
+            min: property.min,
+            max: property.max,
+        }
+    }
+}
+
+/// A property with a fixed set of named values, like `Direction` or `Axis`.
+pub trait EnumProperty: Copy + PartialEq + Debug + Sized + 'static {
+    const VALUES: &'static [Self];
+
+    fn name(&self) -> &'static str;
+}
+
+impl<T: EnumProperty> PropertyValue for T {
+    fn count(_property: &Property<Self>) -> usize {
+        T::VALUES.len()
+    }
+
+    fn to_index(self, _property: &Property<Self>) -> usize {
+        T::VALUES.iter()
+            .position(|value| *value == self)
+            .expect("Every value is listed in VALUES.")
+    }
+
+    fn from_index(index: usize, _property: &Property<Self>) -> Option<Self> {
+        T::VALUES.get(index).copied()
+    }
+
+    fn definition(property: &Property<Self>) -> PropertyDefinition {
+        PropertyDefinition::Enum {
+            name: property.name.to_string(),
+            values: T::VALUES.iter()
+                .map(|value| value.name().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Combines the value indices of all properties into a state id. Each entry is `(value index, value count)`,
+/// the first property being the least significant digit.
+pub fn encode_state(digits: &[(usize, usize)]) -> u8 {
+    let mut id = 0;
+    let mut stride = 1;
+
+    for (index, count) in digits {
+        id += index * stride;
+        stride *= count;
+    }
+
+    id as u8
+}
+
+/// Takes a state id apart again, one property at a time and in the same order `encode_state` put them together.
+pub struct StateDecoder {
+    block: &'static str,
+    id: u8,
+    remaining: usize,
+}
+
+impl StateDecoder {
+    pub fn new(block: &'static str, id: u8) -> Self {
+        Self { block, id, remaining: id as usize }
+    }
+
+    pub fn next<T: PropertyValue>(&mut self, property: &Property<T>) -> anyhow::Result<T> {
+        let count = property.count();
+        let value = T::from_index(self.remaining % count, property).ok_or_else(|| self.error())?;
+        self.remaining /= count;
+
+        Ok(value)
+    }
+
+    /// Fails if the id had digits left over, i.e. was larger than the number of states.
+    pub fn finish(self) -> anyhow::Result<()> {
+        if self.remaining == 0 { Ok(()) } else { Err(self.error().into()) }
+    }
+
+    fn error(&self) -> BlockRegistryError {
+        BlockRegistryError::InvalidStateId(self.block.to_string(), self.id)
+    }
+}
@@ -0,0 +1,558 @@
+use std::collections::BTreeMap;
+
+use anyhow::ensure;
+use log::warn;
+use metrohash::MetroHashMap;
+use once_cell::sync::OnceCell;
+use serde_derive::{ Deserialize, Serialize };
+
+use crate::error::block::{ BlockRegistryError, InvalidBlockIdError };
+
+use super::{
+    state::{ BlockHandler, State },
+    palette::BlockPalette,
+    shape::BlockShape,
+    property::{ Property, PropertyValue },
+    Block,
+    BlockId,
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PropertyDefinition {
+    Bool {
+        name: String,
+    },
+    Int {
+        name: String,
+        min: i32,
+        max: i32,
+    },
+    Enum {
+        name: String,
+        values: Vec<String>,
+    },
+}
+
+impl PropertyDefinition {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Bool { name } | Self::Int { name, .. } | Self::Enum { name, .. } => name,
+        }
+    }
+
+    /// Every value the property can take, in the order they're numbered in.
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Self::Bool { .. } => vec!["false".to_string(), "true".to_string()],
+            Self::Int { min, max, .. } => (*min..=*max).map(|i| i.to_string()).collect(),
+            Self::Enum { values, .. } => values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionShape {
+    #[default]
+    Full,
+    None,
+    Slab,
+}
+
+impl CollisionShape {
+    pub fn shape(&self) -> BlockShape {
+        match self {
+            Self::Full => BlockShape::full(),
+            Self::None => BlockShape::empty(),
+            Self::Slab => BlockShape::slab(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<PropertyDefinition>,
+    #[serde(default)]
+    pub default_state: BTreeMap<String, String>,
+    #[serde(default)]
+    pub hardness: f32,
+    #[serde(default)]
+    pub opacity: u8,
+    #[serde(default)]
+    pub emission: u8,
+    #[serde(default)]
+    pub collision: CollisionShape,
+    /// Falls back to the collision shape.
+    pub outline: Option<CollisionShape>,
+    /// Falls back to the hardness.
+    pub blast_resistance: Option<f32>,
+    #[serde(default = "BlockDefinition::default_friction")]
+    pub friction: f32,
+}
+
+impl BlockDefinition {
+    fn default_friction() -> f32 {
+        0.6
+    }
+}
+
+#[derive(Deserialize)]
+struct DefinitionsFile {
+    fallback: Option<String>,
+    #[serde(rename = "block")]
+    blocks: Vec<BlockDefinition>,
+}
+
+/// The block ids a world was created with, by block name. Saved with the world so ids never change once they're handed out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockIdMapping {
+    ids: BTreeMap<String, u16>,
+}
+
+impl BlockIdMapping {
+    pub const FILE_NAME: &'static str = "block_ids.toml";
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        Ok(Self { ids: toml::from_str(text)? })
+    }
+
+    pub fn serialize(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(&self.ids)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<u16> {
+        self.ids.get(name).copied()
+    }
+
+    fn assign(&mut self, name: &str) -> u16 {
+        if let Some(id) = self.get(name) {
+            return id;
+        }
+
+        let id = if name == Block::AIR {
+            0
+        } else {
+            self.ids
+                .values()
+                .max()
+                .map_or(1, |max| max + 1)
+        };
+
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+}
+
+pub type HandlerFactory = fn(u8) -> anyhow::Result<Box<dyn BlockHandler + Send + Sync>>;
+
+fn create_handler<S: State + BlockHandler + Send + Sync + 'static>(
+    state: u8
+) -> anyhow::Result<Box<dyn BlockHandler + Send + Sync>> {
+    Ok(Box::new(S::from_id(state)?))
+}
+
+/// Used for blocks that only exist in the definitions file and have no behaviour of their own.
+struct DefaultHandler;
+
+impl BlockHandler for DefaultHandler {}
+
+fn create_default_handler(_state: u8) -> anyhow::Result<Box<dyn BlockHandler + Send + Sync>> {
+    Ok(Box::new(DefaultHandler))
+}
+
+struct TypedBlock {
+    factory: HandlerFactory,
+    properties: Vec<PropertyDefinition>,
+    default_state: u8,
+}
+
+/// The code side of the registry: which `State` type handles which block name.
+#[derive(Default)]
+pub struct BlockHandlers {
+    typed: MetroHashMap<&'static str, TypedBlock>,
+}
+
+impl BlockHandlers {
+    pub fn register<S: State + BlockHandler + Send + Sync + 'static>(&mut self) {
+        self.typed.insert(S::NAME, TypedBlock {
+            factory: create_handler::<S>,
+            properties: S::properties(),
+            default_state: S::default().id(),
+        });
+    }
+
+    /// Fills in whatever the definition leaves out from the typed state, and makes sure the two agree on the rest.
+    fn apply(&self, definition: &mut BlockDefinition) -> anyhow::Result<(HandlerFactory, Option<u8>)> {
+        let Some(typed) = self.typed.get(definition.name.as_str()) else {
+            return Ok((create_default_handler, None));
+        };
+
+        if definition.properties.is_empty() {
+            definition.properties = typed.properties.clone();
+        }
+
+        ensure!(
+            definition.properties == typed.properties,
+            BlockRegistryError::PropertyMismatch(definition.name.clone())
+        );
+
+        let default_state = if definition.default_state.is_empty() { Some(typed.default_state) } else { None };
+
+        Ok((typed.factory, default_state))
+    }
+}
+
+pub struct BlockType {
+    id: u16,
+    definition: BlockDefinition,
+    state_count: usize,
+    default_state: u8,
+    factory: HandlerFactory,
+}
+
+impl BlockType {
+    fn new(
+        id: u16,
+        mut definition: BlockDefinition,
+        handlers: &BlockHandlers
+    ) -> anyhow::Result<Self> {
+        let (factory, typed_default) = handlers.apply(&mut definition)?;
+        let name = definition.name.clone();
+        ensure!(id <= (u8::MAX as u16), BlockRegistryError::TooManyBlocks(name, id));
+
+        let state_count = definition.properties
+            .iter()
+            .map(|property| property.values().len())
+            .product();
+        ensure!(state_count <= 256, BlockRegistryError::TooManyStates(name, state_count));
+
+        for property in definition.default_state.keys() {
+            ensure!(
+                definition.properties.iter().any(|p| p.name() == property),
+                BlockRegistryError::UnknownProperty(name, property.clone())
+            );
+        }
+
+        //The state id is a mixed radix number, with the first property as the least significant digit
+        let mut default_state = 0;
+        let mut stride = 1;
+
+        for property in &definition.properties {
+            let values = property.values();
+
+            let index = match definition.default_state.get(property.name()) {
+                Some(value) =>
+                    values
+                        .iter()
+                        .position(|v| v == value)
+                        .ok_or_else(||
+                            BlockRegistryError::InvalidPropertyValue(
+                                name.clone(),
+                                property.name().to_string(),
+                                value.clone()
+                            )
+                        )?,
+                None => 0,
+            };
+
+            default_state += index * stride;
+            stride *= values.len();
+        }
+
+        Ok(Self {
+            id,
+            definition,
+            state_count,
+            default_state: typed_default.unwrap_or(default_state as u8),
+            factory,
+        })
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    pub fn definition(&self) -> &BlockDefinition {
+        &self.definition
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.state_count
+    }
+
+    pub fn default_state(&self) -> BlockId {
+        BlockId((self.id << 8) | (self.default_state as u16))
+    }
+
+    /// Every state of this block, ordered by state id.
+    pub fn states(&self) -> impl Iterator<Item = BlockId> + '_ {
+        (0..self.state_count).map(|state| BlockId((self.id << 8) | (state as u16)))
+    }
+
+    /// The name of a state the way it is saved, e.g. `grass[snowy=true]`. Blocks without properties are just their name.
+    pub fn state_name(&self, id: BlockId) -> String {
+        if self.definition.properties.is_empty() {
+            return self.name().to_string();
+        }
+
+        let mut remaining = (id.0 & 255) as usize;
+
+        let properties: Vec<String> = self.definition.properties
+            .iter()
+            .map(|property| {
+                let values = property.values();
+                let value = &values[remaining % values.len()];
+                remaining /= values.len();
+
+                format!("{}={}", property.name(), value)
+            })
+            .collect();
+
+        format!("{}[{}]", self.name(), properties.join(","))
+    }
+
+    /// Where the value of a property sits in a state id: `(stride, value count)`.
+    /// Returns `None` if the block has no such property, or it has a different type than expected.
+    pub fn property_layout<T: PropertyValue>(&self, property: &Property<T>) -> Option<(usize, usize)> {
+        let mut stride = 1;
+
+        for definition in &self.definition.properties {
+            let count = definition.values().len();
+
+            if definition.name() == property.name() {
+                return if *definition == property.definition() { Some((stride, count)) } else { None };
+            }
+
+            stride *= count;
+        }
+
+        None
+    }
+}
+
+/// Every block state of an installed registry, laid out densely by block id and then state.
+/// Built once and never modified afterwards, so it can be read from any thread without locking.
+struct BlockTable {
+    offsets: Vec<usize>, //Where each block id's states start in `blocks`, plus one past the end
+    blocks: Vec<Block>,
+}
+
+impl BlockTable {
+    fn get(&self, id: BlockId) -> Option<&Block> {
+        let block = (id.0 >> 8) as usize;
+        let start = *self.offsets.get(block)?;
+        let end = *self.offsets.get(block + 1)?;
+        let index = start + ((id.0 & 255) as usize);
+
+        if index < end { self.blocks.get(index) } else { None }
+    }
+}
+
+static REGISTRY: OnceCell<BlockRegistry> = OnceCell::new();
+
+/// All blocks known to the game. Built once at startup from the definitions file and then installed globally.
+pub struct BlockRegistry {
+    types: Vec<Option<BlockType>>,
+    by_name: MetroHashMap<String, u16>,
+    fallback: BlockId,
+    table: OnceCell<BlockTable>,
+    palette: OnceCell<BlockPalette>,
+}
+
+impl BlockRegistry {
+    pub const BUILTIN_DEFINITIONS: &'static str = include_str!("../../assets/blocks.toml");
+
+    /// Registers every block in `definitions`. Blocks get their id from `mapping`, new blocks are added to it.
+    pub fn load(
+        definitions: &str,
+        mapping: &mut BlockIdMapping,
+        handlers: &BlockHandlers
+    ) -> anyhow::Result<Self> {
+        let file: DefinitionsFile = toml::from_str(definitions)?;
+
+        let mut registry = Self {
+            types: Vec::new(),
+            by_name: MetroHashMap::default(),
+            fallback: BlockId::default(),
+            table: OnceCell::new(),
+            palette: OnceCell::new(),
+        };
+
+        //Hand out air's id first, so a fresh mapping starts with it
+        if file.blocks.iter().any(|block| block.name == Block::AIR) {
+            mapping.assign(Block::AIR);
+        }
+
+        for definition in file.blocks {
+            let name = definition.name.clone();
+            ensure!(!registry.by_name.contains_key(&name), BlockRegistryError::DuplicateBlock(name));
+
+            let id = mapping.assign(&name);
+            ensure!(name != Block::AIR || id == 0, BlockRegistryError::AirIsNotZero(id));
+
+            let block_type = BlockType::new(id, definition, handlers)?;
+
+            if registry.types.len() <= (id as usize) {
+                registry.types.resize_with((id as usize) + 1, || None);
+            }
+
+            registry.types[id as usize] = Some(block_type);
+            registry.by_name.insert(name, id);
+        }
+
+        for name in mapping.ids.keys() {
+            if !registry.by_name.contains_key(name) {
+                warn!("Block {} is in the id mapping but no longer defined", name);
+            }
+        }
+
+        if let Some(fallback) = file.fallback {
+            registry.fallback = registry
+                .parse_state(&fallback)
+                .ok_or(BlockRegistryError::UnknownBlock(fallback))?;
+        }
+
+        Ok(registry)
+    }
+
+    /// The definitions shipped with the game, numbered in file order.
+    pub fn builtin() -> Self {
+        Self::load(Self::BUILTIN_DEFINITIONS, &mut BlockIdMapping::default(), &super::default_handlers()).expect(
+            "The builtin block definitions are valid."
+        )
+    }
+
+    /// The installed registry. One has to be installed at startup, before any block is looked up; installing it
+    /// on first use instead would lock in the builtin ids before a world gets to install its own.
+    pub fn global() -> anyhow::Result<&'static Self> {
+        Ok(REGISTRY.get().ok_or(BlockRegistryError::NotInstalled)?)
+    }
+
+    /// Makes this the registry every `BlockId` resolves against. Can only happen once, though installing a registry with the exact same ids again is allowed.
+    /// Every block state is created right away, so a broken handler shows up here rather than on the first lookup.
+    pub fn install(self) -> anyhow::Result<&'static Self> {
+        if let Err(registry) = REGISTRY.set(self) {
+            ensure!(Self::global()?.same_ids(&registry), BlockRegistryError::AlreadyInstalled);
+        }
+
+        let registry = Self::global()?;
+        registry.table()?;
+
+        Ok(registry)
+    }
+
+    fn table(&'static self) -> anyhow::Result<&'static BlockTable> {
+        self.table.get_or_try_init(|| {
+            let mut offsets = Vec::with_capacity(self.types.len() + 1);
+            let mut blocks = Vec::new();
+
+            for block_type in &self.types {
+                offsets.push(blocks.len());
+
+                if let Some(block_type) = block_type {
+                    for id in block_type.states() {
+                        let handler = (block_type.factory)((id.0 & 255) as u8)?;
+                        blocks.push(Block::new(id, block_type, handler));
+                    }
+                }
+            }
+
+            offsets.push(blocks.len());
+
+            Ok(BlockTable { offsets, blocks })
+        })
+    }
+
+    /// What saved blocks that no longer exist get replaced with. Set by `fallback` in the definitions file, air if it isn't set.
+    pub fn fallback(&self) -> BlockId {
+        self.fallback
+    }
+
+    /// The name of every state of every block, which is what saved worlds and clients use to agree on ids.
+    pub fn palette(&self) -> &BlockPalette {
+        self.palette.get_or_init(|| BlockPalette::of(self))
+    }
+
+    pub fn state_name(&self, id: BlockId) -> Option<String> {
+        self.get(id.0 >> 8).map(|block_type| block_type.state_name(id))
+    }
+
+    /// Finds a state by the name `BlockType::state_name` gave it. Properties that no longer exist or have a value that's
+    /// no longer valid are left at their default, so a state survives properties being added or removed.
+    /// Returns `None` if the block itself doesn't exist.
+    pub fn parse_state(&self, name: &str) -> Option<BlockId> {
+        let (block, properties) = match name.split_once('[') {
+            Some((block, properties)) => (block, properties.strip_suffix(']').unwrap_or(properties)),
+            None => (name, ""),
+        };
+
+        let block_type = self.get_by_name(block)?;
+        let mut state = (block_type.default_state().0 & 255) as usize;
+        let mut stride = 1;
+
+        for property in &block_type.definition().properties {
+            let values = property.values();
+
+            let value = properties
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| *name == property.name())
+                .and_then(|(_, value)| values.iter().position(|v| v == value));
+
+            if let Some(value) = value {
+                let old = (state / stride) % values.len();
+                state = state - old * stride + value * stride;
+            }
+
+            stride *= values.len();
+        }
+
+        Some(BlockId((block_type.id() << 8) | (state as u16)))
+    }
+
+    fn same_ids(&self, other: &Self) -> bool {
+        self.by_name == other.by_name &&
+            self.types.len() == other.types.len() &&
+            self.types
+                .iter()
+                .zip(other.types.iter())
+                .all(|(a, b)| a.as_ref().map(BlockType::state_count) == b.as_ref().map(BlockType::state_count))
+    }
+
+    pub fn get(&self, id: u16) -> Option<&BlockType> {
+        self.types.get(id as usize).and_then(Option::as_ref)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&BlockType> {
+        self.by_name.get(name).and_then(|id| self.get(*id))
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = &BlockType> {
+        self.types.iter().flatten()
+    }
+
+    pub fn id_of<S: State>(&self, state: &S) -> anyhow::Result<BlockId> {
+        let block_type = self
+            .get_by_name(S::NAME)
+            .ok_or_else(|| BlockRegistryError::UnknownBlock(S::NAME.to_string()))?;
+
+        ensure!(
+            (state.id() as usize) < block_type.state_count(),
+            BlockRegistryError::InvalidStateId(S::NAME.to_string(), state.id())
+        );
+
+        Ok(BlockId((block_type.id() << 8) | (state.id() as u16)))
+    }
+
+    /// Looks up a block state. This is a plain index into a table, so it's cheap enough to call for every block access.
+    pub fn block(&'static self, id: BlockId) -> anyhow::Result<&'static Block> {
+        Ok(self.table()?.get(id).ok_or(InvalidBlockIdError(id.0))?)
+    }
+}
@@ -0,0 +1,87 @@
+use glam::Vec3;
+
+use crate::util::{ aabb::Aabb, block_pos::BlockPos, direction::Direction };
+
+/// The geometry of a block state, as boxes relative to the block's corner, each within the unit cube.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockShape {
+    boxes: Vec<Aabb>,
+}
+
+impl BlockShape {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn full() -> Self {
+        Self { boxes: vec![Aabb::UNIT] }
+    }
+
+    pub fn from_boxes(boxes: Vec<Aabb>) -> Self {
+        Self { boxes }
+    }
+
+    /// The lower half of a block, or the upper half if `top` is set.
+    pub fn slab(top: bool) -> Self {
+        let min_y = if top { 0.5 } else { 0.0 };
+
+        Self { boxes: vec![Aabb::new(Vec3::new(0.0, min_y, 0.0), Vec3::new(1.0, min_y + 0.5, 1.0))] }
+    }
+
+    /// A slab with a step on it, taking up the half of the other half that `facing` points to.
+    /// `top` stairs are upside down, the slab being at the top and the step below it.
+    pub fn stair(facing: Direction, top: bool) -> Self {
+        let (min_y, max_y) = if top { (0.0, 0.5) } else { (0.5, 1.0) };
+
+        let (min_x, max_x, min_z, max_z) = match facing {
+            Direction::North => (0.0, 1.0, 0.0, 0.5),
+            Direction::South => (0.0, 1.0, 0.5, 1.0),
+            Direction::East => (0.5, 1.0, 0.0, 1.0),
+            Direction::West => (0.0, 0.5, 0.0, 1.0),
+            //Stairs are only ever placed facing sideways, so there's no step to speak of otherwise
+            Direction::Up | Direction::Down => {
+                return Self::slab(top);
+            }
+        };
+
+        let mut shape = Self::slab(top);
+        shape.boxes.push(Aabb::new(Vec3::new(min_x, min_y, min_z), Vec3::new(max_x, max_y, max_z)));
+
+        shape
+    }
+
+    /// A board `thickness` thick against the side of the block that `side` points to.
+    pub fn panel(side: Direction, thickness: f32) -> Self {
+        let (mut min, mut max) = (Vec3::ZERO, Vec3::ONE);
+
+        match side {
+            Direction::North => max.z = thickness,
+            Direction::South => min.z = 1.0 - thickness,
+            Direction::East => min.x = 1.0 - thickness,
+            Direction::West => max.x = thickness,
+            Direction::Up => min.y = 1.0 - thickness,
+            Direction::Down => max.y = thickness,
+        }
+
+        Self { boxes: vec![Aabb::new(min, max)] }
+    }
+
+    pub fn boxes(&self) -> &[Aabb] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.boxes == [Aabb::UNIT]
+    }
+
+    /// The boxes in world coordinates, for the block at `pos`.
+    pub fn at<'a>(&'a self, pos: &BlockPos) -> impl Iterator<Item = Aabb> + 'a {
+        let corner = Vec3::from(pos.clone());
+
+        self.boxes.iter().map(move |aabb| aabb.offset(corner))
+    }
+}
@@ -1,13 +1,15 @@
-use crate::util::block_pos::BlockPos;
+use proc_macros::State;
 
-use super::state::{ BlockHandler, State };
+use crate::{
+    util::{ block_pos::BlockPos, direction::Direction },
+    dimension::access::WorldAccess,
+};
 
-#[derive(Debug, Clone)]
-pub struct AirState;
+use super::{ state::BlockHandler, property::Property, context::PlacementContext, BlockId };
 
-impl State for AirState {
-    const DEFAULT: Self = Self;
-}
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "air")]
+pub struct AirState;
 
 impl BlockHandler for AirState {
     fn is_replaceable(&self, _pos: BlockPos) -> bool {
@@ -15,7 +17,8 @@ impl BlockHandler for AirState {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "grass")]
 pub struct GrassState {
     snowy: bool,
 }
@@ -23,18 +26,100 @@ pub struct GrassState {
 impl GrassState {
     pub const NORMAL: Self = Self { snowy: false };
     pub const SNOWY: Self = Self { snowy: true };
+
+    pub const SNOWY_PROPERTY: Property<bool> = Property::new("snowy");
+
+    pub fn is_snowy(&self) -> bool {
+        self.snowy
+    }
+}
+
+impl GrassState {
+    /// Grass is snowy whenever there's snow on top of it.
+    fn state_for(world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<BlockId> {
+        let snowy = world.get_block(pos.offset(Direction::Up))? == BlockId::of(&SnowState)?;
+
+        BlockId::of(&Self { snowy })
+    }
+
+    /// Grass can't grow underneath opaque blocks. Above the top of the world counts as uncovered.
+    fn is_covered(world: &mut dyn WorldAccess, pos: &BlockPos) -> anyhow::Result<bool> {
+        let above = pos.offset(Direction::Up);
+
+        if !world.height().contains_y(above.y()) {
+            return Ok(false);
+        }
+
+        Ok(world.get_block(above)?.resolve()?.opacity() >= 15)
+    }
 }
 
-impl BlockHandler for GrassState {}
+impl BlockHandler for GrassState {
+    fn get_placement_state(
+        &self,
+        world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        Self::state_for(world, &context.pos)
+    }
+
+    fn on_neighbor_changed(
+        &self,
+        world: &mut dyn WorldAccess,
+        pos: BlockPos,
+        direction: Direction
+    ) -> anyhow::Result<()> {
+        if direction != Direction::Up {
+            return Ok(());
+        }
+
+        let state = Self::state_for(world, &pos)?;
+
+        if state != BlockId::of(self)? {
+            world.set_block(pos, state)?;
+        }
 
-impl State for GrassState {
-    fn id(&self) -> u8 {
-        self.snowy as u8
+        Ok(())
     }
 
-    fn from_id(id: u8) -> anyhow::Result<Self> {
-        Ok(Self { snowy: id == 1 })
+    /// Covered grass dies off, uncovered grass spreads to dirt nearby.
+    fn on_random_tick(&self, world: &mut dyn WorldAccess, pos: BlockPos) -> anyhow::Result<()> {
+        if Self::is_covered(world, &pos)? {
+            return world.set_block(pos, BlockId::of(&DirtState)?);
+        }
+
+        let target = BlockPos::new(
+            pos.x() + (world.random(3) as i32) - 1,
+            pos.y() + (world.random(5) as i32) - 3,
+            pos.z() + (world.random(3) as i32) - 1
+        );
+
+        if
+            world.height().contains_y(target.y()) &&
+            world.is_loaded(&target) &&
+            world.get_block(target.clone())? == BlockId::of(&DirtState)? &&
+            !Self::is_covered(world, &target)?
+        {
+            let state = Self::state_for(world, &target)?;
+            world.set_block(target, state)?;
+        }
+
+        Ok(())
     }
+}
 
-    const DEFAULT: Self = Self::NORMAL;
-}
\ No newline at end of file
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "dirt")]
+pub struct DirtState;
+
+impl BlockHandler for DirtState {}
+
+#[derive(Debug, Clone, Default, State)]
+#[block(name = "snow")]
+pub struct SnowState;
+
+impl BlockHandler for SnowState {
+    fn is_replaceable(&self, _pos: BlockPos) -> bool {
+        true
+    }
+}
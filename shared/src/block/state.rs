@@ -1,36 +1,117 @@
 use std::{ fmt::Debug };
 
+use crate::{
+    dimension::access::WorldAccess,
+    util::{ block_pos::BlockPos, direction::Direction },
+};
+
+use super::{
+    context::{ PlacementContext, UseContext },
+    entity::BlockEntity,
+    registry::PropertyDefinition,
+    shape::BlockShape,
+    BlockId,
+};
+
+/// The behaviour of a block state. The hooks are run by the server world, which passes itself in as `world`.
+pub trait BlockHandler {
+    fn is_replaceable(&self, _pos: BlockPos) -> bool {
+        false
+    }
 
+    fn map_color(&self) -> i32 {
+        1
+    }
 
+    /// Overrides the collision shape from the definitions file, for blocks whose shape depends on their state.
+    fn get_collision_shape(&self) -> Option<BlockShape> {
+        None
+    }
 
+    /// Overrides the outline shape from the definitions file, which is what gets hit when a player aims at the block.
+    fn get_outline_shape(&self) -> Option<BlockShape> {
+        None
+    }
 
+    /// The block entity that's set together with this block. A block entity survives changes between states of the
+    /// same block, so this is only called when a different block is replaced.
+    fn create_block_entity(&self) -> Option<Box<dyn BlockEntity>> {
+        None
+    }
 
+    /// Picks the state to place, given the default state in `context.block`.
+    fn get_placement_state(
+        &self,
+        _world: &mut dyn WorldAccess,
+        context: &PlacementContext
+    ) -> anyhow::Result<BlockId> {
+        Ok(context.block)
+    }
 
+    /// Whether `context.block` can be placed into this block's space to combine with it, like a slab onto the open
+    /// side of another one. The combined state comes from the placed block's `get_placement_state`.
+    fn can_merge(&self, _context: &PlacementContext) -> bool {
+        false
+    }
 
+    /// Runs after this block replaced `previous`.
+    fn on_placed(
+        &self,
+        _world: &mut dyn WorldAccess,
+        _pos: BlockPos,
+        _previous: BlockId
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 
-use crate::util::block_pos::BlockPos;
-
-
-
+    /// Runs after this block was replaced by another one.
+    fn on_broken(&self, _world: &mut dyn WorldAccess, _pos: BlockPos) -> anyhow::Result<()> {
+        Ok(())
+    }
 
+    /// Runs after the block next to this one, in `direction`, changed.
+    fn on_neighbor_changed(
+        &self,
+        _world: &mut dyn WorldAccess,
+        _pos: BlockPos,
+        _direction: Direction
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 
+    /// Returns whether the interaction did something; if not, the player gets to place a block instead.
+    fn on_use(
+        &self,
+        _world: &mut dyn WorldAccess,
+        _pos: BlockPos,
+        _context: &UseContext
+    ) -> anyhow::Result<bool> {
+        Ok(false)
+    }
 
-pub trait BlockHandler {
-    fn is_replaceable(&self, _pos: BlockPos) -> bool {
-        false
+    fn on_random_tick(&self, _world: &mut dyn WorldAccess, _pos: BlockPos) -> anyhow::Result<()> {
+        Ok(())
     }
 
-    fn map_color(&self) -> i32 {
-        1
+    fn on_scheduled_tick(&self, _world: &mut dyn WorldAccess, _pos: BlockPos) -> anyhow::Result<()> {
+        Ok(())
     }
 }
 
-pub trait State: Debug + Clone {
+/// The typed form of a block's state. Usually implemented through `#[derive(State)]`.
+pub trait State: Debug + Clone + Default {
+    /// The name of the block in the definitions file.
+    const NAME: &'static str;
+
+    /// The properties making up the state, in the order their values are combined into the state id.
+    fn properties() -> Vec<PropertyDefinition> {
+        Vec::new()
+    }
+
     fn id(&self) -> u8 {
         0
     }
     fn from_id(_id: u8) -> anyhow::Result<Self> where Self: Sized {
-        Ok(Self::DEFAULT)
+        Ok(Self::default())
     }
-    const DEFAULT: Self;
-}
\ No newline at end of file
+}
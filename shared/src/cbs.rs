@@ -26,11 +26,8 @@ pub trait FixedSizePacketable: Packetable {
 }
 
 pub trait DynamicSizePacketable: Packetable {
-    fn size_in_bytes(units: u8) -> usize;
-    fn size_in_units(&self) -> u8;
-    fn current_size_in_bytes(&self) -> usize {
-        Self::size_in_bytes(self.size_in_units())
-    }
+    /// The number of bytes `write_to_buffer` is going to produce; sent ahead of the data so the receiver knows how much to read.
+    fn size_in_bytes(&self) -> usize;
 }
 
 pub struct PacketBuf {
@@ -54,14 +51,18 @@ impl PacketBuf {
     }
 
     pub fn next_bytes<const BYTES: usize>(& mut self) -> anyhow::Result<& [u8]> {
+        self.next_n_bytes(BYTES)
+    }
+
+    pub fn next_n_bytes(&mut self, bytes: usize) -> anyhow::Result<&[u8]> {
         ensure!(
-            self.index + BYTES <= self.total_bytes,
-            CbsBufferError::NotEnoughData(BYTES, self.available_bytes())
+            self.index + bytes <= self.total_bytes,
+            CbsBufferError::NotEnoughData(bytes, self.available_bytes())
         );
-        self.consume_bytes(BYTES);
+        let start = self.index;
+        self.consume_bytes(bytes);
 
-        let result = &self.data[self.index..self.index + BYTES];
-        Ok(result)
+        Ok(&self.data[start..start + bytes])
     }
 
     pub fn available_bytes(&self) -> usize {
@@ -69,61 +70,44 @@ impl PacketBuf {
     }
 
     pub fn next_byte(&mut self) -> anyhow::Result<u8> {
-        ensure!(
-            self.index < self.total_bytes,
-            CbsBufferError::NotEnoughData(1, self.available_bytes())
-        );
-
-        let result = self.data[self.index >> 3];
-
-        self.consume_bytes(1);
-
-        Ok(result)
+        Ok(self.next_bytes::<1>()?[0])
     }
 
     pub fn next_u16(&mut self) -> anyhow::Result<u16> {
-        ensure!(
-            self.index + 2 <= self.total_bytes,
-            CbsBufferError::NotEnoughData(2, self.available_bytes())
-        );
-
-        let result = unsafe { *(self.data.as_ptr() as *const u16) };
-
-        self.consume_bytes(2);
+        let mut temp = [0u8; 2];
+        temp.copy_from_slice(self.next_bytes::<2>()?);
 
-        Ok(result)
+        Ok(u16::from_le_bytes(temp))
     }
 
     pub fn next_u32(&mut self) -> anyhow::Result<u32> {
-        ensure!(
-            self.index + 4 <= self.total_bytes,
-            CbsBufferError::NotEnoughData(4, self.available_bytes())
-        );
-
-        let result = unsafe { *(self.data.as_ptr() as *const u32) };
-
-        self.consume_bytes(4);
+        let mut temp = [0u8; 4];
+        temp.copy_from_slice(self.next_bytes::<4>()?);
 
-        Ok(result)
+        Ok(u32::from_le_bytes(temp))
     }
 
     pub fn next_u64(&mut self) -> anyhow::Result<u64> {
-        ensure!(
-            self.index + 8 <= self.total_bytes,
-            CbsBufferError::NotEnoughData(8, self.available_bytes())
-        );
+        let mut temp = [0u8; 8];
+        temp.copy_from_slice(self.next_bytes::<8>()?);
 
-        let result = unsafe { *(self.data.as_ptr() as *const u64) };
+        Ok(u64::from_le_bytes(temp))
+    }
+
+    pub fn next_f32(&mut self) -> anyhow::Result<f32> {
+        Ok(f32::from_bits(self.next_u32()?))
+    }
 
-        self.consume_bytes(8);
+    pub fn next_string(&mut self) -> anyhow::Result<String> {
+        let len = self.next_u16()? as usize;
 
-        Ok(result)
+        Ok(String::from_utf8(self.next_n_bytes(len)?.to_vec())?)
     }
 
     pub fn next_n_bytes_as_u32<const BYTES: usize>(&mut self) -> anyhow::Result<u32> {
         let mut temp = [0u8; 4];
 
-        temp.copy_from_slice(self.next_bytes::<BYTES>()?);
+        temp[0..BYTES].copy_from_slice(self.next_bytes::<BYTES>()?);
 
         Ok(u32::from_le_bytes(temp))
     }
@@ -135,33 +119,31 @@ pub trait WriteExt: Write {
     }
 
     fn write_u16(&mut self, val: u16) -> anyhow::Result<usize> {
-        let mut buf = [0u8; 2];
-
-        unsafe {
-            *(buf.as_mut_ptr() as *mut u16) = val;
-        }
-
-        Ok(self.write(&buf)?)
+        self.write_all(&val.to_le_bytes())?;
+        Ok(2)
     }
 
     fn write_u32(&mut self, val: u32) -> anyhow::Result<usize> {
-        let mut buf = [0u8; 4];
+        self.write_all(&val.to_le_bytes())?;
+        Ok(4)
+    }
 
-        unsafe {
-            *(buf.as_mut_ptr() as *mut u32) = val;
-        }
+    fn write_u64(&mut self, val: u64) -> anyhow::Result<usize> {
+        self.write_all(&val.to_le_bytes())?;
+        Ok(8)
+    }
 
-        Ok(self.write(&buf)?)
+    fn write_f32(&mut self, val: f32) -> anyhow::Result<usize> {
+        self.write_u32(val.to_bits())
     }
 
-    fn write_u64(&mut self, val: u64) -> anyhow::Result<usize> {
-        let mut buf = [0u8; 8];
+    fn write_string(&mut self, val: &str) -> anyhow::Result<usize> {
+        ensure!(val.len() <= (u16::MAX as usize), CbsBufferError::StringTooLong(val.len()));
 
-        unsafe {
-            *(buf.as_mut_ptr() as *mut u64) = val;
-        }
+        self.write_u16(val.len() as u16)?;
+        self.write_all(val.as_bytes())?;
 
-        Ok(self.write(&buf)?)
+        Ok(2 + val.len())
     }
 
     fn first_n_bytes_u128<const BYTES: usize>(&mut self, int: u128) -> anyhow::Result<usize> {
@@ -0,0 +1,31 @@
+use crate::{ block::{ BlockId, entity::BlockEntity }, util::{ block_pos::BlockPos, aabb::Aabb } };
+
+use super::height::WorldHeight;
+
+/// The part of a world that block behaviour gets to see and change. Implemented by the server's dimensions.
+pub trait WorldAccess {
+    fn height(&self) -> WorldHeight;
+
+    /// Whether the chunk `pos` is in is loaded. Anything that spreads on its own should stop at unloaded chunks
+    /// rather than load them, or it would keep loading more and more of the world.
+    fn is_loaded(&self, pos: &BlockPos) -> bool;
+
+    /// Loads the chunk if necessary.
+    fn get_block(&mut self, pos: BlockPos) -> anyhow::Result<BlockId>;
+
+    /// Replaces a block, running the `BlockHandler` hooks of the old and the new block and notifying its neighbours.
+    fn set_block(&mut self, pos: BlockPos, block: BlockId) -> anyhow::Result<()>;
+
+    /// The block entity at `pos`, if the block there has one. Since the caller may change it, it's saved and sent to clients again.
+    fn block_entity_mut(&mut self, pos: &BlockPos) -> anyhow::Result<Option<&mut dyn BlockEntity>>;
+
+    /// Runs the `on_scheduled_tick` hook of the block at `pos` in `delay` ticks, as long as it hasn't been replaced by then.
+    /// Of the ticks due at the same time, the ones with a lower priority run first.
+    fn schedule_tick(&mut self, pos: BlockPos, delay: u64, priority: i8) -> anyhow::Result<()>;
+
+    /// The block collision boxes intersecting `area`, e.g. for entities to bump into. Loads the chunks if necessary.
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>>;
+
+    /// A random number from `0` up to, but not including, `bound`.
+    fn random(&mut self, bound: u32) -> u32;
+}
@@ -2,37 +2,84 @@ use std::io::Write;
 
 
 
+use anyhow::ensure;
 use metrohash::MetroHashMap;
 
 use crate::{
     util::block_pos::{  BlockPos },
-    block::{ BlockId }, cbs::{PacketBuf, DynamicSizePacketable, WriteExt},
+    block::{ BlockId, palette::BlockRemapper }, cbs::{PacketBuf, DynamicSizePacketable, FixedSizePacketable},
+    error::dimension::InvalidChunkDataError,
 };
 use crate::cbs::Packetable;
 
-use super::subchunk::SubChunk;
+use super::{ subchunk::SubChunk, height::WorldHeight };
 
 
 #[derive(Debug, Clone)]
 pub struct Chunk {
-    pub(crate) non_air_sub_chunks: MetroHashMap<u8, SubChunk>,
+    height: WorldHeight,
+    pub(crate) non_air_sub_chunks: MetroHashMap<i32, SubChunk>, //Keyed by section, i.e. y >> 4
 }
 
 impl Chunk {
-    pub fn empty() -> Chunk {
-        Chunk { non_air_sub_chunks: MetroHashMap::default() }
+    pub fn empty(height: WorldHeight) -> Chunk {
+        Chunk { height, non_air_sub_chunks: MetroHashMap::default() }
+    }
+
+    pub fn height(&self) -> WorldHeight {
+        self.height
     }
 
     pub fn get_block(&self, pos: BlockPos) -> anyhow::Result<BlockId> {
         let y = pos.y();
-        pos.validate()?;
+        pos.validate(&self.height)?;
 
-        Ok(match self.non_air_sub_chunks.get(&((y << 4) as u8)) {
+        Ok(match self.non_air_sub_chunks.get(&pos.get_section()) {
             Some(sc) =>
                 sc.get_block((pos.x() & 15) as i16, (y & 15) as i16, (pos.z() & 15) as i16),
             None => BlockId::default(),
         })
     }
+
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockId) -> anyhow::Result<()> {
+        let y = pos.y();
+        pos.validate(&self.height)?;
+
+        let (x, y, z) = ((pos.x() & 15) as i16, (y & 15) as i16, (pos.z() & 15) as i16);
+        let section = pos.get_section();
+
+        if let Some(sc) = self.non_air_sub_chunks.get_mut(&section) {
+            sc.set_block(x, y, z, block);
+
+            if sc.is_empty() {
+                self.non_air_sub_chunks.remove(&section);
+            }
+        } else if block.0 != BlockId::default().0 {
+            let mut sc = SubChunk::default();
+            sc.set_block(x, y, z, block);
+            self.non_air_sub_chunks.insert(section, sc);
+        }
+
+        Ok(())
+    }
+
+    /// The sections that contain anything but air, in no particular order.
+    pub fn sections(&self) -> impl Iterator<Item = i32> + '_ {
+        self.non_air_sub_chunks.keys().copied()
+    }
+
+    /// Translates every block from the palette the chunk was saved or sent with to the local one.
+    pub fn remap(&mut self, remapper: &BlockRemapper) {
+        for sc in self.non_air_sub_chunks.values_mut() {
+            sc.remap(remapper);
+        }
+
+        self.non_air_sub_chunks.retain(|_, sc| !sc.is_empty());
+    }
+
+    fn mask_len(sections: u8) -> usize {
+        (sections as usize).div_ceil(8)
+    }
 }
 
 impl Packetable for Chunk {
@@ -40,51 +87,69 @@ impl Packetable for Chunk {
         self,
         buffer: &mut std::io::BufWriter<T>
     ) -> anyhow::Result<()> {
-        let mut available_subchunks = 0u16; //16 bit flags; the nth bit is a bool whether or not that subchunk is included or not
+        //One bit per section of the world, starting at the bottom; the nth bit is a bool whether or not that subchunk is included or not
+        let mut available_subchunks = vec![0u8; Self::mask_len(self.height.sections())];
 
-        let mut subchunks: Vec<(u8, SubChunk)> = self.non_air_sub_chunks.into_iter().collect();
+        let mut subchunks: Vec<(i32, SubChunk)> = self.non_air_sub_chunks.into_iter().collect();
 
         subchunks.sort_by_key(|kvp| kvp.0);
 
-        for (y, _) in &subchunks {
-            let y = *y << 4; // divide by 16 to get the actual subchunk height
-            available_subchunks |= 1 << y; //Set the Yth bit to true
+        for (section, _) in &subchunks {
+            let index = self.height
+                .section_index(*section)
+                .ok_or(InvalidChunkDataError::SectionOutOfRange(*section, self.height))?;
+            available_subchunks[index >> 3] |= 1 << (index & 7);
         }
 
-        buffer.write_u16(available_subchunks)?;
+        self.height.write_to_buffer(buffer)?;
+        buffer.write_all(&available_subchunks)?;
 
         for (_, sc) in subchunks.into_iter() {
             sc.write_to_buffer(buffer)?;
         }
-        
+
 
         Ok(())
     }
 
     fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
-        let available_subchunks = reader.next_u16()?;
+        let height = WorldHeight::read_from_buf(reader)?;
+
+        let available_subchunks = reader
+            .next_n_bytes(Self::mask_len(height.sections()))?
+            .to_vec();
+
+        let subchunk_count: usize = available_subchunks
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum();
+        ensure!(
+            reader.available_bytes() >= subchunk_count * SubChunk::BYTES,
+            InvalidChunkDataError::InvalidDataSize(
+                reader.available_bytes() * 8,
+                subchunk_count * SubChunk::BYTES * 8
+            )
+        );
 
         let mut map = MetroHashMap::default();
 
-        for i in 0..15 {
-            if (available_subchunks & (1 << i)) != 0 {
+        for (i, section) in height.section_range().enumerate() {
+            if (available_subchunks[i >> 3] & (1 << (i & 7))) != 0 {
                 //Check each bit for being true
-                map.insert((i as u8) * 16, SubChunk::read_from_buf(reader)?);
+                map.insert(section, SubChunk::read_from_buf(reader)?);
             }
         }
 
-        Ok(Self { non_air_sub_chunks: map })
+        Ok(Self { height, non_air_sub_chunks: map })
     }
 
 
 }
 
 impl DynamicSizePacketable for Chunk {
-    fn size_in_bytes(units: u8) -> usize {
-        2 + (units as usize) * SubChunk::BYTES
+    fn size_in_bytes(&self) -> usize {
+        WorldHeight::SIZE_IN_BYTES +
+            Self::mask_len(self.height.sections()) +
+            self.non_air_sub_chunks.len() * SubChunk::BYTES
     }
-
-    fn size_in_units(&self) -> u8 {
-        self.non_air_sub_chunks.len() as u8
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,30 @@
+use crate::util::{ aabb::Aabb, block_pos::BlockPos };
+
+use super::storage::ChunkStorage;
+
+/// The collision boxes of all blocks that intersect `area`, in world coordinates. Blocks outside of the world's
+/// height have none.
+pub fn collision_boxes<S: ChunkStorage + ?Sized>(storage: &mut S, area: &Aabb) -> anyhow::Result<Vec<Aabb>> {
+    let min = area.min.floor().as_ivec3();
+    //Boxes only touching a block's side don't intersect it
+    let max = area.max.ceil().as_ivec3() - 1;
+    let mut boxes = Vec::new();
+
+    for x in min.x..=max.x {
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                let pos = BlockPos::new(x, y, z);
+                let chunk = storage.get_chunk(&pos.get_chunk())?;
+
+                if !chunk.height().contains_y(y) {
+                    continue;
+                }
+
+                let block = chunk.get_block(pos.clone())?.resolve()?;
+                boxes.extend(block.collision_shape().at(&pos).filter(|aabb| aabb.intersects(area)));
+            }
+        }
+    }
+
+    Ok(boxes)
+}
@@ -0,0 +1,113 @@
+use std::{ ops::Range, io::{ Write, BufWriter } };
+
+use anyhow::ensure;
+
+use crate::{
+    error::dimension::InvalidWorldHeightError,
+    util::block_pos::BlockPos,
+    cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt },
+};
+
+use super::subchunk::SubChunk;
+
+/// The vertical extent of a dimension, measured in whole sub-chunks ("sections").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldHeight {
+    min_y: i32,
+    sections: u8,
+}
+
+impl WorldHeight {
+    const SECTION_HEIGHT: i32 = SubChunk::DIMENSIONS as i32;
+
+    pub const DEFAULT: Self = Self { min_y: 0, sections: 16 };
+
+    pub fn new(min_y: i32, sections: u8) -> anyhow::Result<Self> {
+        ensure!(min_y % Self::SECTION_HEIGHT == 0, InvalidWorldHeightError::UnalignedMinY(min_y));
+        ensure!(sections > 0, InvalidWorldHeightError::NoSections);
+
+        let max_y = min_y + (sections as i32) * Self::SECTION_HEIGHT;
+
+        ensure!(
+            BlockPos::VALID_Y.start <= min_y && max_y <= BlockPos::VALID_Y.end,
+            InvalidWorldHeightError::OutOfBounds(min_y, max_y)
+        );
+
+        Ok(Self { min_y, sections })
+    }
+
+    pub fn from_sections(min_section: i32, sections: u8) -> anyhow::Result<Self> {
+        Self::new(min_section * Self::SECTION_HEIGHT, sections)
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    /// The first y coordinate above the world, i.e. this is exclusive.
+    pub fn max_y(&self) -> i32 {
+        self.min_y + (self.sections as i32) * Self::SECTION_HEIGHT
+    }
+
+    pub fn y_range(&self) -> Range<i32> {
+        self.min_y()..self.max_y()
+    }
+
+    pub fn sections(&self) -> u8 {
+        self.sections
+    }
+
+    pub fn min_section(&self) -> i32 {
+        Self::section_of(self.min_y)
+    }
+
+    pub fn section_range(&self) -> Range<i32> {
+        self.min_section()..self.min_section() + (self.sections as i32)
+    }
+
+    pub fn contains_y(&self, y: i32) -> bool {
+        self.y_range().contains(&y)
+    }
+
+    /// The section a y coordinate falls into. Uses an arithmetic shift, so y = -1 lies in section -1 rather than 0.
+    pub fn section_of(y: i32) -> i32 {
+        y >> 4
+    }
+
+    /// The position of a section relative to the bottom of the world, if it's inside of it.
+    pub fn section_index(&self, section: i32) -> Option<usize> {
+        if self.section_range().contains(&section) {
+            Some((section - self.min_section()) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for WorldHeight {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Packetable for WorldHeight {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u8(self.min_section() as i8 as u8)?;
+        buffer.write_u8(self.sections)?;
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        let min_section = reader.next_byte()? as i8 as i32;
+        let sections = reader.next_byte()?;
+
+        Self::from_sections(min_section, sections)
+    }
+}
+
+impl FixedSizePacketable for WorldHeight {
+    const SIZE_IN_BYTES: usize = 2;
+}
@@ -0,0 +1,36 @@
+use std::io::{ Write, BufWriter };
+
+use crate::cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt };
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DimensionId(pub u16);
+
+impl DimensionId {
+    pub const OVERWORLD: Self = Self(0);
+    pub const NETHER: Self = Self(1);
+    pub const END: Self = Self(2);
+}
+
+impl Default for DimensionId {
+    fn default() -> Self {
+        Self::OVERWORLD
+    }
+}
+
+impl Packetable for DimensionId {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u16(self.0)?;
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self(reader.next_u16()?))
+    }
+}
+
+impl FixedSizePacketable for DimensionId {
+    const SIZE_IN_BYTES: usize = 2;
+}
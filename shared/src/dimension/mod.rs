@@ -1,4 +1,10 @@
 pub mod chunk;
 pub mod subchunk;
 pub mod storage;
+pub mod height;
+pub mod id;
+pub mod settings;
+pub mod raycast;
+pub mod collision;
 
+pub mod access;
@@ -0,0 +1,83 @@
+use glam::{ Vec3, IVec3 };
+
+use crate::util::{ block_pos::BlockPos, direction::Direction };
+
+use super::storage::ChunkStorage;
+
+/// The first block a ray ran into.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaycastHit {
+    pub pos: BlockPos,
+    pub face: Direction, //The side of the block the ray came in through
+    pub point: Vec3,
+    pub distance: f32,
+}
+
+/// Walks the blocks along a ray one by one, in the order the ray passes through them, until one of their collision
+/// shapes is hit or the ray is `max_dist` long. Shapes the ray starts inside of are passed through, and so is
+/// everything outside of the world's height.
+pub fn raycast<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    origin: Vec3,
+    dir: Vec3,
+    max_dist: f32
+) -> anyhow::Result<Option<RaycastHit>> {
+    //The walk below would never reach the end of a ray that isn't finite
+    if !origin.is_finite() || !dir.is_finite() || !max_dist.is_finite() {
+        return Ok(None);
+    }
+
+    let dir = dir.normalize_or_zero();
+
+    if dir == Vec3::ZERO || max_dist <= 0.0 {
+        return Ok(None);
+    }
+
+    let mut voxel = origin.floor().as_ivec3();
+    let step = IVec3::new(dir.x.signum() as i32, dir.y.signum() as i32, dir.z.signum() as i32);
+
+    //How far along the ray the next block boundary on each axis is, and how far apart the boundaries are
+    let mut next = Vec3::ZERO;
+    let mut delta = Vec3::ZERO;
+
+    for axis in 0..3 {
+        if dir[axis] == 0.0 {
+            next[axis] = f32::INFINITY;
+            delta[axis] = f32::INFINITY;
+        } else {
+            let boundary = if dir[axis] > 0.0 { (voxel[axis] + 1) as f32 } else { voxel[axis] as f32 };
+            next[axis] = (boundary - origin[axis]) / dir[axis];
+            delta[axis] = 1.0 / dir[axis].abs();
+        }
+    }
+
+    loop {
+        let pos = BlockPos::new(voxel.x, voxel.y, voxel.z);
+        let chunk = storage.get_chunk(&pos.get_chunk())?;
+
+        if chunk.height().contains_y(pos.y()) {
+            let block = chunk.get_block(pos.clone())?.resolve()?;
+
+            //Shapes stay within their block, so the first block that's hit at all has the closest hit
+            let hit = block
+                .collision_shape()
+                .at(&pos)
+                .filter_map(|aabb| aabb.clip(origin, dir))
+                .filter(|(distance, _)| *distance <= max_dist)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+
+            if let Some((distance, face)) = hit {
+                return Ok(Some(RaycastHit { pos, face, point: origin + dir * distance, distance }));
+            }
+        }
+
+        let axis = if next.x <= next.y && next.x <= next.z { 0 } else if next.y <= next.z { 1 } else { 2 };
+
+        if next[axis] > max_dist {
+            return Ok(None);
+        }
+
+        voxel[axis] += step[axis];
+        next[axis] += delta[axis];
+    }
+}
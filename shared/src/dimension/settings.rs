@@ -0,0 +1,79 @@
+use std::io::{ Write, BufWriter };
+
+use crate::cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt };
+
+use super::height::WorldHeight;
+
+/// Everything a client needs to know about a dimension before it can receive its chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionSettings {
+    pub height: WorldHeight,
+    pub has_skylight: bool,
+    pub has_ceiling: bool,
+}
+
+impl DimensionSettings {
+    const SKYLIGHT_FLAG: u8 = 1;
+    const CEILING_FLAG: u8 = 1 << 1;
+
+    pub fn overworld() -> Self {
+        Self {
+            height: WorldHeight::new(-64, 24).expect("This is a valid height."),
+            has_skylight: true,
+            has_ceiling: false,
+        }
+    }
+
+    pub fn nether() -> Self {
+        Self {
+            height: WorldHeight::DEFAULT,
+            has_skylight: false,
+            has_ceiling: true,
+        }
+    }
+
+    pub fn end() -> Self {
+        Self {
+            height: WorldHeight::DEFAULT,
+            has_skylight: false,
+            has_ceiling: false,
+        }
+    }
+}
+
+impl Packetable for DimensionSettings {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        let mut flags = 0;
+
+        if self.has_skylight {
+            flags |= Self::SKYLIGHT_FLAG;
+        }
+
+        if self.has_ceiling {
+            flags |= Self::CEILING_FLAG;
+        }
+
+        self.height.write_to_buffer(buffer)?;
+        buffer.write_u8(flags)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        let height = WorldHeight::read_from_buf(reader)?;
+        let flags = reader.next_byte()?;
+
+        Ok(Self {
+            height,
+            has_skylight: (flags & Self::SKYLIGHT_FLAG) != 0,
+            has_ceiling: (flags & Self::CEILING_FLAG) != 0,
+        })
+    }
+}
+
+impl FixedSizePacketable for DimensionSettings {
+    const SIZE_IN_BYTES: usize = WorldHeight::SIZE_IN_BYTES + 1;
+}
@@ -1,13 +1,26 @@
-use crate::util::chunk_pos::ChunkPos;
+use glam::Vec3;
 
-use super::chunk::Chunk;
+use crate::util::{ chunk_pos::ChunkPos, aabb::Aabb };
+
+use super::{ chunk::Chunk, raycast::{ self, RaycastHit }, collision };
 
 pub trait ChunkLoader {
-    fn get_chunk(&self, pos: &ChunkPos) -> Option<Chunk>;
+    fn get_chunk(&self, pos: &ChunkPos) -> anyhow::Result<Option<Chunk>>;
 }
 
 pub trait ChunkStorage {
     fn is_chunk_cached(&self, pos: &ChunkPos) -> bool;
 
     fn get_chunk(&mut self, pos: &ChunkPos) -> anyhow::Result<&Chunk>;
+
+    /// The first block with a collision shape along the ray from `origin` in direction `dir`, at most `max_dist` away.
+    /// Loads every chunk the ray passes through.
+    fn raycast(&mut self, origin: Vec3, dir: Vec3, max_dist: f32) -> anyhow::Result<Option<RaycastHit>> {
+        raycast::raycast(self, origin, dir, max_dist)
+    }
+
+    /// The block collision boxes intersecting `area`. Loads every chunk the area touches.
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>> {
+        collision::collision_boxes(self, area)
+    }
 }
\ No newline at end of file
@@ -1,6 +1,9 @@
 use std::{slice::from_raw_parts, io::Write};
 
-use crate::{block::BlockId, cbs::{Packetable, PacketBuf, FixedSizePacketable}};
+use crate::{
+    block::{ BlockId, palette::BlockRemapper },
+    cbs::{ Packetable, PacketBuf, FixedSizePacketable },
+};
 
 
 #[derive(Debug, Clone)]
@@ -17,6 +20,20 @@ impl SubChunk {
     pub fn get_block(&self, x: i16, y: i16, z: i16) -> BlockId {
         BlockId(self.data[((y << 8) | (z << 4) | x) as usize])
     }
+
+    pub fn set_block(&mut self, x: i16, y: i16, z: i16, block: BlockId) {
+        self.data[((y << 8) | (z << 4) | x) as usize] = block.0;
+    }
+
+    pub fn remap(&mut self, remapper: &BlockRemapper) {
+        for block in self.data.iter_mut() {
+            *block = remapper.remap(BlockId(*block)).0;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|block| *block == BlockId::default().0)
+    }
 }
 
 impl Default for SubChunk {
@@ -39,11 +56,14 @@ impl Packetable for SubChunk {
 
     fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
         let bytes = reader.next_bytes::<{ Self::BYTES }>()?;
-        Ok(Self {
-            data: (unsafe { from_raw_parts(bytes.as_ptr() as *const u16, Self::BLOCK_COUNT) })
-                .try_into()
-                .unwrap(),
-        })
+        let mut data = [0u16; Self::BLOCK_COUNT];
+
+        //The buffer isn't necessarily aligned for u16s, so the data can't just be reinterpreted
+        for (block, pair) in data.iter_mut().zip(bytes.chunks_exact(2)) {
+            *block = u16::from_le_bytes([pair[0], pair[1]]);
+        }
+
+        Ok(Self { data })
     }
 
   
@@ -0,0 +1,25 @@
+use std::io::{ Write, BufWriter };
+
+use crate::cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt };
+
+/// Identifies an entity while the server is running. Ids aren't saved, entities get a new one every time they're loaded.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EntityId(pub u32);
+
+impl Packetable for EntityId {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u32(self.0)?;
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self(reader.next_u32()?))
+    }
+}
+
+impl FixedSizePacketable for EntityId {
+    const SIZE_IN_BYTES: usize = 4;
+}
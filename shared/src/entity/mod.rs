@@ -0,0 +1,42 @@
+pub mod id;
+pub mod movement;
+pub mod spawn;
+pub mod player;
+pub mod physics;
+pub mod pathfinding;
+
+use std::io::{ Write, BufWriter };
+
+use glam::{ Vec2, Vec3 };
+
+use crate::{ cbs::{ PacketBuf, WriteExt }, util::aabb::Aabb };
+
+/// The box an entity of `size` (width and height) takes up when standing at `pos`, with `pos` at the bottom center.
+pub fn bounding_box(pos: Vec3, size: Vec2) -> Aabb {
+    let half = Vec3::new(size.x / 2.0, 0.0, size.x / 2.0);
+
+    Aabb::new(pos - half, pos + half + Vec3::new(0.0, size.y, 0.0))
+}
+
+pub(crate) fn write_vec3<T: Write>(buffer: &mut BufWriter<T>, vec: Vec3) -> anyhow::Result<()> {
+    for component in vec.to_array() {
+        buffer.write_f32(component)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_vec3(reader: &mut PacketBuf) -> anyhow::Result<Vec3> {
+    Ok(Vec3::new(reader.next_f32()?, reader.next_f32()?, reader.next_f32()?))
+}
+
+pub(crate) fn write_vec2<T: Write>(buffer: &mut BufWriter<T>, vec: Vec2) -> anyhow::Result<()> {
+    buffer.write_f32(vec.x)?;
+    buffer.write_f32(vec.y)?;
+
+    Ok(())
+}
+
+pub(crate) fn read_vec2(reader: &mut PacketBuf) -> anyhow::Result<Vec2> {
+    Ok(Vec2::new(reader.next_f32()?, reader.next_f32()?))
+}
@@ -0,0 +1,84 @@
+use std::io::{ Write, BufWriter };
+
+use glam::Vec3;
+
+use crate::{ cbs::{ Packetable, DynamicSizePacketable, PacketBuf, WriteExt }, error::net::PacketReadError };
+
+use super::{ write_vec3, read_vec3 };
+
+/// How an entity moved since clients were last told where it is. Small moves are sent as a fixed point delta,
+/// anything further as the new position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityMovement {
+    Delta(i16, i16, i16), //In 1/4096ths of a block
+    Teleport(Vec3),
+}
+
+impl EntityMovement {
+    pub const UNITS_PER_BLOCK: f32 = 4096.0;
+
+    const DELTA: u8 = 0;
+    const TELEPORT: u8 = 1;
+
+    /// The movement from `from` to `to`. A delta won't end up exactly at `to`, so whoever sends it should keep
+    /// track of where `apply` puts the entity, not where it really is.
+    pub fn between(from: Vec3, to: Vec3) -> Self {
+        let units = ((to - from) * Self::UNITS_PER_BLOCK).round();
+        let fits = |component: f32| component >= i16::MIN as f32 && component <= i16::MAX as f32;
+
+        if units.to_array().into_iter().all(fits) {
+            Self::Delta(units.x as i16, units.y as i16, units.z as i16)
+        } else {
+            Self::Teleport(to)
+        }
+    }
+
+    /// Where an entity that was at `from` is after this movement.
+    pub fn apply(&self, from: Vec3) -> Vec3 {
+        match *self {
+            Self::Delta(x, y, z) => from + Vec3::new(x as f32, y as f32, z as f32) / Self::UNITS_PER_BLOCK,
+            Self::Teleport(to) => to,
+        }
+    }
+}
+
+impl Packetable for EntityMovement {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Delta(x, y, z) => {
+                buffer.write_u8(Self::DELTA)?;
+
+                for component in [x, y, z] {
+                    buffer.write_u16(component as u16)?;
+                }
+            }
+            Self::Teleport(to) => {
+                buffer.write_u8(Self::TELEPORT)?;
+                write_vec3(buffer, to)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        match reader.next_byte()? {
+            Self::DELTA =>
+                Ok(Self::Delta(reader.next_u16()? as i16, reader.next_u16()? as i16, reader.next_u16()? as i16)),
+            Self::TELEPORT => Ok(Self::Teleport(read_vec3(reader)?)),
+            other => Err(PacketReadError::InvalidEntityMovement(other).into()),
+        }
+    }
+}
+
+impl DynamicSizePacketable for EntityMovement {
+    fn size_in_bytes(&self) -> usize {
+        match self {
+            Self::Delta(..) => 1 + 3 * 2,
+            Self::Teleport(_) => 1 + 3 * 4,
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Finding a way through the world for entities that walk, and following it.
+
+use std::{ cmp::Ordering, collections::BinaryHeap };
+
+use glam::{ Vec2, Vec3 };
+use metrohash::MetroHashMap;
+
+use crate::{
+    block::fluid::FluidState,
+    dimension::storage::ChunkStorage,
+    util::{ block_pos::BlockPos, direction::Direction },
+};
+
+use super::physics::Body;
+
+/// What a walking entity is able to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathSettings {
+    pub width: f32, //Entities wider than a block need room in the blocks around the one they stand in as well
+    pub height: f32,
+    pub max_jump: i32, //How many blocks the entity can climb in one step
+    pub max_drop: i32, //How many blocks it's willing to fall
+    pub node_budget: usize, //How many positions are looked at before giving up
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        Self { width: 0.6, height: 1.8, max_jump: 1, max_drop: 3, node_budget: 2000 }
+    }
+}
+
+/// The positions an entity stands in along its way, from where it starts to where it ends up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub nodes: Vec<BlockPos>,
+    pub complete: bool, //False if the path only gets as close to the goal as possible
+}
+
+impl Path {
+    pub fn end(&self) -> &BlockPos {
+        self.nodes.last().expect("Paths always have at least their start.")
+    }
+
+    /// Drops the nodes that an entity can skip by walking straight to a later one on the same height.
+    pub fn smooth<S: ChunkStorage + ?Sized>(&mut self, storage: &mut S, settings: &PathSettings) -> anyhow::Result<()> {
+        let mut smoothed = vec![self.nodes[0].clone()];
+        let mut current = 0;
+
+        while current < self.nodes.len() - 1 {
+            let mut furthest = current + 1;
+
+            for candidate in (current + 2..self.nodes.len()).rev() {
+                if is_straight_walk(storage, &self.nodes[current], &self.nodes[candidate], settings)? {
+                    furthest = candidate;
+                    break;
+                }
+            }
+
+            smoothed.push(self.nodes[furthest].clone());
+            current = furthest;
+        }
+
+        self.nodes = smoothed;
+
+        Ok(())
+    }
+}
+
+/// A* search for a walkable path from `start` to `goal`, both being the block an entity stands in. If the goal can't
+/// be reached within the settings' node budget, the path leads to the closest position that was found instead.
+/// `None` if the entity can't stand at `start` at all.
+pub fn find_path<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    start: BlockPos,
+    goal: BlockPos,
+    settings: &PathSettings
+) -> anyhow::Result<Option<Path>> {
+    if !is_walkable(storage, &start, settings)? {
+        return Ok(None);
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut visited: MetroHashMap<BlockPos, (Option<BlockPos>, f32)> = MetroHashMap::default();
+    let mut closest = (estimate(&start, &goal), start.clone());
+    let mut expanded = 0;
+
+    visited.insert(start.clone(), (None, 0.0));
+    open.push(OpenNode { pos: start.clone(), cost: 0.0, estimate: estimate(&start, &goal) });
+
+    while let Some(OpenNode { pos, cost, .. }) = open.pop() {
+        if pos == goal {
+            closest = (0.0, pos);
+            break;
+        }
+
+        //Outdated entries for nodes that were reached more cheaply later on
+        if visited.get(&pos).is_some_and(|(_, best)| *best < cost) {
+            continue;
+        }
+
+        expanded += 1;
+
+        if expanded > settings.node_budget {
+            break;
+        }
+
+        for (next, step_cost) in neighbours(storage, &pos, settings)? {
+            let next_cost = cost + step_cost;
+
+            if visited.get(&next).is_some_and(|(_, best)| *best <= next_cost) {
+                continue;
+            }
+
+            let remaining = estimate(&next, &goal);
+
+            if remaining < closest.0 {
+                closest = (remaining, next.clone());
+            }
+
+            visited.insert(next.clone(), (Some(pos.clone()), next_cost));
+            open.push(OpenNode { pos: next, cost: next_cost, estimate: next_cost + remaining });
+        }
+    }
+
+    let (_, end) = closest;
+    let complete = end == goal;
+    let mut nodes = vec![end];
+
+    while let Some((Some(previous), _)) = visited.get(nodes.last().expect("The end is always there.")) {
+        nodes.push(previous.clone());
+    }
+
+    nodes.reverse();
+
+    let mut path = Path { nodes, complete };
+    path.smooth(storage, settings)?;
+
+    Ok(Some(path))
+}
+
+/// A position waiting to be expanded, ordered so the heap pops the one with the lowest estimated total first.
+struct OpenNode {
+    pos: BlockPos,
+    cost: f32,
+    estimate: f32,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// Every step costs at least one per block along each axis, so this never overestimates.
+fn estimate(from: &BlockPos, to: &BlockPos) -> f32 {
+    ((from.x() - to.x()).abs() + (from.y() - to.y()).abs() + (from.z() - to.z()).abs()) as f32
+}
+
+/// The positions that can be walked to from `pos` in one step, with what it costs to get there. Climbing and
+/// dropping cost one more per block of height.
+fn neighbours<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    pos: &BlockPos,
+    settings: &PathSettings
+) -> anyhow::Result<Vec<(BlockPos, f32)>> {
+    let mut result = Vec::new();
+    let clearance = clearance(settings);
+    let radius = footprint_radius(settings);
+
+    for direction in Direction::HORIZONTALS {
+        let next = pos.offset(direction);
+
+        if is_walkable(storage, &next, settings)? {
+            result.push((next, 1.0));
+            continue;
+        }
+
+        //Climbing needs room above the entity's head where it is now
+        for up in 1..=settings.max_jump {
+            if !has_clearance(storage, &BlockPos::new(pos.x(), pos.y() + clearance + up - 1, pos.z()), 1, radius)? {
+                break;
+            }
+
+            let target = BlockPos::new(next.x(), next.y() + up, next.z());
+
+            if is_walkable(storage, &target, settings)? {
+                result.push((target, 1.0 + up as f32));
+                break;
+            }
+        }
+
+        //Dropping needs the whole column the entity falls through to be free
+        if !has_clearance(storage, &next, clearance, radius)? {
+            continue;
+        }
+
+        for down in 1..=settings.max_drop {
+            let target = BlockPos::new(next.x(), next.y() - down, next.z());
+
+            if is_walkable(storage, &target, settings)? {
+                result.push((target, 1.0 + down as f32));
+                break;
+            }
+
+            if !has_clearance(storage, &target, 1, radius)? {
+                break;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// How many blocks high the room an entity needs is.
+fn clearance(settings: &PathSettings) -> i32 {
+    settings.height.ceil().max(1.0) as i32
+}
+
+/// How many blocks an entity standing in the middle of a block reaches into the blocks around it, horizontally.
+fn footprint_radius(settings: &PathSettings) -> i32 {
+    (settings.width / 2.0 - 0.5).ceil().max(0.0) as i32
+}
+
+/// The blocks on the same height as `pos` that an entity standing in the middle of it takes up.
+fn footprint(pos: &BlockPos, radius: i32) -> impl Iterator<Item = BlockPos> + '_ {
+    (-radius..=radius)
+        .flat_map(move |x| (-radius..=radius).map(move |z| BlockPos::new(pos.x() + x, pos.y(), pos.z() + z)))
+}
+
+/// Whether an entity can stand in `pos`: on top of something solid, with enough room and no fluid all around it.
+fn is_walkable<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    pos: &BlockPos,
+    settings: &PathSettings
+) -> anyhow::Result<bool> {
+    let radius = footprint_radius(settings);
+
+    if !has_clearance(storage, pos, clearance(settings), radius)? {
+        return Ok(false);
+    }
+
+    for block in footprint(pos, radius) {
+        if is_solid(storage, &block.offset(Direction::Down))? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Whether the `clearance` blocks high room from `pos` up is free, across the footprint of `radius`.
+fn has_clearance<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    pos: &BlockPos,
+    clearance: i32,
+    radius: i32
+) -> anyhow::Result<bool> {
+    for block in footprint(pos, radius) {
+        for up in 0..clearance {
+            if !is_passable(storage, &BlockPos::new(block.x(), block.y() + up, block.z()))? {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Nothing to collide with and no fluid. Outside of the world's height everything is passable.
+fn is_passable<S: ChunkStorage + ?Sized>(storage: &mut S, pos: &BlockPos) -> anyhow::Result<bool> {
+    let chunk = storage.get_chunk(&pos.get_chunk())?;
+
+    if !chunk.height().contains_y(pos.y()) {
+        return Ok(true);
+    }
+
+    let block = chunk.get_block(pos.clone())?;
+
+    Ok(block.resolve()?.collision_shape().is_empty() && FluidState::of(block).is_none())
+}
+
+/// Something to stand on. Outside of the world's height there's nothing.
+fn is_solid<S: ChunkStorage + ?Sized>(storage: &mut S, pos: &BlockPos) -> anyhow::Result<bool> {
+    let chunk = storage.get_chunk(&pos.get_chunk())?;
+
+    if !chunk.height().contains_y(pos.y()) {
+        return Ok(false);
+    }
+
+    Ok(!chunk.get_block(pos.clone())?.resolve()?.collision_shape().is_empty())
+}
+
+/// Whether an entity can walk from `from` straight to `to` on the same height without running into or falling off
+/// anything, checked along the line at each corner of its bounding box. Entities wider than a block are checked at
+/// every block between the corners too, with ground below each of them.
+fn is_straight_walk<S: ChunkStorage + ?Sized>(
+    storage: &mut S,
+    from: &BlockPos,
+    to: &BlockPos,
+    settings: &PathSettings
+) -> anyhow::Result<bool> {
+    const SAMPLE_DISTANCE: f32 = 0.25;
+
+    if from.y() != to.y() {
+        return Ok(false);
+    }
+
+    let start = Vec2::new(from.x() as f32 + 0.5, from.z() as f32 + 0.5);
+    let end = Vec2::new(to.x() as f32 + 0.5, to.z() as f32 + 0.5);
+    let samples = ((end - start).length() / SAMPLE_DISTANCE).ceil() as u32;
+    let half = Vec2::splat(settings.width / 2.0);
+    let clearance = clearance(settings);
+
+    for sample in 0..=samples {
+        let center = start.lerp(end, sample as f32 / samples.max(1) as f32);
+        let (min, max) = ((center - half).floor().as_ivec2(), (center + half).floor().as_ivec2());
+
+        for x in min.x..=max.x {
+            for z in min.y..=max.y {
+                let pos = BlockPos::new(x, from.y(), z);
+
+                if !is_solid(storage, &pos.offset(Direction::Down))? || !has_clearance(storage, &pos, clearance, 0)? {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Steers an entity along a path, one waypoint after the other.
+#[derive(Debug, Clone)]
+pub struct PathFollower {
+    path: Path,
+    next: usize,
+    speed: f32, //In blocks per tick
+}
+
+impl PathFollower {
+    /// How close to a waypoint the entity has to get, horizontally, before heading for the next one. It also has to
+    /// stand on the ground at the waypoint's height, so jumps and falls aren't cut short.
+    pub const REACHED_DISTANCE: f32 = 0.2;
+    /// The upward velocity to jump up a block with, given the default physics.
+    pub const JUMP_VELOCITY: f32 = 0.5;
+
+    pub fn new(path: Path, speed: f32) -> Self {
+        //The first node is where the entity already is
+        Self { path, next: 1, speed }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.path.nodes.len()
+    }
+
+    /// The velocity `body` should have to get on towards the next waypoint, or `None` once it's at the end. Jumps
+    /// when the waypoint is higher up and the body is on the ground.
+    pub fn steer(&mut self, body: &Body) -> Option<Vec3> {
+        let target = loop {
+            let node = self.path.nodes.get(self.next)?;
+            let target = Vec3::new(node.x() as f32 + 0.5, node.y() as f32, node.z() as f32 + 0.5);
+            let offset = Vec2::new(target.x - body.pos.x, target.z - body.pos.z);
+
+            let reached = offset.length() <= Self::REACHED_DISTANCE &&
+                body.on_ground &&
+                (target.y - body.pos.y).abs() < 0.5;
+
+            if !reached {
+                break target;
+            }
+
+            self.next += 1;
+        };
+
+        let offset = Vec2::new(target.x - body.pos.x, target.z - body.pos.z);
+        let horizontal = offset.normalize_or_zero() * offset.length().min(self.speed);
+        let jump = target.y > body.pos.y + 0.5 && body.on_ground;
+        let vertical = if jump { Self::JUMP_VELOCITY } else { body.velocity.y };
+
+        Some(Vec3::new(horizontal.x, vertical, horizontal.y))
+    }
+}
@@ -0,0 +1,200 @@
+//! Movement of entities through the world, shared by the server and the client so that a client predicting its own
+//! player ends up exactly where the server does. Nothing in here depends on anything but its inputs.
+
+use glam::{ Vec2, Vec3 };
+
+use crate::{
+    block::BlockId,
+    dimension::{ access::WorldAccess, storage::ChunkStorage },
+    util::{ aabb::Aabb, block_pos::BlockPos },
+};
+
+use super::bounding_box;
+
+/// How an entity is pulled around while it moves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsSettings {
+    pub gravity: f32, //Subtracted from the vertical velocity every tick
+    pub drag: f32, //The part of the velocity that is lost every tick
+    pub step_height: f32, //Ledges up to this high are walked up onto, rather than bumped into
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self { gravity: 0.08, drag: 0.02, step_height: 0.6 }
+    }
+}
+
+/// The part of the world physics looks at: what's in the way, and what a body is standing on.
+pub trait Surroundings {
+    /// The block collision boxes intersecting `area`.
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>>;
+
+    /// The block at `pos`, air outside of the world's height.
+    fn block(&mut self, pos: BlockPos) -> anyhow::Result<BlockId>;
+}
+
+impl<S: ChunkStorage + ?Sized> Surroundings for S {
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>> {
+        ChunkStorage::collision_boxes(self, area)
+    }
+
+    fn block(&mut self, pos: BlockPos) -> anyhow::Result<BlockId> {
+        let chunk = self.get_chunk(&pos.get_chunk())?;
+
+        if !chunk.height().contains_y(pos.y()) {
+            return Ok(BlockId::default());
+        }
+
+        chunk.get_block(pos)
+    }
+}
+
+impl Surroundings for dyn WorldAccess + '_ {
+    fn collision_boxes(&mut self, area: &Aabb) -> anyhow::Result<Vec<Aabb>> {
+        WorldAccess::collision_boxes(self, area)
+    }
+
+    fn block(&mut self, pos: BlockPos) -> anyhow::Result<BlockId> {
+        if !self.height().contains_y(pos.y()) {
+            return Ok(BlockId::default());
+        }
+
+        self.get_block(pos)
+    }
+}
+
+/// What an entity ran into during a tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collisions {
+    pub on_ground: bool,
+    pub horizontal: bool, //Stopped by a wall along the x or z axis
+    pub vertical: bool, //Stopped by a floor or a ceiling
+}
+
+/// The part of an entity physics is concerned with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    pub pos: Vec3, //At the bottom center of the bounding box
+    pub velocity: Vec3,
+    pub size: Vec2, //Width and height
+    pub on_ground: bool,
+}
+
+impl Body {
+    pub fn bounding_box(&self) -> Aabb {
+        bounding_box(self.pos, self.size)
+    }
+
+    /// Where the collision boxes need to be known for the body to move by `movement`, including stepping up.
+    pub fn reach(&self, movement: Vec3, settings: &PhysicsSettings) -> Aabb {
+        self.bounding_box().expand_towards(movement).expand_towards(Vec3::Y * settings.step_height)
+    }
+}
+
+/// Runs one tick for `body`: moves it by its velocity as far as the blocks around it let it, then applies gravity,
+/// drag and the friction of the block it ends up standing on. Velocity along the axes the body was stopped on is
+/// dropped.
+pub fn step<W: Surroundings + ?Sized>(
+    body: &mut Body,
+    settings: &PhysicsSettings,
+    world: &mut W
+) -> anyhow::Result<Collisions> {
+    let movement = body.velocity;
+    let boxes = world.collision_boxes(&body.reach(movement, settings))?;
+    let moved = move_with_step(body.bounding_box(), movement, &boxes, settings.step_height, body.on_ground);
+
+    let collisions = Collisions {
+        on_ground: moved.y != movement.y && movement.y < 0.0,
+        horizontal: moved.x != movement.x || moved.z != movement.z,
+        vertical: moved.y != movement.y,
+    };
+
+    body.pos += moved;
+    body.on_ground = collisions.on_ground;
+
+    for axis in 0..3 {
+        if moved[axis] != movement[axis] {
+            body.velocity[axis] = 0.0;
+        }
+    }
+
+    body.velocity.y -= settings.gravity;
+    body.velocity *= 1.0 - settings.drag;
+
+    if body.on_ground {
+        let friction = world.block(ground_below(body.pos))?.resolve()?.friction();
+        body.velocity.x *= friction;
+        body.velocity.z *= friction;
+    }
+
+    Ok(collisions)
+}
+
+/// The block a body at `pos` stands on. Half a block down, so bodies on slabs get the slab rather than what's below.
+pub fn ground_below(pos: Vec3) -> BlockPos {
+    let below = (pos - Vec3::Y * 0.5).floor().as_ivec3();
+
+    BlockPos::new(below.x, below.y, below.z)
+}
+
+/// How far `aabb` gets when moved by `movement`, stopping at the first of `obstacles` along each axis. The axes are
+/// handled one after the other, vertical first, so sliding along walls and floors works.
+pub fn collide(aabb: &Aabb, movement: Vec3, obstacles: &[Aabb]) -> Vec3 {
+    let mut aabb = *aabb;
+    let mut moved = Vec3::ZERO;
+
+    for axis in [1, 0, 2] {
+        let offset = obstacles.iter().fold(movement[axis], |offset, other| clip_axis(&aabb, other, axis, offset));
+
+        moved[axis] = offset;
+        aabb.min[axis] += offset;
+        aabb.max[axis] += offset;
+    }
+
+    moved
+}
+
+/// Like `collide`, but a body on the ground that is stopped by a ledge no higher than `step_height` walks up onto it.
+fn move_with_step(aabb: Aabb, movement: Vec3, obstacles: &[Aabb], step_height: f32, on_ground: bool) -> Vec3 {
+    let moved = collide(&aabb, movement, obstacles);
+    let blocked = moved.x != movement.x || moved.z != movement.z;
+    let landing = movement.y < 0.0 && moved.y != movement.y;
+
+    if step_height <= 0.0 || !blocked || !(on_ground || landing) {
+        return moved;
+    }
+
+    //Up as far as it goes, across, then back down onto whatever is there
+    let up = collide(&aabb, Vec3::Y * step_height, obstacles).y;
+    let raised = aabb.offset(Vec3::Y * up);
+    let across = collide(&raised, Vec3::new(movement.x, 0.0, movement.z), obstacles);
+    let down = collide(&raised.offset(across), Vec3::NEG_Y * up, obstacles).y;
+    let stepped = Vec3::new(across.x, up + down, across.z);
+
+    if Vec2::new(stepped.x, stepped.z).length_squared() > Vec2::new(moved.x, moved.z).length_squared() {
+        stepped
+    } else {
+        moved
+    }
+}
+
+/// Shortens `movement` along `axis` so `aabb` stops at `other`, if `other` is in the way.
+fn clip_axis(aabb: &Aabb, other: &Aabb, axis: usize, movement: f32) -> f32 {
+    //Boxes barely overlapping, e.g. from rounding, are still treated as being next to each other
+    const EPSILON: f32 = 1.0e-5;
+
+    let overlaps = (0..3)
+        .filter(|other_axis| *other_axis != axis)
+        .all(|other_axis| aabb.min[other_axis] < other.max[other_axis] && aabb.max[other_axis] > other.min[other_axis]);
+
+    if !overlaps {
+        movement
+    } else if movement > 0.0 && other.min[axis] >= aabb.max[axis] - EPSILON {
+        movement.min(other.min[axis] - aabb.max[axis])
+    } else if movement < 0.0 && other.max[axis] <= aabb.min[axis] + EPSILON {
+        movement.max(other.max[axis] - aabb.min[axis])
+    } else {
+        movement
+    }
+}
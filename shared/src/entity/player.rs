@@ -0,0 +1,137 @@
+use std::io::{ Write, BufWriter };
+
+use glam::{ Vec2, Vec3 };
+
+use crate::{
+    cbs::{ Packetable, FixedSizePacketable, PacketBuf, WriteExt },
+    block::Block,
+    error::net::PacketReadError,
+    util::{ block_pos::BlockPos, direction::Direction },
+};
+
+use super::{ write_vec3, read_vec3, write_vec2, read_vec2 };
+
+/// The width and height of a player's bounding box.
+pub const PLAYER_SIZE: Vec2 = Vec2::new(0.6, 1.8);
+/// How far above its feet a player looks from.
+pub const PLAYER_EYE_HEIGHT: f32 = 1.62;
+/// How far from its eyes a player can reach blocks.
+pub const PLAYER_REACH: f32 = 5.0;
+/// How many ticks it takes to break a block with bare hands, per point of hardness.
+pub const BREAK_TICKS_PER_HARDNESS: f32 = 30.0;
+
+/// The direction a player with `rotation` (yaw and pitch, in degrees) looks in. A yaw of 0 looks south, 90 west;
+/// a positive pitch looks down.
+pub fn look_direction(rotation: Vec2) -> Vec3 {
+    let (yaw, pitch) = (rotation.x.to_radians(), rotation.y.to_radians());
+
+    Vec3::new(-yaw.sin() * pitch.cos(), -pitch.sin(), yaw.cos() * pitch.cos())
+}
+
+/// How many ticks a player needs to break `block`. `None` for blocks that can't be broken at all, either because
+/// their hardness is negative or because there's nothing to aim at.
+pub fn break_ticks(block: &Block) -> Option<u32> {
+    if block.hardness() < 0.0 || block.outline_shape().is_empty() {
+        return None;
+    }
+
+    Some((block.hardness() * BREAK_TICKS_PER_HARDNESS).ceil() as u32)
+}
+
+/// Where a client says its player moved to. The server checks it before taking it over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerMove {
+    pub pos: Vec3,
+    pub rotation: Vec2,
+    pub on_ground: bool,
+}
+
+impl Packetable for PlayerMove {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        write_vec3(buffer, self.pos)?;
+        write_vec2(buffer, self.rotation)?;
+        buffer.write_u8(self.on_ground as u8)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self {
+            pos: read_vec3(reader)?,
+            rotation: read_vec2(reader)?,
+            on_ground: reader.next_byte()? != 0,
+        })
+    }
+}
+
+impl FixedSizePacketable for PlayerMove {
+    const SIZE_IN_BYTES: usize = 3 * 4 + 2 * 4 + 1;
+}
+
+/// Puts a client's player somewhere, e.g. back where it was after a move the server didn't accept. Until the client
+/// confirms it with the same id, the server ignores its moves, since they started from the wrong place.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerTeleport {
+    pub id: u32,
+    pub pos: Vec3,
+    pub rotation: Vec2,
+}
+
+impl Packetable for PlayerTeleport {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u32(self.id)?;
+        write_vec3(buffer, self.pos)?;
+        write_vec2(buffer, self.rotation)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self { id: reader.next_u32()?, pos: read_vec3(reader)?, rotation: read_vec2(reader)? })
+    }
+}
+
+impl FixedSizePacketable for PlayerTeleport {
+    const SIZE_IN_BYTES: usize = 4 + 3 * 4 + 2 * 4;
+}
+
+/// A player clicking the `face` of the block at `pos` with whatever it holds. The block is used if it reacts to that,
+/// otherwise a held block is placed against the face. `hit` is where on the block the click landed, from 0 to 1 on
+/// each axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceBlock {
+    pub pos: BlockPos,
+    pub face: Direction,
+    pub hit: Vec3,
+}
+
+impl Packetable for PlaceBlock {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        self.pos.write_to_buffer(buffer)?;
+        buffer.write_u8(self.face as u8)?;
+        write_vec3(buffer, self.hit)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        let pos = BlockPos::read_from_buf(reader)?;
+        let face = reader.next_byte()?;
+        let face = *Direction::LOOKUP.get(face as usize).ok_or(PacketReadError::InvalidDirection(face))?;
+
+        Ok(Self { pos, face, hit: read_vec3(reader)? })
+    }
+}
+
+impl FixedSizePacketable for PlaceBlock {
+    const SIZE_IN_BYTES: usize = BlockPos::SIZE_IN_BYTES + 1 + 3 * 4;
+}
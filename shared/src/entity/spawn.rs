@@ -0,0 +1,48 @@
+use std::io::{ Write, BufWriter };
+
+use glam::{ Vec2, Vec3 };
+
+use crate::cbs::{ Packetable, DynamicSizePacketable, FixedSizePacketable, PacketBuf, WriteExt };
+
+use super::{ id::EntityId, write_vec3, read_vec3, write_vec2, read_vec2 };
+
+/// Everything a client needs to know about an entity it hasn't seen before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySpawn {
+    pub id: EntityId,
+    pub kind: String,
+    pub pos: Vec3,
+    pub velocity: Vec3,
+    pub rotation: Vec2,
+}
+
+impl Packetable for EntitySpawn {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        self.id.write_to_buffer(buffer)?;
+        buffer.write_string(&self.kind)?;
+        write_vec3(buffer, self.pos)?;
+        write_vec3(buffer, self.velocity)?;
+        write_vec2(buffer, self.rotation)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self {
+            id: EntityId::read_from_buf(reader)?,
+            kind: reader.next_string()?,
+            pos: read_vec3(reader)?,
+            velocity: read_vec3(reader)?,
+            rotation: read_vec2(reader)?,
+        })
+    }
+}
+
+impl DynamicSizePacketable for EntitySpawn {
+    fn size_in_bytes(&self) -> usize {
+        EntityId::SIZE_IN_BYTES + 2 + self.kind.len() + 2 * 3 * 4 + 2 * 4
+    }
+}
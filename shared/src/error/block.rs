@@ -1,10 +1,52 @@
 use anyhow::anyhow;
 
 #[derive(Debug)]
-pub struct InvalidBlockIdError(pub u8);
+pub struct InvalidBlockIdError(pub u16);
 
 impl From<InvalidBlockIdError> for anyhow::Error {
     fn from(value: InvalidBlockIdError) -> Self {
         anyhow!("Invalid Block Id: {}", value.0)
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+pub enum BlockRegistryError {
+    DuplicateBlock(String),
+    UnknownBlock(String),
+    AirIsNotZero(u16),
+    TooManyBlocks(String, u16),
+    TooManyStates(String, usize),
+    InvalidStateId(String, u8),
+    UnknownProperty(String, String),
+    InvalidPropertyValue(String, String, String),
+    PropertyMismatch(String),
+    AlreadyInstalled,
+    NotInstalled,
+}
+
+impl From<BlockRegistryError> for anyhow::Error {
+    fn from(value: BlockRegistryError) -> Self {
+        match value {
+            BlockRegistryError::DuplicateBlock(name) =>
+                anyhow!("Block {} is defined more than once", name),
+            BlockRegistryError::UnknownBlock(name) => anyhow!("There is no block named {}", name),
+            BlockRegistryError::AirIsNotZero(id) =>
+                anyhow!("Air needs to have the block id 0, but the id mapping assigns it {}", id),
+            BlockRegistryError::TooManyBlocks(name, id) =>
+                anyhow!("Block {} was assigned id {}, but at most 256 blocks are supported", name, id),
+            BlockRegistryError::TooManyStates(name, count) =>
+                anyhow!("Block {} has {} states, but at most 256 are supported", name, count),
+            BlockRegistryError::InvalidStateId(name, state) =>
+                anyhow!("Block {} has no state with id {}", name, state),
+            BlockRegistryError::UnknownProperty(name, property) =>
+                anyhow!("Block {} has no property named {}", name, property),
+            BlockRegistryError::InvalidPropertyValue(name, property, value) =>
+                anyhow!("{} is not a valid value for property {} of block {}", value, property, name),
+            BlockRegistryError::PropertyMismatch(name) =>
+                anyhow!("The properties of block {} in the definitions file don't match its state in code", name),
+            BlockRegistryError::AlreadyInstalled =>
+                anyhow!("A block registry with different ids has already been installed"),
+            BlockRegistryError::NotInstalled => anyhow!("No block registry has been installed yet"),
+        }
+    }
+}
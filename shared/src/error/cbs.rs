@@ -1,13 +1,15 @@
 use anyhow::anyhow;
 
 pub enum CbsBufferError {
-    NotEnoughData(usize, usize)
+    NotEnoughData(usize, usize),
+    StringTooLong(usize),
 }
 
 impl From<CbsBufferError> for anyhow::Error {
     fn from(value: CbsBufferError) -> Self {
         match  value {
             CbsBufferError::NotEnoughData(needed, present) => anyhow!("Only {} bits remaining in buffer, however this read operation wants to read {} bits", present, needed),
+            CbsBufferError::StringTooLong(len) => anyhow!("Strings can be at most {} bytes long, this one has {} bytes", u16::MAX, len),
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,7 @@
-use crate::{util::chunk_pos::ChunkPos, dimension::subchunk::SubChunk};
+use crate::{
+    util::{ chunk_pos::ChunkPos, block_pos::BlockPos },
+    dimension::{ subchunk::SubChunk, height::WorldHeight },
+};
 use anyhow::anyhow;
 
 #[derive(Debug)]
@@ -30,6 +33,7 @@ impl From<InvalidSubChunkDataError> for anyhow::Error {
 pub enum InvalidChunkDataError {
     InvalidHeaderSize(usize),
     InvalidDataSize(usize, usize),
+    SectionOutOfRange(i32, WorldHeight),
 }
 
 impl From<InvalidChunkDataError> for anyhow::Error {
@@ -45,6 +49,37 @@ impl From<InvalidChunkDataError> for anyhow::Error {
                     expected,
                     expected / 8
                 ),
+            InvalidChunkDataError::SectionOutOfRange(section, height) =>
+                anyhow!(
+                    "Sub-chunk {} lies outside of the chunk's sections {:?}",
+                    section,
+                    height.section_range()
+                ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InvalidWorldHeightError {
+    UnalignedMinY(i32),
+    NoSections,
+    OutOfBounds(i32, i32),
+}
+
+impl From<InvalidWorldHeightError> for anyhow::Error {
+    fn from(value: InvalidWorldHeightError) -> Self {
+        match value {
+            InvalidWorldHeightError::UnalignedMinY(y) =>
+                anyhow!("The bottom of the world (y = {}) must be a multiple of 16", y),
+            InvalidWorldHeightError::NoSections =>
+                anyhow!("A world needs to be at least one sub-chunk high"),
+            InvalidWorldHeightError::OutOfBounds(min, max) =>
+                anyhow!(
+                    "World height {}..{} exceeds the representable range {:?}",
+                    min,
+                    max,
+                    BlockPos::VALID_Y
+                ),
         }
     }
 }
@@ -0,0 +1,29 @@
+use anyhow::anyhow;
+
+#[derive(Debug)]
+pub enum ItemError {
+    DuplicateItem(String),
+    UnknownItem(String),
+    UnknownItemId(u16),
+    EmptyStack(String),
+    InvalidSlot(usize, usize),
+    InvalidHotbarSlot(u8),
+    InvalidWindow(u8),
+    InvalidClick(u8),
+}
+
+impl From<ItemError> for anyhow::Error {
+    fn from(value: ItemError) -> Self {
+        match value {
+            ItemError::DuplicateItem(name) => anyhow!("Item {} is defined more than once", name),
+            ItemError::UnknownItem(name) => anyhow!("There is no item named {}", name),
+            ItemError::UnknownItemId(id) => anyhow!("There is no item with id {}", id),
+            ItemError::EmptyStack(name) => anyhow!("A stack of {} can't be empty", name),
+            ItemError::InvalidSlot(slot, size) =>
+                anyhow!("Slot {} doesn't exist in an inventory with {} slots", slot, size),
+            ItemError::InvalidHotbarSlot(slot) => anyhow!("Slot {} isn't part of the hotbar", slot),
+            ItemError::InvalidWindow(window) => anyhow!("There is no open window with id {}", window),
+            ItemError::InvalidClick(kind) => anyhow!("There is no kind of click with ID {}!", kind),
+        }
+    }
+}
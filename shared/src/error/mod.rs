@@ -2,4 +2,6 @@ pub mod block;
 pub mod dimension;
 pub mod net;
 pub mod util;
-pub mod cbs;
\ No newline at end of file
+pub mod cbs;
+pub mod tag;
+pub mod item;
\ No newline at end of file
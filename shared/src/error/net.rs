@@ -5,6 +5,9 @@ pub enum PacketReadError {
     InvalidPacketType(u16),
     NotEnoughData(u32, u32),
     IOError(io::Error),
+    InvalidEntityMovement(u8),
+    InvalidDirection(u8),
+    TooLarge(usize, usize),
 }
 
 impl From<PacketReadError> for anyhow::Error {
@@ -19,6 +22,12 @@ impl From<PacketReadError> for anyhow::Error {
             PacketReadError::IOError(inner) => inner.into(),
             PacketReadError::InvalidPacketType(id) =>
                 anyhow!("There is no Packet type with ID {}!", id),
+            PacketReadError::InvalidEntityMovement(kind) =>
+                anyhow!("There is no kind of entity movement with ID {}!", kind),
+            PacketReadError::InvalidDirection(direction) =>
+                anyhow!("There is no direction with ID {}!", direction),
+            PacketReadError::TooLarge(size, max) =>
+                anyhow!("Packet of {} bytes is bigger than the maximum of {} bytes", size, max),
         }
     }
 }
\ No newline at end of file
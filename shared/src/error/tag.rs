@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+
+#[derive(Debug)]
+pub enum TagError {
+    UnknownType(u8),
+    TooDeep(usize),
+    MixedList(&'static str, &'static str),
+    MissingValue(String, &'static str),
+    Snbt(String, usize),
+}
+
+impl From<TagError> for anyhow::Error {
+    fn from(value: TagError) -> Self {
+        match value {
+            TagError::UnknownType(id) => anyhow!("Unknown tag type {}", id),
+            TagError::TooDeep(depth) => anyhow!("Tags can't be nested more than {} levels deep", depth),
+            TagError::MixedList(expected, actual) =>
+                anyhow!("A list of {} tags can't hold a {} tag", expected, actual),
+            TagError::MissingValue(key, kind) => anyhow!("Compound has no {} tag named {}", kind, key),
+            TagError::Snbt(message, position) => anyhow!("Invalid SNBT at character {}: {}", position, message),
+        }
+    }
+}
+
+/// What serde sees when turning a value into a `Tag` or back fails. Unlike the other errors it needs to be a
+/// `std::error::Error`, since serde makes up its own messages through it.
+#[derive(Debug)]
+pub struct TagSerdeError(pub String);
+
+impl Display for TagSerdeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TagSerdeError {}
+
+impl serde::ser::Error for TagSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl serde::de::Error for TagSerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use anyhow::anyhow;
 
 use crate::util::block_pos::BlockPos;
@@ -21,7 +23,7 @@ impl From<PositionDeserializeError> for anyhow::Error {
 #[derive(Debug)]
 pub enum InvalidPositionError {
     InvalidX(i32),
-    InvalidY(i32),
+    InvalidY(i32, Range<i32>),
     InvalidZ(i32),
 }
 
@@ -34,12 +36,8 @@ impl From<InvalidPositionError> for anyhow::Error {
                     x,
                     BlockPos::VALID_X
                 ),
-            InvalidPositionError::InvalidY(y) =>
-                anyhow!(
-                    "Invalid y value {}. Y Coordinates must be in the range {:?}.",
-                    y,
-                    BlockPos::VALID_Y
-                ),
+            InvalidPositionError::InvalidY(y, range) =>
+                anyhow!("Invalid y value {}. Y Coordinates must be in the range {:?}.", y, range),
             InvalidPositionError::InvalidZ(z) =>
                 anyhow!(
                     "Invalid z value {}. Z Coordinates must be in the range {:?}.",
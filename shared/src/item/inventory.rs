@@ -0,0 +1,313 @@
+use std::{ io::{ Write, BufWriter }, ops::Range };
+
+use crate::{
+    cbs::{ Packetable, DynamicSizePacketable, FixedSizePacketable, PacketBuf, WriteExt },
+    error::item::ItemError,
+};
+
+use super::stack::{ ItemStack, write_slot, read_slot, slot_size };
+
+/// A fixed number of slots, each holding a stack or nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Self { slots: vec![None; size] }
+    }
+
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    pub fn slot_mut(&mut self, slot: usize) -> anyhow::Result<&mut Option<ItemStack>> {
+        let size = self.size();
+
+        Ok(self.slots.get_mut(slot).ok_or(ItemError::InvalidSlot(slot, size))?)
+    }
+
+    /// Returns what was in the slot before.
+    pub fn set(&mut self, slot: usize, stack: Option<ItemStack>) -> anyhow::Result<Option<ItemStack>> {
+        Ok(std::mem::replace(self.slot_mut(slot)?, stack))
+    }
+
+    /// Puts as much of `stack` into the whole inventory as fits, see `insert_into`.
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        self.insert_into(stack, 0..self.size())
+    }
+
+    /// Puts as much of `stack` into the slots in `range` as fits: onto stacks of the same item first, then into the
+    /// first empty slot. Returns what's left of it.
+    pub fn insert_into(&mut self, stack: ItemStack, range: Range<usize>) -> Option<ItemStack> {
+        let range = range.start.min(self.size())..range.end.min(self.size());
+        let mut remaining = Some(stack);
+
+        for slot in self.slots[range.clone()].iter_mut().flatten() {
+            slot.merge(&mut remaining, u8::MAX);
+
+            //Nothing left once everything found a place
+            remaining.as_ref()?;
+        }
+
+        if let Some(empty) = self.slots[range].iter_mut().find(|slot| slot.is_none()) {
+            *empty = remaining.take();
+        }
+
+        remaining
+    }
+
+    /// Picks up the whole stack in `slot` if nothing is held, otherwise puts down as much of `cursor` as fits there.
+    /// A stack of a different item is swapped with the held one.
+    pub fn left_click(&mut self, cursor: &mut Option<ItemStack>, slot: usize) -> anyhow::Result<()> {
+        let slot = self.slot_mut(slot)?;
+
+        match (slot.as_mut(), cursor.as_mut()) {
+            (Some(stack), Some(held)) if stack.stacks_with(held) => {
+                stack.merge(cursor, u8::MAX);
+            }
+            _ => std::mem::swap(slot, cursor),
+        }
+
+        Ok(())
+    }
+
+    /// Picks up half of the stack in `slot`, rounded up, if nothing is held, otherwise puts down a single item there.
+    /// A stack of a different item is swapped with the held one.
+    pub fn right_click(&mut self, cursor: &mut Option<ItemStack>, slot: usize) -> anyhow::Result<()> {
+        let slot = self.slot_mut(slot)?;
+
+        match (slot.as_mut(), cursor.as_mut()) {
+            (Some(stack), None) => {
+                let half = stack.count().div_ceil(2);
+                *cursor = ItemStack::take(slot, half);
+            }
+            (None, Some(_)) => {
+                *slot = ItemStack::take(cursor, 1);
+            }
+            (Some(stack), Some(held)) if stack.stacks_with(held) => {
+                stack.merge(cursor, 1);
+            }
+            _ => std::mem::swap(slot, cursor),
+        }
+
+        Ok(())
+    }
+}
+
+/// The inventory every player carries around. The first slots are the hotbar, one of which is selected and held in
+/// the player's hand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerInventory {
+    inventory: Inventory,
+    selected: u8,
+}
+
+impl PlayerInventory {
+    pub const SIZE: usize = 36;
+    pub const HOTBAR_SIZE: usize = 9;
+    /// The window id of a player's own inventory, which is always open.
+    pub const WINDOW: u8 = 0;
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    pub fn inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
+
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.inventory.slots()[..Self::HOTBAR_SIZE]
+    }
+
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    pub fn select(&mut self, slot: u8) -> anyhow::Result<()> {
+        if slot as usize >= Self::HOTBAR_SIZE {
+            return Err(ItemError::InvalidHotbarSlot(slot).into());
+        }
+
+        self.selected = slot;
+
+        Ok(())
+    }
+
+    /// What the player holds in its hand.
+    pub fn selected_stack(&self) -> Option<&ItemStack> {
+        self.inventory.get(self.selected as usize)
+    }
+
+    pub fn selected_slot_mut(&mut self) -> &mut Option<ItemStack> {
+        &mut self.inventory.slots[self.selected as usize]
+    }
+
+    pub fn click(&mut self, cursor: &mut Option<ItemStack>, slot: usize, kind: ClickKind) -> anyhow::Result<()> {
+        match kind {
+            ClickKind::Left => self.inventory.left_click(cursor, slot),
+            ClickKind::Right => self.inventory.right_click(cursor, slot),
+            ClickKind::QuickMove => self.quick_move(slot),
+        }
+    }
+
+    /// Moves a stack from the hotbar into the rest of the inventory, or the other way around. Whatever doesn't fit
+    /// stays where it was.
+    fn quick_move(&mut self, slot: usize) -> anyhow::Result<()> {
+        let Some(stack) = self.inventory.set(slot, None)? else {
+            return Ok(());
+        };
+
+        let target = if slot < Self::HOTBAR_SIZE { Self::HOTBAR_SIZE..Self::SIZE } else { 0..Self::HOTBAR_SIZE };
+        let remaining = self.inventory.insert_into(stack, target);
+        self.inventory.set(slot, remaining)?;
+
+        Ok(())
+    }
+}
+
+impl Default for PlayerInventory {
+    fn default() -> Self {
+        Self { inventory: Inventory::new(Self::SIZE), selected: 0 }
+    }
+}
+
+/// What a click on a slot does, see `Inventory::left_click` and `Inventory::right_click`. Quick moves take a stack
+/// to the other part of the inventory without picking it up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickKind {
+    Left,
+    Right,
+    QuickMove,
+}
+
+/// A click a client made in one of its windows. The server applies it to its own copy and sends back what changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryClick {
+    pub window: u8,
+    pub slot: u16,
+    pub kind: ClickKind,
+}
+
+impl Packetable for InventoryClick {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u8(self.window)?;
+        buffer.write_u16(self.slot)?;
+
+        buffer.write_u8(match self.kind {
+            ClickKind::Left => 0,
+            ClickKind::Right => 1,
+            ClickKind::QuickMove => 2,
+        })?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        let window = reader.next_byte()?;
+        let slot = reader.next_u16()?;
+
+        let kind = match reader.next_byte()? {
+            0 => ClickKind::Left,
+            1 => ClickKind::Right,
+            2 => ClickKind::QuickMove,
+            other => {
+                return Err(ItemError::InvalidClick(other).into());
+            }
+        };
+
+        Ok(Self { window, slot, kind })
+    }
+}
+
+impl FixedSizePacketable for InventoryClick {
+    const SIZE_IN_BYTES: usize = 1 + 2 + 1;
+}
+
+/// Every slot of a window, and what the client holds, sent whenever the client might be out of sync.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowItems {
+    pub window: u8,
+    pub slots: Vec<Option<ItemStack>>,
+    pub cursor: Option<ItemStack>,
+}
+
+impl Packetable for WindowItems {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u8(self.window)?;
+        buffer.write_u16(self.slots.len() as u16)?;
+
+        for slot in self.slots {
+            write_slot(buffer, slot)?;
+        }
+
+        write_slot(buffer, self.cursor)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        let window = reader.next_byte()?;
+        let count = reader.next_u16()?;
+        let slots = (0..count).map(|_| read_slot(reader)).collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { window, slots, cursor: read_slot(reader)? })
+    }
+}
+
+impl DynamicSizePacketable for WindowItems {
+    fn size_in_bytes(&self) -> usize {
+        1 + 2 + self.slots.iter().map(slot_size).sum::<usize>() + slot_size(&self.cursor)
+    }
+}
+
+/// A single slot of a window that changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotUpdate {
+    pub window: u8,
+    pub slot: u16, //`CURSOR` for what the client holds
+    pub stack: Option<ItemStack>,
+}
+
+impl SlotUpdate {
+    pub const CURSOR: u16 = u16::MAX;
+}
+
+impl Packetable for SlotUpdate {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u8(self.window)?;
+        buffer.write_u16(self.slot)?;
+        write_slot(buffer, self.stack)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
+        Ok(Self { window: reader.next_byte()?, slot: reader.next_u16()?, stack: read_slot(reader)? })
+    }
+}
+
+impl DynamicSizePacketable for SlotUpdate {
+    fn size_in_bytes(&self) -> usize {
+        1 + 2 + slot_size(&self.stack)
+    }
+}
@@ -0,0 +1,134 @@
+pub mod stack;
+pub mod inventory;
+
+use anyhow::ensure;
+use metrohash::MetroHashMap;
+use once_cell::sync::OnceCell;
+use serde_derive::Deserialize;
+
+use crate::{ block::{ registry::BlockRegistry, Block, BlockId }, error::item::ItemError };
+
+/// Identifies an item within the registry it came from. Items are saved and sent by name, so ids never leave it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemId(pub u16);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemDefinition {
+    pub name: String,
+    #[serde(default = "ItemDefinition::default_max_stack")]
+    pub max_stack: u8,
+}
+
+impl ItemDefinition {
+    fn default_max_stack() -> u8 {
+        64
+    }
+}
+
+#[derive(Deserialize)]
+struct DefinitionsFile {
+    #[serde(rename = "item")]
+    items: Vec<ItemDefinition>,
+}
+
+#[derive(Debug)]
+pub struct ItemType {
+    id: ItemId,
+    name: String,
+    max_stack: u8,
+    block: Option<BlockId>, //What the item places
+}
+
+impl ItemType {
+    pub fn id(&self) -> ItemId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How many of the item fit into one slot.
+    pub fn max_stack(&self) -> u8 {
+        self.max_stack
+    }
+
+    pub fn block(&self) -> Option<BlockId> {
+        self.block
+    }
+}
+
+static REGISTRY: OnceCell<ItemRegistry> = OnceCell::new();
+
+/// All items known to the game: one for every block, then the ones from the definitions file.
+#[derive(Debug)]
+pub struct ItemRegistry {
+    types: Vec<ItemType>,
+    by_name: MetroHashMap<String, ItemId>,
+    by_block: MetroHashMap<u16, ItemId>, //By block id, without the state
+}
+
+impl ItemRegistry {
+    pub const BUILTIN_DEFINITIONS: &'static str = include_str!("../../assets/items.toml");
+    pub const DEFAULT_MAX_STACK: u8 = 64;
+
+    /// Every block of `blocks` but air becomes an item placing its default state, numbered by block id. The items in
+    /// `definitions` come after them, in file order.
+    pub fn load(definitions: &str, blocks: &BlockRegistry) -> anyhow::Result<Self> {
+        let file: DefinitionsFile = toml::from_str(definitions)?;
+
+        let mut registry = Self {
+            types: Vec::new(),
+            by_name: MetroHashMap::default(),
+            by_block: MetroHashMap::default(),
+        };
+
+        for block_type in blocks.types().filter(|block_type| block_type.name() != Block::AIR) {
+            let id = registry.add(block_type.name(), Self::DEFAULT_MAX_STACK, Some(block_type.default_state()))?;
+            registry.by_block.insert(block_type.id(), id);
+        }
+
+        for definition in file.items {
+            registry.add(&definition.name, definition.max_stack, None)?;
+        }
+
+        Ok(registry)
+    }
+
+    fn add(&mut self, name: &str, max_stack: u8, block: Option<BlockId>) -> anyhow::Result<ItemId> {
+        ensure!(!self.by_name.contains_key(name), ItemError::DuplicateItem(name.to_string()));
+
+        let id = ItemId(self.types.len() as u16);
+        self.types.push(ItemType { id, name: name.to_string(), max_stack: max_stack.max(1), block });
+        self.by_name.insert(name.to_string(), id);
+
+        Ok(id)
+    }
+
+    /// The items shipped with the game, for the installed block registry. Built on first use, so blocks have to be
+    /// installed before anything touches items.
+    pub fn global() -> anyhow::Result<&'static Self> {
+        REGISTRY.get_or_try_init(|| {
+            Ok(Self::load(Self::BUILTIN_DEFINITIONS, BlockRegistry::global()?).expect(
+                "The builtin item definitions are valid."
+            ))
+        })
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&ItemType> {
+        self.types.get(id.0 as usize)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&ItemType> {
+        self.by_name.get(name).and_then(|id| self.get(*id))
+    }
+
+    /// The item that places `block`, in any of its states.
+    pub fn of_block(&self, block: BlockId) -> Option<&ItemType> {
+        self.by_block.get(&(block.0 >> 8)).and_then(|id| self.get(*id))
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = &ItemType> {
+        self.types.iter()
+    }
+}
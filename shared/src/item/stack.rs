@@ -0,0 +1,175 @@
+use std::io::{ Write, BufWriter };
+
+use crate::{
+    cbs::{ Packetable, DynamicSizePacketable, PacketBuf, WriteExt },
+    error::item::ItemError,
+    tag::Compound,
+};
+
+use super::{ ItemId, ItemRegistry, ItemType };
+
+/// Some amount of a single item, together with data that sets it apart from other stacks of that item, e.g. a custom
+/// name. Only stacks with the same data can be merged. Stacks are never empty, an empty slot holds `None` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    item: ItemId,
+    count: u8,
+    tag: Compound,
+}
+
+impl ItemStack {
+    pub fn new(item: ItemId, count: u8) -> Self {
+        Self { item, count: count.max(1), tag: Compound::new() }
+    }
+
+    /// A stack of the item named `name` from the global registry.
+    pub fn of(name: &str, count: u8) -> anyhow::Result<Self> {
+        let item = ItemRegistry::global()?.get_by_name(name).ok_or_else(|| ItemError::UnknownItem(name.to_string()))?;
+
+        Ok(Self::new(item.id(), count))
+    }
+
+    pub fn with_tag(self, tag: Compound) -> Self {
+        Self { tag, ..self }
+    }
+
+    pub fn with_count(self, count: u8) -> Self {
+        Self { count: count.max(1), ..self }
+    }
+
+    pub fn item(&self) -> ItemId {
+        self.item
+    }
+
+    pub fn item_type(&self) -> anyhow::Result<&'static ItemType> {
+        Ok(ItemRegistry::global()?.get(self.item).ok_or(ItemError::UnknownItemId(self.item.0))?)
+    }
+
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+
+    /// Counts below one are raised to one; to empty a stack, take it out of its slot.
+    pub fn set_count(&mut self, count: u8) {
+        self.count = count.max(1);
+    }
+
+    pub fn tag(&self) -> &Compound {
+        &self.tag
+    }
+
+    pub fn tag_mut(&mut self) -> &mut Compound {
+        &mut self.tag
+    }
+
+    /// How many items fit into the stack. Items that aren't registered don't stack at all.
+    pub fn max_stack(&self) -> u8 {
+        ItemRegistry::global()
+            .ok()
+            .and_then(|items| items.get(self.item))
+            .map_or(1, ItemType::max_stack)
+    }
+
+    /// How many more items the stack has room for.
+    pub fn space(&self) -> u8 {
+        self.max_stack().saturating_sub(self.count)
+    }
+
+    pub fn stacks_with(&self, other: &ItemStack) -> bool {
+        self.item == other.item && self.tag == other.tag
+    }
+
+    /// Moves up to `limit` items of `other` onto this stack, as far as there's room and the two stack. `other` is
+    /// emptied once nothing is left of it. Returns how many items were moved.
+    pub fn merge(&mut self, other: &mut Option<ItemStack>, limit: u8) -> u8 {
+        let Some(stack) = other else {
+            return 0;
+        };
+
+        if !self.stacks_with(stack) {
+            return 0;
+        }
+
+        let moved = limit.min(stack.count).min(self.space());
+        self.count += moved;
+
+        if moved == stack.count {
+            *other = None;
+        } else {
+            stack.count -= moved;
+        }
+
+        moved
+    }
+
+    /// Takes up to `count` items out of `slot`, emptying it if that's all there is.
+    pub fn take(slot: &mut Option<ItemStack>, count: u8) -> Option<ItemStack> {
+        let stack = slot.as_mut()?;
+
+        if count >= stack.count {
+            return slot.take();
+        }
+
+        stack.count -= count;
+
+        Some(stack.clone().with_count(count))
+    }
+}
+
+impl Packetable for ItemStack {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_string(self.item_type()?.name())?;
+        buffer.write_u8(self.count)?;
+        self.tag.write_to_buffer(buffer)?;
+
+        Ok(())
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        let name = reader.next_string()?;
+        let item = ItemRegistry::global()?.get_by_name(&name).ok_or_else(|| ItemError::UnknownItem(name.clone()))?;
+        let count = reader.next_byte()?;
+
+        if count == 0 {
+            return Err(ItemError::EmptyStack(name).into());
+        }
+
+        Ok(Self { item: item.id(), count, tag: Compound::read_from_buf(reader)? })
+    }
+}
+
+impl DynamicSizePacketable for ItemStack {
+    fn size_in_bytes(&self) -> usize {
+        let name = ItemRegistry::global()
+            .ok()
+            .and_then(|items| items.get(self.item))
+            .map_or(0, |item| item.name().len());
+
+        2 + name + 1 + self.tag.size_in_bytes()
+    }
+}
+
+/// Slots are written as a flag for whether they hold anything, followed by the stack.
+pub(crate) fn write_slot<T: Write + Unpin + Send>(
+    buffer: &mut BufWriter<T>,
+    slot: Option<ItemStack>
+) -> anyhow::Result<()> {
+    buffer.write_u8(slot.is_some() as u8)?;
+
+    if let Some(stack) = slot {
+        stack.write_to_buffer(buffer)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn read_slot(reader: &mut PacketBuf) -> anyhow::Result<Option<ItemStack>> {
+    Ok(if reader.next_byte()? != 0 { Some(ItemStack::read_from_buf(reader)?) } else { None })
+}
+
+pub(crate) fn slot_size(slot: &Option<ItemStack>) -> usize {
+    1 + slot.as_ref().map_or(0, ItemStack::size_in_bytes)
+}
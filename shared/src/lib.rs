@@ -1,3 +1,5 @@
+extern crate self as shared; //Lets the derive macros refer to this crate by name from inside of it
+
 pub mod block;
 #[macro_use]
 pub mod util;
@@ -5,10 +7,66 @@ pub mod dimension;
 pub mod net;
 pub mod error;
 pub mod cbs;
+pub mod tag;
+pub mod entity;
+pub mod item;
 
 #[cfg(test)]
 mod test {
-    use crate::util::{ block_pos::BlockPos };
+    use std::io::BufWriter;
+
+    use glam::{ Vec2, Vec3 };
+
+    use metrohash::MetroHashMap;
+
+    use crate::{
+        util::{ block_pos::BlockPos, chunk_pos::ChunkPos, direction::Direction, aabb::Aabb },
+        dimension::{
+            chunk::Chunk,
+            height::WorldHeight,
+            subchunk::SubChunk,
+            id::DimensionId,
+            settings::DimensionSettings,
+            storage::ChunkStorage,
+        },
+        cbs::{ Packetable, PacketBuf, DynamicSizePacketable },
+        block::{
+            BlockId,
+            default_handlers,
+            simple::GrassState,
+            registry::{ BlockRegistry, BlockIdMapping },
+            property::Property,
+            state::State,
+            palette::{ BlockPalette, BlockRemapper },
+            shape::BlockShape,
+            orientable::{ SlabState, SlabType },
+            fluid::WaterState,
+        },
+        tag::{ Tag, Compound, snbt, to_tag, from_tag },
+        net::{
+            packet::{ Packet, PacketDirection, PacketReader, PacketSource },
+            packet_data::{ PacketData, PacketType },
+        },
+        entity::{
+            id::EntityId,
+            movement::EntityMovement,
+            spawn::EntitySpawn,
+            player::{ self, PlayerMove, PlayerTeleport, PlaceBlock, PLAYER_SIZE },
+            physics::{ self, Body, PhysicsSettings, Collisions },
+            pathfinding::{ self, PathSettings, PathFollower },
+        },
+        item::{
+            ItemRegistry,
+            stack::ItemStack,
+            inventory::{ ClickKind, Inventory, InventoryClick, PlayerInventory, SlotUpdate, WindowItems },
+        },
+    };
+
+    /// Tests run in the same process, so any of them may be first to touch blocks. Installing the same ids again
+    /// does nothing.
+    fn install_blocks() {
+        BlockRegistry::builtin().install().unwrap();
+    }
 
     #[test]
     pub fn test_block_pos() {
@@ -18,4 +76,957 @@ mod test {
 
         assert_eq!(pos, new_pos);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_negative_block_pos() {
+        let pos = BlockPos::new(-32, -64, -1);
+        let new_pos = BlockPos::from_long(pos.as_long());
+
+        assert_eq!(pos, new_pos);
+        assert_eq!(pos.get_section(), -4);
+    }
+
+    #[test]
+    pub fn test_chunk_round_trip() {
+        let height = WorldHeight::new(-64, 24).unwrap();
+        let mut chunk = Chunk::empty(height);
+
+        let mut sub_chunk = SubChunk::default();
+        sub_chunk.data[(15 << 8) | (3 << 4) | 2] = 7;
+        chunk.non_air_sub_chunks.insert(-1, sub_chunk);
+        chunk.non_air_sub_chunks.insert(19, SubChunk::default());
+
+        let size = chunk.size_in_bytes();
+        let mut writer = BufWriter::new(Vec::new());
+        chunk.write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), size);
+
+        let read = Chunk::read_from_buf(&mut PacketBuf::new(bytes.into_boxed_slice())).unwrap();
+
+        assert_eq!(read.height(), height);
+        assert_eq!(read.get_block(BlockPos::new(2, -1, 3)).unwrap().0, 7);
+        assert_eq!(read.get_block(BlockPos::new(2, 15, 3)).unwrap().0, BlockId::default().0);
+        assert!(read.get_block(BlockPos::new(0, -65, 0)).is_err());
+        assert!(read.get_block(BlockPos::new(0, 320, 0)).is_err());
+    }
+
+    #[test]
+    pub fn test_change_dimension_round_trip() {
+        let data = PacketData::ChangeDimension(DimensionId::NETHER, DimensionSettings::nether());
+
+        let mut writer = BufWriter::new(Vec::new());
+        data.write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), PacketType::ChangeDimension.get_required_buffer_size(None));
+
+        let read = PacketData::read_data(
+            PacketType::ChangeDimension,
+            &mut PacketBuf::new(bytes.into_boxed_slice())
+        ).unwrap();
+
+        match read {
+            PacketData::ChangeDimension(id, settings) => {
+                assert_eq!(id, DimensionId::NETHER);
+                assert_eq!(settings, DimensionSettings::nether());
+            }
+            other => panic!("Expected a ChangeDimension packet, got {:?}", other),
+        }
+    }
+
+    #[test]
+    pub fn test_block_entity_data_round_trip() {
+        let data = Compound::new()
+            .with("open", 1i8)
+            .with("delay", -20)
+            .with("angle", 0.25f32)
+            .with("text", "Hello")
+            .with("items", Compound::new().with("ids", vec![1i32, 2, 3]));
+        let packet = PacketData::BlockEntityData(DimensionId::END, BlockPos::new(-3, 70, 9), data.clone());
+
+        let size = packet.size_header().unwrap() as usize;
+        let mut writer = BufWriter::new(Vec::new());
+        packet.write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), size);
+
+        let read = PacketData::read_data(
+            PacketType::BlockEntityData,
+            &mut PacketBuf::new(bytes.into_boxed_slice())
+        ).unwrap();
+
+        match read {
+            PacketData::BlockEntityData(id, pos, read) => {
+                assert_eq!((id, pos), (DimensionId::END, BlockPos::new(-3, 70, 9)));
+                assert_eq!(read, data);
+                assert_eq!(read.get_int("delay").unwrap(), -20);
+                assert!(read.get_int("text").is_err());
+            }
+            other => panic!("Expected a BlockEntityData packet, got {:?}", other),
+        }
+    }
+
+    #[test]
+    pub fn test_packet_reader() {
+        //Hands out one piece per read, and would block in between like a socket that has nothing new yet
+        struct Pieces(std::collections::VecDeque<Option<Vec<u8>>>);
+
+        impl std::io::Read for Pieces {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.pop_front() {
+                    Some(Some(piece)) => {
+                        buf[..piece.len()].copy_from_slice(&piece);
+                        Ok(piece.len())
+                    }
+                    Some(None) => Err(std::io::ErrorKind::WouldBlock.into()),
+                    None => Ok(0),
+                }
+            }
+        }
+
+        let mut writer = BufWriter::new(Vec::new());
+        let packets = [
+            PacketData::TeleportConfirm(7),
+            PacketData::DespawnEntities(DimensionId::NETHER, vec![EntityId(1), EntityId(2)]),
+        ];
+
+        for data in packets {
+            Packet::new(PacketDirection::ToServer, data).write_to_buffer(&mut writer).unwrap();
+        }
+
+        let bytes = writer.into_inner().unwrap();
+
+        //Split inside the first packet's type, and inside the second packet's size header
+        let mut stream = Pieces(
+            [Some(bytes[..1].to_vec()), None, Some(bytes[1..9].to_vec()), None, Some(bytes[9..].to_vec()), None]
+                .into_iter()
+                .collect()
+        );
+        let mut reader = PacketReader::default();
+        let source = PacketSource::Server;
+
+        assert!(reader.fill(&mut stream).unwrap());
+        assert!(reader.next_packet(&source).unwrap().is_none());
+
+        assert!(reader.fill(&mut stream).unwrap());
+        let first = reader.next_packet(&source).unwrap().unwrap();
+        assert!(matches!(first.data, PacketData::TeleportConfirm(7)));
+        assert!(reader.next_packet(&source).unwrap().is_none());
+
+        assert!(reader.fill(&mut stream).unwrap());
+        let second = reader.next_packet(&source).unwrap().unwrap();
+        assert!(matches!(second.data, PacketData::DespawnEntities(DimensionId::NETHER, ids) if ids.len() == 2));
+
+        //Only a closed connection ends it
+        assert!(!reader.fill(&mut stream).unwrap());
+
+        //Unknown packet types leave no way to tell where the next packet starts
+        let mut stream = Pieces([Some(vec![255, 255]), None].into_iter().collect());
+        reader.fill(&mut stream).unwrap();
+        assert!(reader.next_packet(&source).is_err());
+    }
+
+    fn packet_round_trip(packet: PacketData) -> PacketData {
+        let packet_type = packet.packet_type();
+        let size = packet
+            .size_header()
+            .map_or_else(|| packet_type.get_required_buffer_size(None), |size| size as usize);
+
+        let mut writer = BufWriter::new(Vec::new());
+        packet.write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), size);
+
+        PacketData::read_data(packet_type, &mut PacketBuf::new(bytes.into_boxed_slice())).unwrap()
+    }
+
+    #[test]
+    pub fn test_entity_packets_round_trip() {
+        let spawn = EntitySpawn {
+            id: EntityId(7),
+            kind: "marker".to_string(),
+            pos: Vec3::new(1.5, -60.0, 300.25),
+            velocity: Vec3::new(0.0, -0.08, 0.1),
+            rotation: Vec2::new(90.0, -15.0),
+        };
+
+        match packet_round_trip(PacketData::SpawnEntity(DimensionId::NETHER, spawn.clone())) {
+            PacketData::SpawnEntity(DimensionId::NETHER, read) => assert_eq!(read, spawn),
+            other => panic!("Expected a SpawnEntity packet, got {:?}", other),
+        }
+
+        let movements = [
+            EntityMovement::Delta(-4096, 0, 32767),
+            EntityMovement::Teleport(Vec3::new(100.0, 64.0, -9.5)),
+        ];
+
+        for movement in movements {
+            match packet_round_trip(PacketData::EntityMove(DimensionId::END, EntityId(3), movement)) {
+                PacketData::EntityMove(DimensionId::END, EntityId(3), read) => assert_eq!(read, movement),
+                other => panic!("Expected an EntityMove packet, got {:?}", other),
+            }
+        }
+
+        match packet_round_trip(PacketData::EntityLook(DimensionId::OVERWORLD, EntityId(1), Vec2::new(-45.0, 30.0))) {
+            PacketData::EntityLook(_, EntityId(1), rotation) => assert_eq!(rotation, Vec2::new(-45.0, 30.0)),
+            other => panic!("Expected an EntityLook packet, got {:?}", other),
+        }
+
+        match packet_round_trip(PacketData::EntityVelocity(DimensionId::OVERWORLD, EntityId(1), Vec3::X)) {
+            PacketData::EntityVelocity(_, EntityId(1), velocity) => assert_eq!(velocity, Vec3::X),
+            other => panic!("Expected an EntityVelocity packet, got {:?}", other),
+        }
+
+        let ids = vec![EntityId(1), EntityId(5), EntityId(9)];
+
+        match packet_round_trip(PacketData::DespawnEntities(DimensionId::OVERWORLD, ids.clone())) {
+            PacketData::DespawnEntities(_, read) => assert_eq!(read, ids),
+            other => panic!("Expected a DespawnEntities packet, got {:?}", other),
+        }
+
+        let movement = PlayerMove {
+            pos: Vec3::new(0.5, 65.0, -2.25),
+            rotation: Vec2::new(180.0, 45.0),
+            on_ground: true,
+        };
+
+        match packet_round_trip(PacketData::PlayerMove(movement)) {
+            PacketData::PlayerMove(read) => assert_eq!(read, movement),
+            other => panic!("Expected a PlayerMove packet, got {:?}", other),
+        }
+
+        let teleport = PlayerTeleport { id: 12, pos: Vec3::new(-8.0, 70.5, 3.0), rotation: Vec2::ZERO };
+
+        match packet_round_trip(PacketData::PlayerTeleport(teleport)) {
+            PacketData::PlayerTeleport(read) => assert_eq!(read, teleport),
+            other => panic!("Expected a PlayerTeleport packet, got {:?}", other),
+        }
+
+        assert!(matches!(packet_round_trip(PacketData::TeleportConfirm(12)), PacketData::TeleportConfirm(12)));
+    }
+
+    #[test]
+    pub fn test_block_interaction() {
+        install_blocks();
+        let pos = BlockPos::new(-3, 64, 1000);
+
+        match packet_round_trip(PacketData::DigStart(pos.clone())) {
+            PacketData::DigStart(read) => assert_eq!(read, pos),
+            other => panic!("Expected a DigStart packet, got {:?}", other),
+        }
+
+        match packet_round_trip(PacketData::DigAbort(pos.clone())) {
+            PacketData::DigAbort(read) => assert_eq!(read, pos),
+            other => panic!("Expected a DigAbort packet, got {:?}", other),
+        }
+
+        match packet_round_trip(PacketData::DigFinish(pos.clone())) {
+            PacketData::DigFinish(read) => assert_eq!(read, pos),
+            other => panic!("Expected a DigFinish packet, got {:?}", other),
+        }
+
+        let place = PlaceBlock { pos: pos.clone(), face: Direction::West, hit: Vec3::new(0.0, 0.75, 0.5) };
+
+        match packet_round_trip(PacketData::PlaceBlock(place.clone())) {
+            PacketData::PlaceBlock(read) => assert_eq!(read, place),
+            other => panic!("Expected a PlaceBlock packet, got {:?}", other),
+        }
+
+        let mut writer = BufWriter::new(Vec::new());
+        PacketData::PlaceBlock(place).write_to_buffer(&mut writer).unwrap();
+        let mut bytes = writer.into_inner().unwrap();
+        bytes[8] = 6;
+        let invalid = PacketData::read_data(PacketType::PlaceBlock, &mut PacketBuf::new(bytes.into_boxed_slice()));
+        assert!(invalid.is_err());
+
+        //Yaw turns from south towards west, pitch looks down
+        assert!(player::look_direction(Vec2::ZERO).abs_diff_eq(Vec3::Z, 1.0e-6));
+        assert!(player::look_direction(Vec2::new(90.0, 0.0)).abs_diff_eq(Vec3::NEG_X, 1.0e-6));
+        assert!(player::look_direction(Vec2::new(0.0, 90.0)).abs_diff_eq(Vec3::NEG_Y, 1.0e-6));
+
+        let registry = BlockRegistry::global().unwrap();
+        let ticks = |name| player::break_ticks(registry.get_by_name(name).unwrap().default_state().resolve().unwrap());
+        assert_eq!(ticks("dirt"), Some(15));
+        assert_eq!(ticks("obsidian"), Some(1500));
+        assert_eq!(ticks("water"), None);
+        assert_eq!(ticks("air"), None);
+    }
+
+    #[test]
+    pub fn test_entity_movement() {
+        let from = Vec3::new(10.0, 64.0, -3.0);
+
+        //Small moves become deltas that land within half a unit of the target
+        let to = from + Vec3::new(0.3, -1.25, 7.9);
+        let movement = EntityMovement::between(from, to);
+        assert!(matches!(movement, EntityMovement::Delta(..)));
+        assert!((movement.apply(from) - to).abs().max_element() <= 0.5 / EntityMovement::UNITS_PER_BLOCK);
+
+        //Going 8 blocks or more along any axis doesn't fit, so the entity is teleported there
+        let to = from + Vec3::new(0.0, 0.0, -8.5);
+        assert_eq!(EntityMovement::between(from, to), EntityMovement::Teleport(to));
+        assert_eq!(EntityMovement::between(from, to).apply(from), to);
+    }
+
+    #[test]
+    pub fn test_tag_round_trip() {
+        let compound = Compound::new()
+            .with("byte", -3i8)
+            .with("short", 300i16)
+            .with("long", i64::MIN)
+            .with("double", 0.1)
+            .with("weird key", "quote \" and \\ backslash")
+            .with("list", Tag::list(vec![Tag::from(1.5f32), Tag::from(-2.0f32)]).unwrap())
+            .with("empty", Tag::List(Vec::new()))
+            .with("bytes", vec![1i8, -1])
+            .with("longs", vec![5i64])
+            .with("nested", Compound::new().with("deeper", Compound::new()));
+
+        let size = compound.size_in_bytes();
+        let mut writer = BufWriter::new(Vec::new());
+        compound.clone().write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), size);
+        assert_eq!(Compound::read_from_buf(&mut PacketBuf::new(bytes.into_boxed_slice())).unwrap(), compound);
+
+        let text = compound.to_string();
+        assert_eq!(snbt::parse_compound(&text).unwrap(), compound);
+        assert_eq!(
+            snbt::parse("{ a: [I; 1, 2], b: true, c: hello, 'd': 2.5e1 }").unwrap().to_string(),
+            "{a:[I;1,2],b:1b,c:\"hello\",d:25.0d}"
+        );
+
+        assert!(Tag::list(vec![Tag::Int(1), Tag::Long(1)]).is_err());
+        let mixed = Compound::new().with("list", Tag::List(vec![Tag::Int(1), Tag::from("two")]));
+        assert!(mixed.write_to_buffer(&mut BufWriter::new(Vec::new())).is_err());
+        assert!(snbt::parse("[1, 2L]").is_err());
+        assert!(snbt::parse("{a:1").is_err());
+
+        //Nesting too deep is refused instead of overflowing the stack
+        let bytes = [vec![8u8, 0, 0], [8u8, 1, 0, 0, 0].repeat(Tag::MAX_DEPTH + 2)].concat();
+        let error = Compound::read_from_buf(&mut PacketBuf::new(bytes.into_boxed_slice())).unwrap_err();
+        assert!(error.to_string().contains("nested"));
+    }
+
+    #[test]
+    pub fn test_tag_serde() {
+        #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+        enum Mode {
+            Survival,
+            Flying { speed: f32 },
+            Riding(u32),
+        }
+
+        #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+        struct Player {
+            name: String,
+            health: u8,
+            pos: (f64, f64, f64),
+            inventory: Vec<u16>,
+            spawn: Option<i32>,
+            modes: Vec<Mode>,
+            stats: std::collections::BTreeMap<u32, bool>,
+        }
+
+        let player = Player {
+            name: "Steve".to_string(),
+            health: 200,
+            pos: (0.5, 64.0, -12.25),
+            inventory: vec![],
+            spawn: None,
+            modes: vec![Mode::Survival, Mode::Flying { speed: 0.1 }, Mode::Riding(7)],
+            stats: [(3, true), (40, false)].into_iter().collect(),
+        };
+
+        let tag = to_tag(&player).unwrap();
+        let Tag::Compound(compound) = &tag else {
+            panic!("Expected a compound, got {}", tag);
+        };
+        assert_eq!(compound.get_short("health").unwrap(), 200);
+        assert!(!compound.contains("spawn"));
+        assert!(compound.get_compound("stats").unwrap().get_byte("40").is_ok());
+
+        //Variants without data are compounds too, so they make up a list of one type with the others
+        assert_eq!(to_tag(&Mode::Survival).unwrap(), Tag::Compound(Compound::new().with("Survival", Compound::new())));
+        assert_eq!(from_tag::<Vec<Mode>>(to_tag(&player.modes).unwrap()).unwrap(), player.modes);
+
+        assert_eq!(from_tag::<Player>(snbt::parse(&tag.to_string()).unwrap()).unwrap(), player);
+        assert!(from_tag::<Player>(Tag::Int(1)).is_err());
+    }
+
+    #[test]
+    pub fn test_block_ids_are_stable() {
+        let first = r#"
+            [[block]]
+            name = "air"
+            [[block]]
+            name = "grass"
+            properties = [{ name = "snowy", type = "bool" }]
+            default_state = { snowy = "true" }
+        "#;
+        let reordered = r#"
+            [[block]]
+            name = "stone"
+            [[block]]
+            name = "grass"
+            properties = [{ name = "snowy", type = "bool" }]
+            [[block]]
+            name = "air"
+        "#;
+
+        let mut mapping = BlockIdMapping::default();
+        let registry = BlockRegistry::load(first, &mut mapping, &default_handlers()).unwrap();
+        assert_eq!(registry.get_by_name("air").unwrap().id(), 0);
+        assert_eq!(registry.get_by_name("grass").unwrap().default_state().0, (1 << 8) | 1);
+
+        let mut mapping = BlockIdMapping::parse(&mapping.serialize().unwrap()).unwrap();
+        let registry = BlockRegistry::load(reordered, &mut mapping, &default_handlers()).unwrap();
+        assert_eq!(registry.get_by_name("air").unwrap().id(), 0);
+        assert_eq!(registry.get_by_name("grass").unwrap().id(), 1);
+        assert_eq!(registry.get_by_name("stone").unwrap().id(), 2);
+    }
+
+    #[test]
+    pub fn test_resolve_typed_block() {
+        install_blocks();
+        let id = BlockId::of(&GrassState::SNOWY).unwrap();
+        let block = id.resolve().unwrap();
+
+        assert_eq!(block.name(), "grass");
+        assert_eq!(block.to_id().0, id.0);
+        assert!(BlockId(0xff00).resolve().is_err());
+        assert!(BlockId(id.0 | 0xff).resolve().is_err());
+
+        //Lookups hand out the same shared block on every thread
+        let address = block as *const _ as usize;
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(move || id.resolve().unwrap() as *const _ as usize))
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), address);
+        }
+    }
+
+    #[test]
+    pub fn test_block_properties() {
+        install_blocks();
+        let normal = BlockId::of(&GrassState::NORMAL).unwrap();
+        let snowy = normal.with(&GrassState::SNOWY_PROPERTY, true).unwrap();
+
+        assert_eq!(snowy.0, BlockId::of(&GrassState::SNOWY).unwrap().0);
+        assert_eq!(snowy.get(&GrassState::SNOWY_PROPERTY), Some(true));
+        assert_eq!(normal.get(&GrassState::SNOWY_PROPERTY), Some(false));
+        assert!(GrassState::from_id(snowy.0 as u8).unwrap().is_snowy());
+        assert!(GrassState::from_id(2).is_err());
+
+        //Air has no such property, and a property of the wrong type doesn't match either
+        assert_eq!(BlockId(0).get(&GrassState::SNOWY_PROPERTY), None);
+        assert_eq!(normal.get(&Property::<u8>::ranged("snowy", 0, 1)), None);
+    }
+
+    #[test]
+    pub fn test_palette_remapping() {
+        let old = r#"
+            [[block]]
+            name = "air"
+            [[block]]
+            name = "stone"
+            [[block]]
+            name = "log"
+            properties = [{ name = "axis", type = "enum", values = ["x", "y", "z"] }]
+        "#;
+        let new = r#"
+            fallback = "dirt"
+            [[block]]
+            name = "air"
+            [[block]]
+            name = "log"
+            properties = [
+                { name = "stripped", type = "bool" },
+                { name = "axis", type = "enum", values = ["x", "y", "z"] },
+            ]
+            [[block]]
+            name = "dirt"
+        "#;
+
+        let old = BlockRegistry::load(old, &mut BlockIdMapping::default(), &default_handlers()).unwrap();
+        let new = BlockRegistry::load(new, &mut BlockIdMapping::default(), &default_handlers()).unwrap();
+
+        let stone = old.parse_state("stone").unwrap();
+        let log = old.parse_state("log[axis=z]").unwrap();
+        assert_eq!(old.state_name(log).unwrap(), "log[axis=z]");
+
+        let mut chunk = Chunk::empty(WorldHeight::DEFAULT);
+        chunk.set_block(BlockPos::new(0, 0, 0), stone).unwrap();
+        chunk.set_block(BlockPos::new(1, 0, 0), log).unwrap();
+
+        //The palette goes over the wire like it would at login
+        let mut writer = BufWriter::new(Vec::new());
+        old.palette().clone().write_to_buffer(&mut writer).unwrap();
+        let bytes = writer.into_inner().unwrap();
+        assert_eq!(bytes.len(), old.palette().size_in_bytes());
+
+        let palette = BlockPalette::read_from_buf(&mut PacketBuf::new(bytes.into_boxed_slice())).unwrap();
+        assert_eq!(&palette, old.palette());
+
+        let remapper = BlockRemapper::new(&palette, &new);
+        assert_eq!(remapper.missing(), ["stone"]);
+        chunk.remap(&remapper);
+
+        assert_eq!(chunk.get_block(BlockPos::new(0, 0, 0)).unwrap(), new.parse_state("dirt").unwrap());
+        assert_eq!(
+            new.state_name(chunk.get_block(BlockPos::new(1, 0, 0)).unwrap()).unwrap(),
+            "log[stripped=false,axis=z]"
+        );
+        assert_eq!(chunk.get_block(BlockPos::new(2, 0, 0)).unwrap(), BlockId::default());
+    }
+
+    #[test]
+    pub fn test_block_shapes() {
+        install_blocks();
+        let blocks = BlockRegistry::global().unwrap();
+        let dirt = blocks.get_by_name("dirt").unwrap().default_state().resolve().unwrap();
+        let obsidian = blocks.get_by_name("obsidian").unwrap().default_state().resolve().unwrap();
+        let air = BlockId::default().resolve().unwrap();
+
+        assert!(dirt.collision_shape().is_full());
+        assert!(air.collision_shape().is_empty());
+        assert_eq!(dirt.blast_resistance(), dirt.hardness());
+        assert_eq!(obsidian.blast_resistance(), 1200.0);
+        assert_eq!(air.friction(), 0.6);
+
+        //A stair facing north has its step at the back half towards negative z
+        let stair = BlockShape::stair(Direction::North, false);
+        let pos = BlockPos::new(-1, 4, 2);
+        let boxes: Vec<Aabb> = stair.at(&pos).collect();
+
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes.iter().any(|aabb| aabb.contains(Vec3::new(-0.5, 4.75, 2.25))));
+        assert!(!boxes.iter().any(|aabb| aabb.contains(Vec3::new(-0.5, 4.75, 2.75))));
+        assert!(BlockShape::slab(true).boxes()[0].intersects(&Aabb::new(Vec3::new(0.2, 0.9, 0.2), Vec3::new(0.4, 1.5, 0.4))));
+        assert!(!BlockShape::slab(false).boxes()[0].intersects(&Aabb::UNIT.offset(Vec3::new(0.0, 0.5, 0.0))));
+    }
+
+    /// Chunks put together by hand, with every other chunk being empty.
+    struct TestChunks {
+        chunks: MetroHashMap<u64, Chunk>,
+        empty: Chunk,
+    }
+
+    impl TestChunks {
+        fn new(height: WorldHeight) -> Self {
+            Self { chunks: MetroHashMap::default(), empty: Chunk::empty(height) }
+        }
+
+        fn set_block(&mut self, pos: BlockPos, block: BlockId) {
+            let height = self.empty.height();
+
+            self.chunks
+                .entry(pos.get_chunk().as_long())
+                .or_insert_with(|| Chunk::empty(height))
+                .set_block(pos, block)
+                .unwrap();
+        }
+    }
+
+    impl ChunkStorage for TestChunks {
+        fn is_chunk_cached(&self, pos: &ChunkPos) -> bool {
+            self.chunks.contains_key(&pos.as_long())
+        }
+
+        fn get_chunk(&mut self, pos: &ChunkPos) -> anyhow::Result<&Chunk> {
+            Ok(self.chunks.get(&pos.as_long()).unwrap_or(&self.empty))
+        }
+    }
+
+    #[test]
+    pub fn test_raycast() {
+        install_blocks();
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let slab = BlockId::of(&SlabState::new(SlabType::Bottom)).unwrap();
+        let mut chunks = TestChunks::new(WorldHeight::new(-16, 2).unwrap());
+
+        chunks.set_block(BlockPos::new(-1, 0, -1), stone);
+        chunks.set_block(BlockPos::new(3, 0, -1), slab);
+        chunks.set_block(BlockPos::new(6, 0, -1), stone);
+
+        //Straight down onto the top of a block in another chunk
+        let hit = chunks.raycast(Vec3::new(-0.5, 5.0, -0.75), Vec3::NEG_Y, 10.0).unwrap().unwrap();
+        assert_eq!((hit.pos, hit.face), (BlockPos::new(-1, 0, -1), Direction::Up));
+        assert_eq!(hit.point, Vec3::new(-0.5, 1.0, -0.75));
+        assert_eq!(hit.distance, 4.0);
+        assert!(chunks.raycast(Vec3::new(-0.5, 5.0, -0.75), Vec3::NEG_Y, 3.9).unwrap().is_none());
+
+        //Above the slab the ray passes on to the block behind it, below it the slab is hit from the side
+        let over = chunks.raycast(Vec3::new(0.5, 0.75, -0.5), Vec3::X, 10.0).unwrap().unwrap();
+        assert_eq!((over.pos, over.face, over.point.x), (BlockPos::new(6, 0, -1), Direction::West, 6.0));
+        let into = chunks.raycast(Vec3::new(0.5, 0.25, -0.5), Vec3::X, 10.0).unwrap().unwrap();
+        assert_eq!((into.pos, into.face, into.point.x), (BlockPos::new(3, 0, -1), Direction::West, 3.0));
+
+        //Diagonally onto the top of the slab
+        let hit = chunks.raycast(Vec3::new(2.0, 2.0, -0.5), Vec3::new(1.0, -1.0, 0.0), 10.0).unwrap().unwrap();
+        assert_eq!((hit.pos, hit.face), (BlockPos::new(3, 0, -1), Direction::Up));
+        assert!((hit.point - Vec3::new(3.5, 0.5, -0.5)).length() < 1e-5);
+
+        //A ray starting inside a block doesn't hit it, and nothing is hit outside of the world
+        let inside = chunks.raycast(Vec3::new(-0.5, 0.5, -0.5), Vec3::NEG_X, 5.0).unwrap();
+        assert!(inside.is_none());
+        assert!(chunks.raycast(Vec3::new(-0.5, 40.0, -0.5), Vec3::Y, 100.0).unwrap().is_none());
+        assert!(chunks.raycast(Vec3::ZERO, Vec3::ZERO, 100.0).unwrap().is_none());
+
+        //Rays that aren't finite don't hit anything instead of never ending
+        let above = Vec3::new(-0.5, 5.0, -0.75);
+        assert!(chunks.raycast(Vec3::new(f32::NAN, 5.0, -0.75), Vec3::NEG_Y, 10.0).unwrap().is_none());
+        assert!(chunks.raycast(above, Vec3::new(0.0, f32::NEG_INFINITY, 0.0), 10.0).unwrap().is_none());
+        assert!(chunks.raycast(above, Vec3::NEG_Y, f32::NAN).unwrap().is_none());
+        assert!(chunks.raycast(above, Vec3::NEG_Y, f32::INFINITY).unwrap().is_none());
+    }
+
+    fn walk(chunks: &mut TestChunks, body: &mut Body, velocity: Vec3, ticks: usize) -> Vec<Collisions> {
+        let settings = PhysicsSettings::default();
+
+        (0..ticks)
+            .map(|_| {
+                body.velocity.x = velocity.x;
+                body.velocity.z = velocity.z;
+                physics::step(body, &settings, chunks).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_physics() {
+        install_blocks();
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let slab = BlockId::of(&SlabState::new(SlabType::Bottom)).unwrap();
+        let mut chunks = TestChunks::new(WorldHeight::new(-16, 2).unwrap());
+
+        for x in -4..8 {
+            for z in -2..3 {
+                chunks.set_block(BlockPos::new(x, 0, z), stone);
+            }
+        }
+
+        for x in 2..7 {
+            chunks.set_block(BlockPos::new(x, 1, 0), slab);
+        }
+
+        chunks.set_block(BlockPos::new(2, 1, 2), stone);
+        chunks.set_block(BlockPos::new(-2, 1, 0), stone);
+        chunks.set_block(BlockPos::new(-2, 2, 0), stone);
+
+        let start = Body { pos: Vec3::new(0.5, 5.0, 0.5), velocity: Vec3::ZERO, size: PLAYER_SIZE, on_ground: false };
+
+        //Falling until landing on the floor, which stops the fall
+        let mut body = start;
+        let collisions = walk(&mut chunks, &mut body, Vec3::ZERO, 40);
+        assert!(collisions.iter().take(5).all(|collisions| *collisions == Collisions::default()));
+        assert!(collisions.last().unwrap().on_ground && collisions.last().unwrap().vertical);
+        assert!((body.pos.y - 1.0).abs() < 1.0e-5);
+        assert!(body.on_ground);
+
+        //Slabs are stepped onto, full blocks aren't
+        let mut onto_slab = body;
+        walk(&mut chunks, &mut onto_slab, Vec3::new(0.2, 0.0, 0.0), 20);
+        assert!((onto_slab.pos.y - 1.5).abs() < 1.0e-5);
+        assert!(onto_slab.pos.x > 3.0);
+
+        let mut onto_block = Body { pos: Vec3::new(0.5, 1.0, 2.5), ..body };
+        let collisions = walk(&mut chunks, &mut onto_block, Vec3::new(0.2, 0.0, 0.0), 20);
+        assert!((onto_block.pos.y - 1.0).abs() < 1.0e-5);
+        assert!((onto_block.pos.x - (2.0 - PLAYER_SIZE.x / 2.0)).abs() < 1.0e-5);
+        assert!(collisions.last().unwrap().horizontal);
+
+        //Walls stop the body and its velocity along that axis, but it keeps sliding along them
+        let mut against_wall = body;
+        against_wall.velocity = Vec3::new(-0.5, 0.0, 0.1);
+        let settings = PhysicsSettings::default();
+        let collisions = (0..10)
+            .map(|_| physics::step(&mut against_wall, &settings, &mut chunks).unwrap())
+            .find(|collisions| collisions.horizontal)
+            .unwrap();
+        assert!(collisions.on_ground);
+        assert_eq!(against_wall.velocity.x, 0.0);
+        assert!(against_wall.velocity.z > 0.0);
+        assert!((against_wall.pos.x - (-1.0 + PLAYER_SIZE.x / 2.0)).abs() < 1.0e-5);
+
+        //The same start always ends in the same place
+        let mut again = start;
+        walk(&mut chunks, &mut again, Vec3::ZERO, 40);
+        assert_eq!(again, body);
+
+        //Bodies slow down depending on what they stand on, on ice they keep sliding for much longer than on stone
+        let ice = BlockRegistry::global().unwrap().get_by_name("ice").unwrap().default_state();
+
+        for x in -4..8 {
+            chunks.set_block(BlockPos::new(x, 0, -2), ice);
+        }
+
+        let mut slide = |z: f32| {
+            let mut sliding = Body { pos: Vec3::new(-3.5, 1.0, z), velocity: Vec3::new(0.3, 0.0, 0.0), ..body };
+
+            for _ in 0..10 {
+                physics::step(&mut sliding, &settings, &mut chunks).unwrap();
+            }
+
+            assert!(sliding.on_ground);
+            sliding.pos.x + 3.5
+        };
+
+        let (on_ice, on_stone) = (slide(-1.5), slide(1.5));
+        assert!(on_ice > on_stone * 2.0, "slid {on_ice} on ice and {on_stone} on stone");
+    }
+
+    /// A stone floor at y = 0, from -2 to 12 along x and z.
+    fn floor() -> TestChunks {
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let mut chunks = TestChunks::new(WorldHeight::new(-16, 2).unwrap());
+
+        for x in -2..=12 {
+            for z in -2..=12 {
+                chunks.set_block(BlockPos::new(x, 0, z), stone);
+            }
+        }
+
+        chunks
+    }
+
+    #[test]
+    pub fn test_pathfinding() {
+        install_blocks();
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let water = BlockId::of(&WaterState::SOURCE).unwrap();
+        let settings = PathSettings::default();
+        let (start, goal) = (BlockPos::new(0, 1, 0), BlockPos::new(8, 1, 0));
+
+        //Open ground is crossed in a straight line
+        let mut chunks = floor();
+        let path = pathfinding::find_path(&mut chunks, start.clone(), goal.clone(), &settings).unwrap().unwrap();
+        assert!(path.complete);
+        assert_eq!(path.nodes, vec![start.clone(), goal.clone()]);
+
+        //Walls are walked around through the gap, and so is water
+        for x in [3, 6] {
+            for z in -2..=9 {
+                if x == 3 {
+                    chunks.set_block(BlockPos::new(x, 1, z), stone);
+                    chunks.set_block(BlockPos::new(x, 2, z), stone);
+                } else {
+                    chunks.set_block(BlockPos::new(x, 1, z), water);
+                }
+            }
+        }
+
+        let path = pathfinding::find_path(&mut chunks, start.clone(), goal.clone(), &settings).unwrap().unwrap();
+        assert!(path.complete);
+        assert!(path.nodes.len() > 2);
+        assert!(path.nodes.iter().any(|node| node.z() >= 10));
+        assert!(path.nodes.iter().all(|node| node.x() != 6 || node.z() >= 10));
+
+        //Too few nodes to get there, so the path only leads closer
+        let few = PathSettings { node_budget: 10, ..settings };
+        let path = pathfinding::find_path(&mut chunks, start.clone(), goal.clone(), &few).unwrap().unwrap();
+        assert!(!path.complete);
+        assert_ne!(*path.end(), start);
+
+        //One block can be climbed and a few dropped, but not two up
+        let mut chunks = floor();
+        chunks.set_block(BlockPos::new(4, 1, 0), stone);
+        chunks.set_block(BlockPos::new(4, 1, 4), stone);
+        chunks.set_block(BlockPos::new(4, 2, 4), stone);
+
+        let ledge = BlockPos::new(4, 2, 0);
+        let up = pathfinding::find_path(&mut chunks, start.clone(), ledge, &settings).unwrap().unwrap();
+        assert!(up.complete);
+        assert!(up.nodes.contains(&BlockPos::new(3, 1, 0)));
+
+        let (down, tower) = (BlockPos::new(0, 1, 4), BlockPos::new(4, 3, 4));
+        let path = pathfinding::find_path(&mut chunks, tower.clone(), down.clone(), &settings).unwrap().unwrap();
+        assert!(path.complete);
+        let path = pathfinding::find_path(&mut chunks, down, tower, &settings).unwrap().unwrap();
+        assert!(!path.complete);
+
+        //Wide entities don't fit through a gap narrow entities walk straight through
+        let mut chunks = floor();
+
+        for z in -2..=12 {
+            if z != 0 && !(8..=10).contains(&z) {
+                chunks.set_block(BlockPos::new(4, 1, z), stone);
+                chunks.set_block(BlockPos::new(4, 2, z), stone);
+            }
+        }
+
+        let path = pathfinding::find_path(&mut chunks, start.clone(), goal.clone(), &settings).unwrap().unwrap();
+        assert_eq!(path.nodes, vec![start.clone(), goal.clone()]);
+
+        let wide = PathSettings { width: 1.4, ..settings };
+        let path = pathfinding::find_path(&mut chunks, start.clone(), goal.clone(), &wide).unwrap().unwrap();
+        assert!(path.complete);
+        assert!(path.nodes.iter().any(|node| node.z() == 9));
+        assert!(path.nodes.iter().all(|node| node.x() != 4 || node.z() == 9));
+
+        //Nowhere to stand
+        let start = BlockPos::new(0, 5, 0);
+        assert!(pathfinding::find_path(&mut chunks, start, goal, &settings).unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_path_following() {
+        install_blocks();
+        let stone = BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state();
+        let mut chunks = floor();
+
+        for z in -2..=3 {
+            chunks.set_block(BlockPos::new(5, 1, z), stone);
+        }
+
+        chunks.set_block(BlockPos::new(8, 1, 6), stone);
+
+        let (start, goal) = (BlockPos::new(0, 1, 0), BlockPos::new(8, 2, 6));
+        let path = pathfinding::find_path(&mut chunks, start, goal, &PathSettings::default()).unwrap().unwrap();
+        assert!(path.complete);
+
+        let mut follower = PathFollower::new(path, 0.2);
+        let settings = PhysicsSettings::default();
+        let mut body = Body { pos: Vec3::new(0.5, 1.0, 0.5), velocity: Vec3::ZERO, size: PLAYER_SIZE, on_ground: true };
+
+        for _ in 0..400 {
+            let Some(velocity) = follower.steer(&body) else {
+                break;
+            };
+
+            body.velocity = velocity;
+            physics::step(&mut body, &settings, &mut chunks).unwrap();
+        }
+
+        assert!(follower.is_finished());
+        assert!((body.pos - Vec3::new(8.5, 2.0, 6.5)).length() < 0.25);
+    }
+
+    #[test]
+    pub fn test_items() {
+        install_blocks();
+        let items = ItemRegistry::global().unwrap();
+
+        //Every block but air can be held
+        let stone = items.get_by_name("stone").unwrap();
+        assert_eq!(stone.block(), Some(BlockRegistry::global().unwrap().get_by_name("stone").unwrap().default_state()));
+        assert_eq!(stone.max_stack(), 64);
+        assert!(items.get_by_name("air").is_none());
+        assert_eq!(items.of_block(BlockId::of(&GrassState::SNOWY).unwrap()).unwrap().name(), "grass");
+
+        let pickaxe = items.get_by_name("wooden_pickaxe").unwrap();
+        assert_eq!(pickaxe.block(), None);
+        assert_eq!(pickaxe.max_stack(), 1);
+        assert!(ItemRegistry::load("[[item]]\nname = \"stone\"", BlockRegistry::global().unwrap()).is_err());
+
+        //Stacks only merge with the same item and data, and only as far as there's room
+        let mut stack = ItemStack::of("stone", 60).unwrap();
+        let mut other = Some(ItemStack::of("stone", 10).unwrap());
+        assert_eq!(stack.merge(&mut other, u8::MAX), 4);
+        assert_eq!((stack.count(), other.as_ref().unwrap().count()), (64, 6));
+
+        let named = ItemStack::of("stone", 1).unwrap().with_tag(Compound::new().with("name", "Rock"));
+        assert!(!named.stacks_with(other.as_ref().unwrap()));
+        assert_eq!(ItemStack::of("dirt", 1).unwrap().merge(&mut other, u8::MAX), 0);
+
+        assert_eq!(ItemStack::take(&mut other, 2).unwrap().count(), 2);
+        assert_eq!(other.as_ref().unwrap().count(), 4);
+        assert_eq!(ItemStack::take(&mut other, 10).unwrap().count(), 4);
+        assert!(other.is_none());
+        assert!(ItemStack::of("nothing", 1).is_err());
+
+        //Full stacks go into the first empty slot, the rest tops up what's already there
+        let mut inventory = Inventory::new(3);
+        inventory.set(1, Some(ItemStack::of("dirt", 60).unwrap())).unwrap();
+        assert!(inventory.insert(ItemStack::of("dirt", 10).unwrap()).is_none());
+        assert_eq!(inventory.get(0).unwrap().count(), 6);
+        assert_eq!(inventory.get(1).unwrap().count(), 64);
+        inventory.insert(named.clone());
+        assert_eq!(inventory.get(2), Some(&named));
+        assert_eq!(inventory.insert(ItemStack::of("coal", 5).unwrap()), Some(ItemStack::of("coal", 5).unwrap()));
+        assert!(inventory.set(3, None).is_err());
+    }
+
+    #[test]
+    pub fn test_inventory_clicks() {
+        install_blocks();
+        let mut inventory = PlayerInventory::default();
+        let mut cursor = None;
+        let dirt = |count| Some(ItemStack::of("dirt", count).unwrap());
+
+        inventory.inventory_mut().set(0, dirt(7)).unwrap();
+        inventory.inventory_mut().set(1, dirt(62)).unwrap();
+
+        //Right picks up half, rounded up, and puts down one at a time
+        inventory.click(&mut cursor, 0, ClickKind::Right).unwrap();
+        assert_eq!((inventory.inventory().get(0).unwrap().count(), cursor.as_ref().unwrap().count()), (3, 4));
+        inventory.click(&mut cursor, 5, ClickKind::Right).unwrap();
+        assert_eq!((inventory.inventory().get(5).unwrap().count(), cursor.as_ref().unwrap().count()), (1, 3));
+
+        //Left puts down what fits and keeps the rest
+        inventory.click(&mut cursor, 1, ClickKind::Left).unwrap();
+        assert_eq!((inventory.inventory().get(1).unwrap().count(), cursor.as_ref().unwrap().count()), (64, 1));
+        inventory.click(&mut cursor, 5, ClickKind::Left).unwrap();
+        assert!(cursor.is_none());
+        assert_eq!(inventory.inventory().get(5).unwrap().count(), 2);
+
+        //Different items are swapped
+        let coal = Some(ItemStack::of("coal", 2).unwrap());
+        cursor = coal.clone();
+        inventory.click(&mut cursor, 0, ClickKind::Left).unwrap();
+        assert_eq!(cursor, dirt(3));
+        assert_eq!(inventory.inventory().get(0), coal.as_ref());
+        assert!(inventory.click(&mut cursor, PlayerInventory::SIZE, ClickKind::Left).is_err());
+
+        //Quick moves go between the hotbar and the rest
+        inventory.click(&mut cursor, 0, ClickKind::QuickMove).unwrap();
+        assert!(inventory.inventory().get(0).is_none());
+        assert_eq!(inventory.inventory().get(PlayerInventory::HOTBAR_SIZE), coal.as_ref());
+        inventory.click(&mut cursor, PlayerInventory::HOTBAR_SIZE, ClickKind::QuickMove).unwrap();
+        assert_eq!(inventory.inventory().get(0), coal.as_ref());
+
+        assert!(inventory.select(PlayerInventory::HOTBAR_SIZE as u8).is_err());
+        inventory.select(5).unwrap();
+        assert_eq!(inventory.selected_stack(), dirt(2).as_ref());
+    }
+
+    #[test]
+    pub fn test_inventory_packets_round_trip() {
+        install_blocks();
+        let stack = ItemStack::of("oak_log", 12).unwrap().with_tag(Compound::new().with("name", "Firewood"));
+
+        let items = WindowItems {
+            window: PlayerInventory::WINDOW,
+            slots: vec![Some(stack.clone()), None, Some(ItemStack::of("stick", 1).unwrap())],
+            cursor: Some(stack.clone()),
+        };
+
+        match packet_round_trip(PacketData::WindowItems(items.clone())) {
+            PacketData::WindowItems(read) => assert_eq!(read, items),
+            other => panic!("Expected a WindowItems packet, got {:?}", other),
+        }
+
+        let updates = [
+            SlotUpdate { window: 0, slot: 4, stack: Some(stack) },
+            SlotUpdate { window: 0, slot: SlotUpdate::CURSOR, stack: None },
+        ];
+
+        for update in updates {
+            match packet_round_trip(PacketData::SetSlot(update.clone())) {
+                PacketData::SetSlot(read) => assert_eq!(read, update),
+                other => panic!("Expected a SetSlot packet, got {:?}", other),
+            }
+        }
+
+        let click = InventoryClick { window: 0, slot: 20, kind: ClickKind::QuickMove };
+
+        match packet_round_trip(PacketData::ClickSlot(click)) {
+            PacketData::ClickSlot(read) => assert_eq!(read, click),
+            other => panic!("Expected a ClickSlot packet, got {:?}", other),
+        }
+
+        assert!(matches!(packet_round_trip(PacketData::SelectHotbarSlot(8)), PacketData::SelectHotbarSlot(8)));
+    }
+}
@@ -12,6 +12,9 @@
 pub mod packet;
 pub mod packet_data;
 
+/// Where the server listens, and where clients connect to unless they're told otherwise.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:19354";
+
 pub trait NetworkHandler {
     fn enqueue_packet(&self, packet: packet::Packet) -> anyhow::Result<()>;
 
@@ -7,15 +7,14 @@ use crate::error::net::PacketReadError;
 use num_traits::FromPrimitive;
 use num_traits::ToPrimitive;
 
-use std::net::TcpStream;
-
 use uuid::Uuid;
 
 use std::io::BufWriter;
 
 use std::io::Write;
 
-use anyhow::{ anyhow, Ok };
+use anyhow::Ok;
+use log::error;
 
 use crate::cbs::PacketBuf;
 
@@ -69,54 +68,100 @@ pub struct Packet {
 }
 
 impl Packet {
-    pub fn try_from_stream(stream: &mut TcpStream, source: PacketSource) -> anyhow::Result<Option<Packet>> {
-        let mut incoming_type = [0u8; 2];
-
-        match stream.read_exact(&mut incoming_type) {
-            Result::Ok(_) => {
-                let incoming_type = u16::from_le_bytes(incoming_type);
-                let packet_type = <PacketType as FromPrimitive>
-                    ::from_u16(incoming_type)
-                    .ok_or(PacketReadError::InvalidPacketType(incoming_type))?;
-                let mut size = None;
-
-                if packet_type.size_can_vary() {
-                    let mut packet_size_buf = [0u8; 1];
+    pub fn new(direction: PacketDirection, data: PacketData) -> Self {
+        Self { direction, packet_type: data.packet_type(), data }
+    }
 
-                    stream.read_exact(&mut packet_size_buf)?;
+    pub fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        buffer.write_u16(<PacketType as ToPrimitive>::to_u16(&self.packet_type).unwrap())?;
 
-                    size = Some(packet_size_buf[0]);
-                }
+        if let Some(header) = self.data.size_header() {
+            buffer.write_u32(header)?;
+        }
 
-                let size = packet_type.get_required_buffer_size(size);
+        self.data.write_to_buffer(buffer)?;
 
-                let mut data_buffer = vec![0u8; size];
+        Ok(())
+    }
+}
 
-                stream.read_exact(&mut data_buffer[0..])?;
+/// Reads packets from a non-blocking stream. Bytes are kept until a whole packet has arrived, since a packet may be
+/// split across any number of reads.
+#[derive(Debug, Default)]
+pub struct PacketReader {
+    buffer: Vec<u8>,
+}
 
-                let mut buffer = PacketBuf::new(data_buffer.into_boxed_slice());
+impl PacketReader {
+    /// The biggest packet that is accepted, so a broken size header can't make the reader wait for gigabytes.
+    pub const MAX_PACKET_SIZE: usize = 1 << 24;
 
-                let data = PacketData::read_data(packet_type, &mut buffer)?;
+    /// Reads whatever the stream has right now without waiting for more. Returns false once the other side closed the
+    /// connection, and errors if it broke.
+    pub fn fill<R: Read>(&mut self, stream: &mut R) -> anyhow::Result<bool> {
+        let mut chunk = [0u8; 4096];
 
-                Ok(Some(Packet { direction: source.as_direction(), packet_type, data }))
+        loop {
+            match stream.read(&mut chunk) {
+                Result::Ok(0) => {
+                    return Ok(false);
+                }
+                Result::Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok(true);
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => {
+                    return Err(PacketReadError::IOError(e).into());
+                }
             }
-            Err(ref e) if e.kind() == ErrorKind::WouldBlock => { Ok(None) /*No bytes received*/ }
-            Err(e) => { Err(anyhow!(e)) }
         }
     }
 
-    pub fn write_to_buffer<T: Write + Unpin + Send>(
-        self,
-        buffer: &mut BufWriter<T>
-    ) -> anyhow::Result<()> {
-        buffer.write_u16(<PacketType as ToPrimitive>::to_u16(&self.packet_type).unwrap())?;
+    /// The next packet, if it arrived completely. A packet that arrived but can't be read is logged and skipped, the
+    /// ones after it are fine. Errors if it's no longer clear where the next packet starts.
+    pub fn next_packet(&mut self, source: &PacketSource) -> anyhow::Result<Option<Packet>> {
+        loop {
+            let Some(header) = self.buffer.get(..2) else {
+                return Ok(None);
+            };
+
+            let incoming_type = u16::from_le_bytes([header[0], header[1]]);
+            let packet_type = <PacketType as FromPrimitive>
+                ::from_u16(incoming_type)
+                .ok_or(PacketReadError::InvalidPacketType(incoming_type))?;
+
+            let (header_size, size) = if packet_type.size_can_vary() {
+                let Some(size) = self.buffer.get(2..6) else {
+                    return Ok(None);
+                };
+
+                (6, Some(u32::from_le_bytes([size[0], size[1], size[2], size[3]])))
+            } else {
+                (2, None)
+            };
+
+            let size = packet_type.get_required_buffer_size(size);
+
+            if size > Self::MAX_PACKET_SIZE {
+                return Err(PacketReadError::TooLarge(size, Self::MAX_PACKET_SIZE).into());
+            }
 
-        if let Some(header) = self.data.size_header() {
-            buffer.write_u8(header)?;
-        }
+            if self.buffer.len() < header_size + size {
+                return Ok(None);
+            }
 
-        self.data.write_to_buffer(buffer)?;
+            let data: Vec<u8> = self.buffer.drain(..header_size + size).skip(header_size).collect();
 
-        Ok(())
+            match PacketData::read_data(packet_type, &mut PacketBuf::new(data.into_boxed_slice())) {
+                Result::Ok(data) => {
+                    return Ok(Some(Packet { direction: source.clone().as_direction(), packet_type, data }));
+                }
+                Err(e) => error!("Skipping a {:?} packet that can't be read: {}", packet_type, e),
+            }
+        }
     }
-}
\ No newline at end of file
+}
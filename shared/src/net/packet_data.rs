@@ -13,9 +13,22 @@ use crate::cbs::FixedSizePacketable;
 use crate::cbs::PacketBuf;
 use crate::cbs::Packetable;
 use crate::dimension::chunk::Chunk;
+use crate::dimension::id::DimensionId;
+use crate::dimension::settings::DimensionSettings;
 
 
 use crate::block::BlockId;
+use crate::block::palette::BlockPalette;
+use crate::tag::Compound;
+use crate::entity::id::EntityId;
+use crate::entity::movement::EntityMovement;
+use crate::entity::spawn::EntitySpawn;
+use crate::entity::player::{ PlayerMove, PlayerTeleport, PlaceBlock };
+use crate::entity::{ write_vec3, read_vec3, write_vec2, read_vec2 };
+use crate::item::inventory::{ InventoryClick, SlotUpdate, WindowItems };
+use crate::cbs::WriteExt;
+
+use glam::{ Vec2, Vec3 };
 
 use crate::util::block_pos::BlockPos;
 use crate::util::chunk_pos::ChunkPos;
@@ -23,8 +36,30 @@ use crate::util::chunk_pos::ChunkPos;
 #[derive(Debug, Clone)]
 pub enum PacketData {
     Ping,
-    BlockUpdate(BlockPos, BlockId),
-    ChunkData(ChunkPos, Chunk),
+    BlockUpdate(DimensionId, BlockPos, BlockId),
+    ChunkData(DimensionId, ChunkPos, Chunk),
+    ChangeDimension(DimensionId, DimensionSettings),
+    Login,
+    BlockPalette(BlockPalette),
+    BlockEntityData(DimensionId, BlockPos, Compound),
+    SpawnEntity(DimensionId, EntitySpawn),
+    EntityMove(DimensionId, EntityId, EntityMovement),
+    EntityLook(DimensionId, EntityId, Vec2), //Yaw and pitch, in degrees
+    EntityVelocity(DimensionId, EntityId, Vec3),
+    DespawnEntities(DimensionId, Vec<EntityId>),
+    PlayerMove(PlayerMove),
+    PlayerTeleport(PlayerTeleport),
+    TeleportConfirm(u32),
+    WindowItems(WindowItems),
+    SetSlot(SlotUpdate),
+    ClickSlot(InventoryClick),
+    SelectHotbarSlot(u8),
+    DigStart(BlockPos),
+    DigAbort(BlockPos),
+    DigFinish(BlockPos),
+    PlaceBlock(PlaceBlock),
+    RequestChunk(ChunkPos), //Answered with the chunk in the dimension the client's player is in
+    Disconnect, //Also made up by the server's network handler when a connection closes
 }
 
 impl PacketData {
@@ -34,22 +69,107 @@ impl PacketData {
     ) -> anyhow::Result<()> {
         match self {
             PacketData::Ping => {}
-            PacketData::BlockUpdate(pos, block) => {
+            PacketData::BlockUpdate(dimension, pos, block) => {
+                dimension.write_to_buffer(buffer)?;
                 pos.write_to_buffer(buffer)?;
                 block.write_to_buffer(buffer)?;
             }
-            PacketData::ChunkData(pos, chunk) => {
+            PacketData::ChunkData(dimension, pos, chunk) => {
+                dimension.write_to_buffer(buffer)?;
                 pos.write_to_buffer(buffer)?;
                 chunk.write_to_buffer(buffer)?;
             }
+            PacketData::ChangeDimension(dimension, settings) => {
+                dimension.write_to_buffer(buffer)?;
+                settings.write_to_buffer(buffer)?;
+            }
+            PacketData::Login | PacketData::Disconnect => {}
+            PacketData::BlockPalette(palette) => {
+                palette.write_to_buffer(buffer)?;
+            }
+            PacketData::BlockEntityData(dimension, pos, data) => {
+                dimension.write_to_buffer(buffer)?;
+                pos.write_to_buffer(buffer)?;
+                data.write_to_buffer(buffer)?;
+            }
+            PacketData::SpawnEntity(dimension, spawn) => {
+                dimension.write_to_buffer(buffer)?;
+                spawn.write_to_buffer(buffer)?;
+            }
+            PacketData::EntityMove(dimension, id, movement) => {
+                dimension.write_to_buffer(buffer)?;
+                id.write_to_buffer(buffer)?;
+                movement.write_to_buffer(buffer)?;
+            }
+            PacketData::EntityLook(dimension, id, rotation) => {
+                dimension.write_to_buffer(buffer)?;
+                id.write_to_buffer(buffer)?;
+                write_vec2(buffer, rotation)?;
+            }
+            PacketData::EntityVelocity(dimension, id, velocity) => {
+                dimension.write_to_buffer(buffer)?;
+                id.write_to_buffer(buffer)?;
+                write_vec3(buffer, velocity)?;
+            }
+            PacketData::DespawnEntities(dimension, ids) => {
+                dimension.write_to_buffer(buffer)?;
+                buffer.write_u32(ids.len() as u32)?;
+
+                for id in ids {
+                    id.write_to_buffer(buffer)?;
+                }
+            }
+            PacketData::PlayerMove(movement) => {
+                movement.write_to_buffer(buffer)?;
+            }
+            PacketData::PlayerTeleport(teleport) => {
+                teleport.write_to_buffer(buffer)?;
+            }
+            PacketData::TeleportConfirm(id) => {
+                buffer.write_u32(id)?;
+            }
+            PacketData::WindowItems(items) => {
+                items.write_to_buffer(buffer)?;
+            }
+            PacketData::SetSlot(update) => {
+                update.write_to_buffer(buffer)?;
+            }
+            PacketData::ClickSlot(click) => {
+                click.write_to_buffer(buffer)?;
+            }
+            PacketData::SelectHotbarSlot(slot) => {
+                buffer.write_u8(slot)?;
+            }
+            PacketData::DigStart(pos) | PacketData::DigAbort(pos) | PacketData::DigFinish(pos) => {
+                pos.write_to_buffer(buffer)?;
+            }
+            PacketData::PlaceBlock(place) => {
+                place.write_to_buffer(buffer)?;
+            }
+            PacketData::RequestChunk(pos) => {
+                pos.write_to_buffer(buffer)?;
+            }
         }
 
         Ok(())
     }
 
-    pub fn size_header(&self) -> Option<u8> {
+    pub fn size_header(&self) -> Option<u32> {
         match self  {
-            PacketData::ChunkData(_, chunk) => Some(chunk.size_in_units()),
+            PacketData::ChunkData(_, _, chunk) =>
+                Some(
+                    (DimensionId::SIZE_IN_BYTES + ChunkPos::SIZE_IN_BYTES + chunk.size_in_bytes()) as u32
+                ),
+            PacketData::BlockPalette(palette) => Some(palette.size_in_bytes() as u32),
+            PacketData::BlockEntityData(_, _, data) =>
+                Some((DimensionId::SIZE_IN_BYTES + BlockPos::SIZE_IN_BYTES + data.size_in_bytes()) as u32),
+            PacketData::SpawnEntity(_, spawn) => Some((DimensionId::SIZE_IN_BYTES + spawn.size_in_bytes()) as u32),
+            PacketData::EntityMove(_, _, movement) =>
+                Some((DimensionId::SIZE_IN_BYTES + EntityId::SIZE_IN_BYTES + movement.size_in_bytes()) as u32),
+            PacketData::DespawnEntities(_, ids) =>
+                Some((DimensionId::SIZE_IN_BYTES + 4 + ids.len() * EntityId::SIZE_IN_BYTES) as u32),
+            PacketData::WindowItems(items) => Some(items.size_in_bytes() as u32),
+            PacketData::SetSlot(update) => Some(update.size_in_bytes() as u32),
             _ => None
         }
     }
@@ -60,6 +180,7 @@ impl PacketData {
             PacketType::BlockUpdate =>
                 Ok(
                     PacketData::BlockUpdate(
+                        DimensionId::read_from_buf(buf)?,
                         BlockPos::read_from_buf(buf)?,
                         BlockId::read_from_buf(buf)?
                     )
@@ -67,10 +188,104 @@ impl PacketData {
             PacketType::ChunkData =>
                 Ok(
                     PacketData::ChunkData(
+                        DimensionId::read_from_buf(buf)?,
                         ChunkPos::read_from_buf(buf)?,
                         Chunk::read_from_buf(buf)?
                     )
                 ),
+            PacketType::ChangeDimension =>
+                Ok(
+                    PacketData::ChangeDimension(
+                        DimensionId::read_from_buf(buf)?,
+                        DimensionSettings::read_from_buf(buf)?
+                    )
+                ),
+            PacketType::Login => Ok(PacketData::Login),
+            PacketType::BlockPalette => Ok(PacketData::BlockPalette(BlockPalette::read_from_buf(buf)?)),
+            PacketType::BlockEntityData =>
+                Ok(
+                    PacketData::BlockEntityData(
+                        DimensionId::read_from_buf(buf)?,
+                        BlockPos::read_from_buf(buf)?,
+                        Compound::read_from_buf(buf)?
+                    )
+                ),
+            PacketType::SpawnEntity =>
+                Ok(PacketData::SpawnEntity(DimensionId::read_from_buf(buf)?, EntitySpawn::read_from_buf(buf)?)),
+            PacketType::EntityMove =>
+                Ok(
+                    PacketData::EntityMove(
+                        DimensionId::read_from_buf(buf)?,
+                        EntityId::read_from_buf(buf)?,
+                        EntityMovement::read_from_buf(buf)?
+                    )
+                ),
+            PacketType::EntityLook =>
+                Ok(
+                    PacketData::EntityLook(
+                        DimensionId::read_from_buf(buf)?,
+                        EntityId::read_from_buf(buf)?,
+                        read_vec2(buf)?
+                    )
+                ),
+            PacketType::EntityVelocity =>
+                Ok(
+                    PacketData::EntityVelocity(
+                        DimensionId::read_from_buf(buf)?,
+                        EntityId::read_from_buf(buf)?,
+                        read_vec3(buf)?
+                    )
+                ),
+            PacketType::DespawnEntities => {
+                let dimension = DimensionId::read_from_buf(buf)?;
+                let count = buf.next_u32()?;
+                let ids = (0..count).map(|_| EntityId::read_from_buf(buf)).collect::<anyhow::Result<_>>()?;
+
+                Ok(PacketData::DespawnEntities(dimension, ids))
+            }
+            PacketType::PlayerMove => Ok(PacketData::PlayerMove(PlayerMove::read_from_buf(buf)?)),
+            PacketType::PlayerTeleport => Ok(PacketData::PlayerTeleport(PlayerTeleport::read_from_buf(buf)?)),
+            PacketType::TeleportConfirm => Ok(PacketData::TeleportConfirm(buf.next_u32()?)),
+            PacketType::WindowItems => Ok(PacketData::WindowItems(WindowItems::read_from_buf(buf)?)),
+            PacketType::SetSlot => Ok(PacketData::SetSlot(SlotUpdate::read_from_buf(buf)?)),
+            PacketType::ClickSlot => Ok(PacketData::ClickSlot(InventoryClick::read_from_buf(buf)?)),
+            PacketType::SelectHotbarSlot => Ok(PacketData::SelectHotbarSlot(buf.next_byte()?)),
+            PacketType::DigStart => Ok(PacketData::DigStart(BlockPos::read_from_buf(buf)?)),
+            PacketType::DigAbort => Ok(PacketData::DigAbort(BlockPos::read_from_buf(buf)?)),
+            PacketType::DigFinish => Ok(PacketData::DigFinish(BlockPos::read_from_buf(buf)?)),
+            PacketType::PlaceBlock => Ok(PacketData::PlaceBlock(PlaceBlock::read_from_buf(buf)?)),
+            PacketType::RequestChunk => Ok(PacketData::RequestChunk(ChunkPos::read_from_buf(buf)?)),
+            PacketType::Disconnect => Ok(PacketData::Disconnect),
+        }
+    }
+
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            PacketData::Ping => PacketType::Ping,
+            PacketData::BlockUpdate(..) => PacketType::BlockUpdate,
+            PacketData::ChunkData(..) => PacketType::ChunkData,
+            PacketData::ChangeDimension(..) => PacketType::ChangeDimension,
+            PacketData::Login => PacketType::Login,
+            PacketData::BlockPalette(..) => PacketType::BlockPalette,
+            PacketData::BlockEntityData(..) => PacketType::BlockEntityData,
+            PacketData::SpawnEntity(..) => PacketType::SpawnEntity,
+            PacketData::EntityMove(..) => PacketType::EntityMove,
+            PacketData::EntityLook(..) => PacketType::EntityLook,
+            PacketData::EntityVelocity(..) => PacketType::EntityVelocity,
+            PacketData::DespawnEntities(..) => PacketType::DespawnEntities,
+            PacketData::PlayerMove(..) => PacketType::PlayerMove,
+            PacketData::PlayerTeleport(..) => PacketType::PlayerTeleport,
+            PacketData::TeleportConfirm(..) => PacketType::TeleportConfirm,
+            PacketData::WindowItems(..) => PacketType::WindowItems,
+            PacketData::SetSlot(..) => PacketType::SetSlot,
+            PacketData::ClickSlot(..) => PacketType::ClickSlot,
+            PacketData::SelectHotbarSlot(..) => PacketType::SelectHotbarSlot,
+            PacketData::DigStart(..) => PacketType::DigStart,
+            PacketData::DigAbort(..) => PacketType::DigAbort,
+            PacketData::DigFinish(..) => PacketType::DigFinish,
+            PacketData::PlaceBlock(..) => PacketType::PlaceBlock,
+            PacketData::RequestChunk(..) => PacketType::RequestChunk,
+            PacketData::Disconnect => PacketType::Disconnect,
         }
     }
 }
@@ -81,21 +296,73 @@ pub enum PacketType {
     Ping,
     BlockUpdate,
     ChunkData,
+    ChangeDimension,
+    Login,
+    BlockPalette,
+    BlockEntityData,
+    SpawnEntity,
+    EntityMove,
+    EntityLook,
+    EntityVelocity,
+    DespawnEntities,
+    PlayerMove,
+    PlayerTeleport,
+    TeleportConfirm,
+    WindowItems,
+    SetSlot,
+    ClickSlot,
+    SelectHotbarSlot,
+    DigStart,
+    DigAbort,
+    DigFinish,
+    PlaceBlock,
+    RequestChunk,
+    Disconnect,
 }
 
 impl PacketType {
-    pub fn get_required_buffer_size(&self, size_header: Option<u8>) -> usize {
+    pub fn get_required_buffer_size(&self, size_header: Option<u32>) -> usize {
         match self {
-            PacketType::Ping => 1,
-            PacketType::BlockUpdate => BlockPos::SIZE_IN_BYTES + BlockId::SIZE_IN_BYTES,
-            PacketType::ChunkData =>
-                ChunkPos::SIZE_IN_BYTES +
-                    Chunk::size_in_bytes(size_header.expect("This should never happen.")),
+            PacketType::Ping => 0,
+            PacketType::BlockUpdate =>
+                DimensionId::SIZE_IN_BYTES + BlockPos::SIZE_IN_BYTES + BlockId::SIZE_IN_BYTES,
+            PacketType::ChunkData => size_header.expect("This should never happen.") as usize,
+            PacketType::ChangeDimension =>
+                DimensionId::SIZE_IN_BYTES + DimensionSettings::SIZE_IN_BYTES,
+            PacketType::Login => 0,
+            PacketType::BlockPalette => size_header.expect("This should never happen.") as usize,
+            PacketType::BlockEntityData => size_header.expect("This should never happen.") as usize,
+            PacketType::SpawnEntity => size_header.expect("This should never happen.") as usize,
+            PacketType::EntityMove => size_header.expect("This should never happen.") as usize,
+            PacketType::EntityLook => DimensionId::SIZE_IN_BYTES + EntityId::SIZE_IN_BYTES + 2 * 4,
+            PacketType::EntityVelocity => DimensionId::SIZE_IN_BYTES + EntityId::SIZE_IN_BYTES + 3 * 4,
+            PacketType::DespawnEntities => size_header.expect("This should never happen.") as usize,
+            PacketType::PlayerMove => PlayerMove::SIZE_IN_BYTES,
+            PacketType::PlayerTeleport => PlayerTeleport::SIZE_IN_BYTES,
+            PacketType::TeleportConfirm => 4,
+            PacketType::WindowItems => size_header.expect("This should never happen.") as usize,
+            PacketType::SetSlot => size_header.expect("This should never happen.") as usize,
+            PacketType::ClickSlot => InventoryClick::SIZE_IN_BYTES,
+            PacketType::SelectHotbarSlot => 1,
+            PacketType::DigStart | PacketType::DigAbort | PacketType::DigFinish => BlockPos::SIZE_IN_BYTES,
+            PacketType::PlaceBlock => PlaceBlock::SIZE_IN_BYTES,
+            PacketType::RequestChunk => ChunkPos::SIZE_IN_BYTES,
+            PacketType::Disconnect => 0,
         }
     }
 
     pub fn size_can_vary(&self) -> bool {
-        matches!(self, Self::ChunkData)
+        matches!(
+            self,
+            Self::ChunkData |
+                Self::BlockPalette |
+                Self::BlockEntityData |
+                Self::SpawnEntity |
+                Self::EntityMove |
+                Self::DespawnEntities |
+                Self::WindowItems |
+                Self::SetSlot
+        )
     }
 
     
@@ -0,0 +1,247 @@
+use serde::{
+    de::{
+        DeserializeOwned,
+        Deserializer,
+        Visitor,
+        SeqAccess,
+        MapAccess,
+        EnumAccess,
+        VariantAccess,
+        DeserializeSeed,
+        IntoDeserializer,
+        Error,
+    },
+    forward_to_deserialize_any,
+};
+
+use crate::error::tag::TagSerdeError;
+
+use super::Tag;
+
+/// The reverse of `to_tag`. Arrays can be read as sequences, and missing `Option` fields come back as `None`.
+pub fn from_tag<T: DeserializeOwned>(tag: Tag) -> anyhow::Result<T> {
+    Ok(T::deserialize(tag)?)
+}
+
+type Result<T> = std::result::Result<T, TagSerdeError>;
+
+impl<'de> IntoDeserializer<'de, TagSerdeError> for Tag {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Tag {
+    type Error = TagSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::Byte(value) => visitor.visit_i8(value),
+            Self::Short(value) => visitor.visit_i16(value),
+            Self::Int(value) => visitor.visit_i32(value),
+            Self::Long(value) => visitor.visit_i64(value),
+            Self::Float(value) => visitor.visit_f32(value),
+            Self::Double(value) => visitor.visit_f64(value),
+            Self::String(value) => visitor.visit_string(value),
+            Self::List(elements) => visitor.visit_seq(ListAccess(elements.into_iter())),
+            Self::Compound(compound) =>
+                visitor.visit_map(CompoundAccess { tags: compound.into_iter(), value: None }),
+            Self::ByteArray(values) => visitor.visit_seq(ListAccess(values.into_iter().map(Tag::Byte))),
+            Self::IntArray(values) => visitor.visit_seq(ListAccess(values.into_iter().map(Tag::Int))),
+            Self::LongArray(values) => visitor.visit_seq(ListAccess(values.into_iter().map(Tag::Long))),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::Byte(value) => visitor.visit_bool(value != 0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::ByteArray(values) => visitor.visit_byte_buf(values.into_iter().map(|value| value as u8).collect()),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    //Only present values make it into a tag, missing ones are handled by serde itself
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::Compound(compound) if compound.is_empty() => visitor.visit_unit(),
+            other => Err(TagSerdeError::custom(format!("Expected an empty compound, got a {}", other.type_name()))),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value> {
+        match self {
+            Self::String(name) => visitor.visit_enum(name.into_deserializer()),
+            Self::Compound(compound) if compound.len() == 1 => {
+                let (name, value) = compound.into_iter().next().expect("The compound has one entry.");
+                visitor.visit_enum(VariantTag { name, value })
+            }
+            other =>
+                Err(
+                    TagSerdeError::custom(
+                        format!("Expected a string or a compound with one entry, got a {}", other.type_name())
+                    )
+                ),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct ListAccess<I: Iterator<Item = Tag>>(I);
+
+impl<'de, I: Iterator<Item = Tag>> SeqAccess<'de> for ListAccess<I> {
+    type Error = TagSerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        self.0.next().map(|tag| seed.deserialize(tag)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint().1
+    }
+}
+
+struct CompoundAccess<I: Iterator<Item = (String, Tag)>> {
+    tags: I,
+    value: Option<Tag>, //Set between `next_key_seed` and `next_value_seed`
+}
+
+impl<'de, I: Iterator<Item = (String, Tag)>> MapAccess<'de> for CompoundAccess<I> {
+    type Error = TagSerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.tags.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(KeyDeserializer(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self.value.take().ok_or_else(|| TagSerdeError::custom("Value without a key"))?;
+        seed.deserialize(value)
+    }
+}
+
+/// Compound keys are always strings, but maps keyed by integers were written with their keys as text.
+struct KeyDeserializer(String);
+
+macro_rules! parse_key {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                match self.0.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(TagSerdeError::custom(format!("Expected an integer key, got {}", self.0))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer {
+    type Error = TagSerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    parse_key!(
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64, deserialize_u8 => visit_u8, deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32, deserialize_u64 => visit_u64
+    );
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// An enum variant, stored as a compound holding its data under the variant's name.
+struct VariantTag {
+    name: String,
+    value: Tag,
+}
+
+impl<'de> EnumAccess<'de> for VariantTag {
+    type Error = TagSerdeError;
+    type Variant = Tag;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Tag)> {
+        let variant = seed.deserialize(self.name.into_deserializer())?;
+
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Tag {
+    type Error = TagSerdeError;
+
+    fn unit_variant(self) -> Result<()> {
+        self.deserialize_unit(serde::de::IgnoredAny).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+}
@@ -0,0 +1,423 @@
+pub mod snbt;
+pub mod ser;
+pub mod de;
+
+use std::{ io::{ Write, BufWriter }, collections::BTreeMap };
+
+use crate::{
+    cbs::{ Packetable, DynamicSizePacketable, PacketBuf, WriteExt },
+    error::tag::TagError,
+};
+
+pub use self::{ ser::to_tag, de::from_tag };
+
+/// A value in a tree of named values, used wherever data has to be saved or sent without a fixed layout.
+/// Every tag is written with its type in front, so it can be read back without knowing what to expect.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    List(Vec<Tag>), //All elements have the same type
+    Compound(Compound),
+    ByteArray(Vec<i8>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// Ends a compound in the binary format, and is the element type of empty lists.
+    const END: u8 = 0;
+
+    /// How deeply lists and compounds may be nested when reading, so broken data can't overflow the stack.
+    pub const MAX_DEPTH: usize = 512;
+
+    pub fn type_id(&self) -> u8 {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) => 3,
+            Self::Long(_) => 4,
+            Self::Float(_) => 5,
+            Self::Double(_) => 6,
+            Self::String(_) => 7,
+            Self::List(_) => 8,
+            Self::Compound(_) => 9,
+            Self::ByteArray(_) => 10,
+            Self::IntArray(_) => 11,
+            Self::LongArray(_) => 12,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Byte(_) => "byte",
+            Self::Short(_) => "short",
+            Self::Int(_) => "int",
+            Self::Long(_) => "long",
+            Self::Float(_) => "float",
+            Self::Double(_) => "double",
+            Self::String(_) => "string",
+            Self::List(_) => "list",
+            Self::Compound(_) => "compound",
+            Self::ByteArray(_) => "byte array",
+            Self::IntArray(_) => "int array",
+            Self::LongArray(_) => "long array",
+        }
+    }
+
+    /// Checks that every element of a list has the same type. Lists built by hand should go through this.
+    pub fn list(elements: Vec<Tag>) -> anyhow::Result<Self> {
+        Self::check_list(&elements)?;
+
+        Ok(Self::List(elements))
+    }
+
+    fn check_list(elements: &[Tag]) -> anyhow::Result<()> {
+        if let Some(first) = elements.first() {
+            if let Some(other) = elements.iter().find(|tag| tag.type_id() != first.type_id()) {
+                return Err(TagError::MixedList(first.type_name(), other.type_name()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_payload<T: Write>(&self, buffer: &mut T) -> anyhow::Result<()> {
+        match self {
+            Self::Byte(value) => {
+                buffer.write_u8(*value as u8)?;
+            }
+            Self::Short(value) => {
+                buffer.write_u16(*value as u16)?;
+            }
+            Self::Int(value) => {
+                buffer.write_u32(*value as u32)?;
+            }
+            Self::Long(value) => {
+                buffer.write_u64(*value as u64)?;
+            }
+            Self::Float(value) => {
+                buffer.write_u32(value.to_bits())?;
+            }
+            Self::Double(value) => {
+                buffer.write_u64(value.to_bits())?;
+            }
+            Self::String(value) => {
+                buffer.write_string(value)?;
+            }
+            Self::List(elements) => {
+                //The variant is public, so a list with mixed elements can still be built without `Tag::list`
+                Self::check_list(elements)?;
+
+                buffer.write_u8(elements.first().map_or(Self::END, Tag::type_id))?;
+                buffer.write_u32(elements.len() as u32)?;
+
+                for element in elements {
+                    element.write_payload(buffer)?;
+                }
+            }
+            Self::Compound(compound) => compound.write_payload(buffer)?,
+            Self::ByteArray(values) => {
+                buffer.write_u32(values.len() as u32)?;
+                buffer.write_all(&values.iter().map(|value| *value as u8).collect::<Vec<_>>())?;
+            }
+            Self::IntArray(values) => {
+                buffer.write_u32(values.len() as u32)?;
+
+                for value in values {
+                    buffer.write_u32(*value as u32)?;
+                }
+            }
+            Self::LongArray(values) => {
+                buffer.write_u32(values.len() as u32)?;
+
+                for value in values {
+                    buffer.write_u64(*value as u64)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_payload(type_id: u8, reader: &mut PacketBuf, depth: usize) -> anyhow::Result<Self> {
+        if depth > Self::MAX_DEPTH {
+            return Err(TagError::TooDeep(Self::MAX_DEPTH).into());
+        }
+
+        Ok(match type_id {
+            1 => Self::Byte(reader.next_byte()? as i8),
+            2 => Self::Short(reader.next_u16()? as i16),
+            3 => Self::Int(reader.next_u32()? as i32),
+            4 => Self::Long(reader.next_u64()? as i64),
+            5 => Self::Float(f32::from_bits(reader.next_u32()?)),
+            6 => Self::Double(f64::from_bits(reader.next_u64()?)),
+            7 => Self::String(reader.next_string()?),
+            8 => {
+                let element_type = reader.next_byte()?;
+                let len = reader.next_u32()?;
+
+                if element_type == Self::END {
+                    Self::List(Vec::new())
+                } else {
+                    Self::List(
+                        (0..len)
+                            .map(|_| Self::read_payload(element_type, reader, depth + 1))
+                            .collect::<anyhow::Result<_>>()?
+                    )
+                }
+            }
+            9 => Self::Compound(Compound::read_payload(reader, depth + 1)?),
+            10 => {
+                let len = reader.next_u32()? as usize;
+                Self::ByteArray(reader.next_n_bytes(len)?.iter().map(|byte| *byte as i8).collect())
+            }
+            11 => {
+                let len = reader.next_u32()?;
+                Self::IntArray((0..len).map(|_| Ok(reader.next_u32()? as i32)).collect::<anyhow::Result<_>>()?)
+            }
+            12 => {
+                let len = reader.next_u32()?;
+                Self::LongArray((0..len).map(|_| Ok(reader.next_u64()? as i64)).collect::<anyhow::Result<_>>()?)
+            }
+            _ => {
+                return Err(TagError::UnknownType(type_id).into());
+            }
+        })
+    }
+
+    fn payload_size(&self) -> usize {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) | Self::Float(_) => 4,
+            Self::Long(_) | Self::Double(_) => 8,
+            Self::String(value) => 2 + value.len(),
+            Self::List(elements) => 5 + elements.iter().map(Tag::payload_size).sum::<usize>(),
+            Self::Compound(compound) => compound.payload_size(),
+            Self::ByteArray(values) => 4 + values.len(),
+            Self::IntArray(values) => 4 + values.len() * 4,
+            Self::LongArray(values) => 4 + values.len() * 8,
+        }
+    }
+}
+
+impl From<Compound> for Tag {
+    fn from(value: Compound) -> Self {
+        Self::Compound(value)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+macro_rules! tag_from {
+    ($($type:ty => $variant:ident),*) => {
+        $(
+            impl From<$type> for Tag {
+                fn from(value: $type) -> Self {
+                    Self::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+tag_from!(
+    i8 => Byte, i16 => Short, i32 => Int, i64 => Long, f32 => Float, f64 => Double, String => String,
+    Vec<i8> => ByteArray, Vec<i32> => IntArray, Vec<i64> => LongArray
+);
+
+/// Named tags, sorted by name. This is what sits at the root of saved and sent data; anything that can change its
+/// layout over time should store a version in it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound {
+    tags: BTreeMap<String, Tag>,
+}
+
+macro_rules! typed_getter {
+    ($name:ident, $variant:ident, $type:ty, $kind:literal) => {
+        pub fn $name(&self, key: &str) -> anyhow::Result<$type> {
+            match self.get(key) {
+                Some(Tag::$variant(value)) => Ok(value.clone()),
+                _ => Err(TagError::MissingValue(key.to_string(), $kind).into()),
+            }
+        }
+    };
+}
+
+impl Compound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &str, tag: impl Into<Tag>) -> Option<Tag> {
+        self.tags.insert(key.to_string(), tag.into())
+    }
+
+    pub fn with(mut self, key: &str, tag: impl Into<Tag>) -> Self {
+        self.insert(key, tag);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        self.tags.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Tag> {
+        self.tags.get_mut(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Tag> {
+        self.tags.remove(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.tags.contains_key(key)
+    }
+
+    typed_getter!(get_byte, Byte, i8, "byte");
+    typed_getter!(get_short, Short, i16, "short");
+    typed_getter!(get_int, Int, i32, "int");
+    typed_getter!(get_long, Long, i64, "long");
+    typed_getter!(get_float, Float, f32, "float");
+    typed_getter!(get_double, Double, f64, "double");
+
+    /// Bools are stored as bytes, like serde does it.
+    pub fn get_bool(&self, key: &str) -> anyhow::Result<bool> {
+        match self.get(key) {
+            Some(Tag::Byte(value)) => Ok(*value != 0),
+            _ => Err(TagError::MissingValue(key.to_string(), "byte").into()),
+        }
+    }
+
+    pub fn get_string(&self, key: &str) -> anyhow::Result<&str> {
+        match self.get(key) {
+            Some(Tag::String(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "string").into()),
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> anyhow::Result<&[Tag]> {
+        match self.get(key) {
+            Some(Tag::List(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "list").into()),
+        }
+    }
+
+    pub fn get_compound(&self, key: &str) -> anyhow::Result<&Compound> {
+        match self.get(key) {
+            Some(Tag::Compound(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "compound").into()),
+        }
+    }
+
+    pub fn get_byte_array(&self, key: &str) -> anyhow::Result<&[i8]> {
+        match self.get(key) {
+            Some(Tag::ByteArray(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "byte array").into()),
+        }
+    }
+
+    pub fn get_int_array(&self, key: &str) -> anyhow::Result<&[i32]> {
+        match self.get(key) {
+            Some(Tag::IntArray(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "int array").into()),
+        }
+    }
+
+    pub fn get_long_array(&self, key: &str) -> anyhow::Result<&[i64]> {
+        match self.get(key) {
+            Some(Tag::LongArray(value)) => Ok(value),
+            _ => Err(TagError::MissingValue(key.to_string(), "long array").into()),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Tag)> {
+        self.tags.iter().map(|(key, tag)| (key.as_str(), tag))
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    fn write_payload<T: Write>(&self, buffer: &mut T) -> anyhow::Result<()> {
+        for (key, tag) in &self.tags {
+            buffer.write_u8(tag.type_id())?;
+            buffer.write_string(key)?;
+            tag.write_payload(buffer)?;
+        }
+
+        buffer.write_u8(Tag::END)?;
+
+        Ok(())
+    }
+
+    fn read_payload(reader: &mut PacketBuf, depth: usize) -> anyhow::Result<Self> {
+        let mut tags = BTreeMap::new();
+
+        loop {
+            let type_id = reader.next_byte()?;
+
+            if type_id == Tag::END {
+                return Ok(Self { tags });
+            }
+
+            let key = reader.next_string()?;
+            tags.insert(key, Tag::read_payload(type_id, reader, depth)?);
+        }
+    }
+
+    fn payload_size(&self) -> usize {
+        1 + self.tags
+            .iter()
+            .map(|(key, tag)| 3 + key.len() + tag.payload_size())
+            .sum::<usize>()
+    }
+}
+
+impl FromIterator<(String, Tag)> for Compound {
+    fn from_iter<T: IntoIterator<Item = (String, Tag)>>(iter: T) -> Self {
+        Self { tags: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for Compound {
+    type Item = (String, Tag);
+    type IntoIter = std::collections::btree_map::IntoIter<String, Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tags.into_iter()
+    }
+}
+
+impl Packetable for Compound {
+    fn write_to_buffer<T: Write + Unpin + Send>(
+        self,
+        buffer: &mut BufWriter<T>
+    ) -> anyhow::Result<()> {
+        self.write_payload(buffer)
+    }
+
+    fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> {
+        Self::read_payload(reader, 0)
+    }
+}
+
+impl DynamicSizePacketable for Compound {
+    fn size_in_bytes(&self) -> usize {
+        self.payload_size()
+    }
+}
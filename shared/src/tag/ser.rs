@@ -0,0 +1,340 @@
+use serde::ser::{
+    Serialize,
+    Serializer,
+    SerializeSeq,
+    SerializeTuple,
+    SerializeTupleStruct,
+    SerializeTupleVariant,
+    SerializeMap,
+    SerializeStruct,
+    SerializeStructVariant,
+    Error,
+};
+
+use crate::error::tag::TagSerdeError;
+
+use super::{ Tag, Compound };
+
+/// Turns anything serde can serialize into a tag. Structs and maps become compounds, sequences lists, and enum
+/// variants a compound holding their data under the variant's name, an empty compound for variants without data.
+/// `None` fields are left out, and unsigned integers are stored in the next bigger signed tag.
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<Tag> {
+    value
+        .serialize(TagSerializer)?
+        .ok_or_else(|| TagSerdeError("A missing value can't be turned into a tag".to_string()).into())
+}
+
+/// Serializes to `None` for missing values, so they can be left out of the compound they are in.
+struct TagSerializer;
+
+type Result<T> = std::result::Result<T, TagSerdeError>;
+
+fn some(tag: impl Into<Tag>) -> Result<Option<Tag>> {
+    Ok(Some(tag.into()))
+}
+
+fn variant(name: &str, tag: Tag) -> Option<Tag> {
+    Some(Tag::Compound(Compound::new().with(name, tag)))
+}
+
+impl Serializer for TagSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = CompoundSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        some(v as i8)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        some(v as i16)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        some(v as i32)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        some(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        match i64::try_from(v) {
+            Ok(v) => some(v),
+            Err(_) => Err(TagSerdeError::custom(format!("{} is too big for a long tag", v))),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        some(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        some(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        some(v.iter().map(|byte| *byte as i8).collect::<Vec<_>>())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        some(Compound::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    //Not a bare string, so variants with and without data can be in the same list
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, name: &'static str) -> Result<Self::Ok> {
+        Ok(variant(name, Tag::Compound(Compound::new())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        value: &T
+    ) -> Result<Self::Ok> {
+        Ok(value.serialize(self)?.and_then(|tag| variant(name, tag)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(ListSerializer { elements: Vec::with_capacity(len.unwrap_or_default()), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        len: usize
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(ListSerializer { elements: Vec::with_capacity(len), variant: Some(name) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(CompoundSerializer { compound: Compound::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        name: &'static str,
+        _len: usize
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(CompoundSerializer { compound: Compound::new(), key: None, variant: Some(name) })
+    }
+}
+
+struct ListSerializer {
+    elements: Vec<Tag>,
+    variant: Option<&'static str>,
+}
+
+impl ListSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let tag = value
+            .serialize(TagSerializer)?
+            .ok_or_else(|| TagSerdeError::custom("Lists can't hold missing values"))?;
+        self.elements.push(tag);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Tag>> {
+        let list = Tag::list(self.elements).map_err(TagSerdeError::custom)?;
+
+        Ok(match self.variant {
+            Some(name) => variant(name, list),
+            None => Some(list),
+        })
+    }
+}
+
+impl SerializeSeq for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl SerializeTuple for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleStruct for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl SerializeTupleVariant for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+struct CompoundSerializer {
+    compound: Compound,
+    key: Option<String>, //Set between `serialize_key` and `serialize_value`
+    variant: Option<&'static str>,
+}
+
+impl CompoundSerializer {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<()> {
+        if let Some(tag) = value.serialize(TagSerializer)? {
+            self.compound.insert(key, tag);
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Tag>> {
+        Ok(match self.variant {
+            Some(name) => variant(name, Tag::Compound(self.compound)),
+            None => Some(Tag::Compound(self.compound)),
+        })
+    }
+}
+
+impl SerializeMap for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        //Compounds are keyed by strings, so anything that doesn't turn into a plain value is out
+        self.key = Some(match key.serialize(TagSerializer)? {
+            Some(Tag::String(key)) => key,
+            Some(Tag::Byte(key)) => key.to_string(),
+            Some(Tag::Short(key)) => key.to_string(),
+            Some(Tag::Int(key)) => key.to_string(),
+            Some(Tag::Long(key)) => key.to_string(),
+            _ => {
+                return Err(TagSerdeError::custom("Compound keys have to be strings or integers"));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().ok_or_else(|| TagSerdeError::custom("Value without a key"))?;
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl SerializeStruct for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
+
+impl SerializeStructVariant for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = TagSerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        self.finish()
+    }
+}
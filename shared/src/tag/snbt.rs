@@ -0,0 +1,311 @@
+//! The text form of tags, for logs and debugging, e.g. `{name:"Steve",pos:[1.5d,64.0d,-3.0d],flags:[B;1b,0b]}`.
+//! Numbers carry a suffix for their type, except ints and doubles; bare words are strings.
+
+use std::fmt::{ Display, Formatter, Write };
+
+use crate::error::tag::TagError;
+
+use super::{ Tag, Compound };
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Byte(value) => write!(f, "{}b", value),
+            Self::Short(value) => write!(f, "{}s", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Long(value) => write!(f, "{}L", value),
+            Self::Float(value) => write!(f, "{:?}f", value),
+            Self::Double(value) => write!(f, "{:?}d", value),
+            Self::String(value) => write_quoted(f, value),
+            Self::List(elements) => write_list(f, "", elements.iter()),
+            Self::Compound(compound) => compound.fmt(f),
+            Self::ByteArray(values) => write_list(f, "B;", values.iter().map(|value| Tag::Byte(*value))),
+            Self::IntArray(values) => write_list(f, "I;", values.iter().map(|value| Tag::Int(*value))),
+            Self::LongArray(values) => write_list(f, "L;", values.iter().map(|value| Tag::Long(*value))),
+        }
+    }
+}
+
+impl Display for Compound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_char('{')?;
+
+        for (index, (key, tag)) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_char(',')?;
+            }
+
+            if !key.is_empty() && key.chars().all(is_bare_char) {
+                f.write_str(key)?;
+            } else {
+                write_quoted(f, key)?;
+            }
+
+            write!(f, ":{}", tag)?;
+        }
+
+        f.write_char('}')
+    }
+}
+
+fn write_list<T: Display>(f: &mut Formatter<'_>, prefix: &str, elements: impl Iterator<Item = T>) -> std::fmt::Result {
+    write!(f, "[{}", prefix)?;
+
+    for (index, element) in elements.enumerate() {
+        if index > 0 {
+            f.write_char(',')?;
+        }
+
+        write!(f, "{}", element)?;
+    }
+
+    f.write_char(']')
+}
+
+fn write_quoted(f: &mut Formatter<'_>, text: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            f.write_char('\\')?;
+        }
+
+        f.write_char(c)?;
+    }
+
+    f.write_char('"')
+}
+
+fn is_bare_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// Reads a single tag written by `Display`, e.g. in a console command.
+pub fn parse(text: &str) -> anyhow::Result<Tag> {
+    let mut parser = Parser { chars: text.chars().collect(), index: 0 };
+
+    let tag = parser.tag(0)?;
+    parser.skip_whitespace();
+
+    if parser.index < parser.chars.len() {
+        return Err(parser.error("Unexpected text after the tag"));
+    }
+
+    Ok(tag)
+}
+
+/// Like `parse`, but the text has to be a compound.
+pub fn parse_compound(text: &str) -> anyhow::Result<Compound> {
+    match parse(text)? {
+        Tag::Compound(compound) => Ok(compound),
+        other => Err(TagError::Snbt(format!("Expected a compound, got a {}", other.type_name()), 0).into()),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> anyhow::Error {
+        TagError::Snbt(message.to_string(), self.index).into()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.index).is_some_and(|c| c.is_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.index).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> anyhow::Result<()> {
+        if self.peek() == Some(expected) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", expected)))
+        }
+    }
+
+    /// Consumes `c` if it comes next.
+    fn accept(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+
+        if found {
+            self.index += 1;
+        }
+
+        found
+    }
+
+    fn tag(&mut self, depth: usize) -> anyhow::Result<Tag> {
+        if depth > Tag::MAX_DEPTH {
+            return Err(TagError::TooDeep(Tag::MAX_DEPTH).into());
+        }
+
+        match self.peek() {
+            Some('{') => Ok(Tag::Compound(self.compound(depth)?)),
+            Some('[') => self.list(depth),
+            Some('"') | Some('\'') => Ok(Tag::String(self.quoted()?)),
+            Some(_) => {
+                let word = self.word()?;
+                Ok(Self::bare_value(&word))
+            }
+            None => Err(self.error("Expected a tag")),
+        }
+    }
+
+    fn compound(&mut self, depth: usize) -> anyhow::Result<Compound> {
+        self.expect('{')?;
+        let mut compound = Compound::new();
+
+        if self.accept('}') {
+            return Ok(compound);
+        }
+
+        loop {
+            let key = match self.peek() {
+                Some('"') | Some('\'') => self.quoted()?,
+                _ => self.word()?,
+            };
+
+            self.expect(':')?;
+            compound.insert(&key, self.tag(depth + 1)?);
+
+            if !self.accept(',') {
+                self.expect('}')?;
+                return Ok(compound);
+            }
+        }
+    }
+
+    fn list(&mut self, depth: usize) -> anyhow::Result<Tag> {
+        self.expect('[')?;
+
+        //Arrays start with their type, e.g. [I;1,2,3]
+        let array_type = match (self.chars.get(self.index), self.chars.get(self.index + 1)) {
+            (Some(c @ ('B' | 'I' | 'L')), Some(';')) => {
+                self.index += 2;
+                Some(*c)
+            }
+            _ => None,
+        };
+
+        let mut elements = Vec::new();
+
+        if !self.accept(']') {
+            loop {
+                elements.push(self.tag(depth + 1)?);
+
+                if !self.accept(',') {
+                    self.expect(']')?;
+                    break;
+                }
+            }
+        }
+
+        match array_type {
+            None => Tag::list(elements).map_err(|e| self.error(&e.to_string())),
+            Some(c) => {
+                let mismatch = || self.error(&format!("Array of type {} holds a different tag", c));
+
+                Ok(match c {
+                    'B' =>
+                        Tag::ByteArray(
+                            elements
+                                .into_iter()
+                                .map(|tag| if let Tag::Byte(value) = tag { Ok(value) } else { Err(mismatch()) })
+                                .collect::<anyhow::Result<_>>()?
+                        ),
+                    'I' =>
+                        Tag::IntArray(
+                            elements
+                                .into_iter()
+                                .map(|tag| if let Tag::Int(value) = tag { Ok(value) } else { Err(mismatch()) })
+                                .collect::<anyhow::Result<_>>()?
+                        ),
+                    _ =>
+                        Tag::LongArray(
+                            elements
+                                .into_iter()
+                                .map(|tag| if let Tag::Long(value) = tag { Ok(value) } else { Err(mismatch()) })
+                                .collect::<anyhow::Result<_>>()?
+                        ),
+                })
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> anyhow::Result<String> {
+        let quote = self.chars[self.index];
+        self.index += 1;
+        let mut text = String::new();
+
+        loop {
+            match self.chars.get(self.index) {
+                Some('\\') => {
+                    let escaped = self.chars.get(self.index + 1).ok_or_else(|| self.error("Unfinished escape"))?;
+                    text.push(*escaped);
+                    self.index += 2;
+                }
+                Some(c) if *c == quote => {
+                    self.index += 1;
+                    return Ok(text);
+                }
+                Some(c) => {
+                    text.push(*c);
+                    self.index += 1;
+                }
+                None => {
+                    return Err(self.error("Unterminated string"));
+                }
+            }
+        }
+    }
+
+    fn word(&mut self) -> anyhow::Result<String> {
+        self.skip_whitespace();
+        let start = self.index;
+
+        while self.chars.get(self.index).is_some_and(|c| is_bare_char(*c)) {
+            self.index += 1;
+        }
+
+        if start == self.index {
+            return Err(self.error("Expected a value"));
+        }
+
+        Ok(self.chars[start..self.index].iter().collect())
+    }
+
+    /// A number if the word looks like one, a string otherwise.
+    fn bare_value(word: &str) -> Tag {
+        let (number, suffix) = match word.char_indices().last() {
+            Some((index, c)) if c.is_ascii_alphabetic() => (&word[..index], Some(c.to_ascii_lowercase())),
+            _ => (word, None),
+        };
+
+        let parsed = match suffix {
+            Some('b') => number.parse().ok().map(Tag::Byte),
+            Some('s') => number.parse().ok().map(Tag::Short),
+            Some('l') => number.parse().ok().map(Tag::Long),
+            Some('f') => number.parse().ok().map(Tag::Float),
+            Some('d') => number.parse().ok().map(Tag::Double),
+            None if number.contains(['.', 'e', 'E']) => number.parse().ok().map(Tag::Double),
+            None => number.parse().ok().map(Tag::Int),
+            _ => None,
+        };
+
+        match (parsed, word) {
+            (Some(tag), _) => tag,
+            (None, "true") => Tag::Byte(1),
+            (None, "false") => Tag::Byte(0),
+            (None, _) => Tag::String(word.to_string()),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use glam::Vec3;
+
+use super::direction::Direction;
+
+/// An axis aligned box, from `min` to `max`. Touching boxes don't count as intersecting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// The box filling a whole block at the origin.
+    pub const UNIT: Self = Self { min: Vec3::ZERO, max: Vec3::ONE };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min: min.min(max), max: min.max(max) }
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn offset(&self, by: Vec3) -> Self {
+        Self { min: self.min + by, max: self.max + by }
+    }
+
+    /// Grows the box by `amount` in every direction.
+    pub fn inflate(&self, amount: Vec3) -> Self {
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    /// Grows the box so it covers everything it passes through when moved by `movement`.
+    pub fn expand_towards(&self, movement: Vec3) -> Self {
+        Self {
+            min: self.min + movement.min(Vec3::ZERO),
+            max: self.max + movement.max(Vec3::ZERO),
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmplt(other.max).all() && self.max.cmpgt(other.min).all()
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.min.cmple(point).all() && self.max.cmpgt(point).all()
+    }
+
+    /// Where a ray from `origin` along `dir` enters the box, as the multiple of `dir` it takes to get there, and the
+    /// face it enters through. Rays starting inside the box or only grazing its edges don't hit it.
+    pub fn clip(&self, origin: Vec3, dir: Vec3) -> Option<(f32, Direction)> {
+        let (mut enter, mut exit, mut face) = (f32::NEG_INFINITY, f32::INFINITY, None);
+
+        let axes = [
+            (0, Direction::West, Direction::East),
+            (1, Direction::Down, Direction::Up),
+            (2, Direction::North, Direction::South),
+        ];
+
+        for (axis, negative, positive) in axes {
+            let (start, delta, min, max) = (origin[axis], dir[axis], self.min[axis], self.max[axis]);
+
+            if delta == 0.0 {
+                if start <= min || start >= max {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let (to_min, to_max) = ((min - start) / delta, (max - start) / delta);
+            let (near, far, side) = if delta > 0.0 { (to_min, to_max, negative) } else { (to_max, to_min, positive) };
+
+            if near > enter {
+                enter = near;
+                face = Some(side);
+            }
+
+            exit = exit.min(far);
+        }
+
+        if enter >= exit || enter < 0.0 {
+            return None;
+        }
+
+        face.map(|face| (enter, face))
+    }
+}
@@ -8,14 +8,15 @@ use anyhow::{ ensure };
 use crate::{
     cbs::{Packetable, PacketBuf, FixedSizePacketable, WriteExt},
     error::util::{ InvalidPositionError },
+    dimension::height::WorldHeight,
 };
 
-use super::chunk_pos::ChunkPos;
+use super::{ chunk_pos::ChunkPos, direction::Direction };
 
 
 
 
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct BlockPos {
     // A Block position serializable to a 64-bit large space
     x: i32,
@@ -80,9 +81,10 @@ impl BlockPos {
         let y = long << (64 - Self::Y_SHIFT - Self::Y_BITS);
         let z = long << (64 - Self::Z_SHIFT - Self::Z_BITS);
 
-        let x = x >> (64 - Self::X_BITS);
-        let y = y >> (64 - Self::Y_BITS);
-        let z = z >> (64 - Self::Z_BITS);
+        //Shift back as signed integers so negative coordinates get sign-extended
+        let x = (x as i64) >> (64 - Self::X_BITS);
+        let y = (y as i64) >> (64 - Self::Y_BITS);
+        let z = (z as i64) >> (64 - Self::Z_BITS);
 
         Self {
             x: x as i32,
@@ -91,9 +93,12 @@ impl BlockPos {
         }
     }
 
-    pub fn validate(&self) -> anyhow::Result<()> {
+    pub fn validate(&self, height: &WorldHeight) -> anyhow::Result<()> {
         ensure!(Self::VALID_X.contains(&self.x), InvalidPositionError::InvalidX(self.x));
-        ensure!(Self::VALID_Y.contains(&self.y), InvalidPositionError::InvalidY(self.y));
+        ensure!(
+            height.contains_y(self.y),
+            InvalidPositionError::InvalidY(self.y, height.y_range())
+        );
         ensure!(Self::VALID_Z.contains(&self.z), InvalidPositionError::InvalidZ(self.z));
 
         Ok(())
@@ -141,9 +146,23 @@ impl BlockPos {
         }
     }
 
+    pub fn offset(&self, direction: Direction) -> Self {
+        let offset = direction.offset();
+
+        Self {
+            x: self.x + offset.x,
+            y: self.y + offset.y,
+            z: self.z + offset.z,
+        }
+    }
+
     pub fn get_chunk(&self) -> ChunkPos {
         ChunkPos::new(self.x >> 4, self.z >> 4)
     }
+
+    pub fn get_section(&self) -> i32 {
+        WorldHeight::section_of(self.y)
+    }
 }
 
 impl From<BlockPos> for IVec3 {
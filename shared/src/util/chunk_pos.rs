@@ -9,7 +9,11 @@ pub struct ChunkPos {
 impl ChunkPos {
     pub fn as_long(&self) -> u64 {
         //mostly used as a hash map key
-        ((self.x as u64) << 32) | (self.z as u64)
+        ((self.x as u64) << 32) | (self.z as u32 as u64)
+    }
+
+    pub fn from_long(long: u64) -> Self {
+        Self::new((long >> 32) as i32, long as u32 as i32)
     }
 
     pub fn new(x: i32, z: i32) -> Self {
@@ -32,15 +36,16 @@ impl Packetable for ChunkPos {
         self,
         buffer: &mut std::io::BufWriter<T>
     ) -> anyhow::Result<()> {
-        buffer.first_n_bytes_u32::<24>(self.x() as u32)?;
-        buffer.first_n_bytes_u32::<24>(self.z() as u32)?;
+        buffer.first_n_bytes_u32::<3>(self.x() as u32)?;
+        buffer.first_n_bytes_u32::<3>(self.z() as u32)?;
 
         Ok(())
     }
 
     fn read_from_buf(reader: &mut PacketBuf) -> anyhow::Result<Self> where Self: Sized {
-        let x = reader.next_n_bytes_as_u32::<24>()? as i32;
-        let z = reader.next_n_bytes_as_u32::<24>()? as i32;
+        //Shift the 24 bits to the top and back down again to restore the sign
+        let x = ((reader.next_n_bytes_as_u32::<3>()? << 8) as i32) >> 8;
+        let z = ((reader.next_n_bytes_as_u32::<3>()? << 8) as i32) >> 8;
 
         Ok(Self::new(x, z))
     }
@@ -1,25 +1,42 @@
 use glam::{i32::IVec3, Vec3};
 
+use crate::block::property::EnumProperty;
+
 #[repr(i8)]
 pub enum AxisDirection {
     Positive = 1,
     Negative = -1,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum Axis {
     X,
+    #[default]
     Y,
     Z,
 }
 
+impl EnumProperty for Axis {
+    const VALUES: &'static [Self] = &[Self::X, Self::Y, Self::Z];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::X => "x",
+            Self::Y => "y",
+            Self::Z => "z",
+        }
+    }
+}
+
 pub enum Plane {
     Horizontal,
     Vertical,
 }
 
 #[repr(u8)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum Direction {
+    #[default]
     North,
     South,
     East,
@@ -28,6 +45,21 @@ pub enum Direction {
     Down,
 }
 
+impl EnumProperty for Direction {
+    const VALUES: &'static [Self] = &Self::LOOKUP;
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::North => "north",
+            Self::South => "south",
+            Self::East => "east",
+            Self::West => "west",
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+}
+
 impl Direction {
     pub const HORIZONTALS: [Self; 4] = [Self::South, Self::East, Self::North, Self::West];
 
@@ -51,6 +83,22 @@ impl Direction {
         }
     }
 
+    /// The next horizontal direction clockwise, seen from above. Up and down stay the same.
+    pub fn clockwise(&self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+            Self::Up => Self::Up,
+            Self::Down => Self::Down,
+        }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        !matches!(self, Self::Up | Self::Down)
+    }
+
     pub fn axis(&self) -> Axis {
         match self {
             Self::North | Self::South => Axis::Z,
@@ -71,9 +119,9 @@ impl Direction {
             Self::North => IVec3::NEG_Z,
             Self::South => IVec3::Z,
             Self::East => IVec3::X,
-            Self::West => -IVec3::NEG_X,
+            Self::West => IVec3::NEG_X,
             Self::Up => IVec3::Y,
-            Self::Down => -IVec3::NEG_Y,
+            Self::Down => IVec3::NEG_Y,
         }
     }
 
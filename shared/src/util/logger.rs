@@ -34,11 +34,7 @@ impl Policy for OnStartupPolicy {
                     Ok(data) =>
                         match data.created() {
                             Ok(created) => Some(created),
-                            Err(_) =>
-                                match data.accessed() {
-                                    Ok(accessed) => Some(accessed),
-                                    Err(_) => None,
-                                }
+                            Err(_) => data.accessed().ok(),
                         }
                     Err(_) => None,
                 }
@@ -5,6 +5,7 @@ pub mod direction;
 
 pub mod logger;
 pub mod chunk_pos;
+pub mod aabb;
 
 pub trait Boxable {
     fn boxed(self) -> Box<Self>;